@@ -0,0 +1,97 @@
+/* accent.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::prelude::*;
+use gtk::{gdk, gio, glib};
+
+const INTERFACE_SCHEMA: &str = "org.gnome.desktop.interface";
+
+/// The accent colors GNOME offers, as libadwaita draws them.
+const GNOME_ACCENTS: &[(&str, &str)] = &[
+    ("blue", "#3584e4"),
+    ("teal", "#2190a4"),
+    ("green", "#3a944a"),
+    ("yellow", "#c88800"),
+    ("orange", "#ed5b00"),
+    ("red", "#e62d42"),
+    ("pink", "#d56199"),
+    ("purple", "#9141ac"),
+    ("slate", "#6f8396"),
+];
+
+fn not_found() -> glib::Error {
+    glib::Error::new(gio::IOErrorEnum::NotFound, "No accent color is set")
+}
+
+/// Reads the accent color through the settings portal, which reports it as
+/// an RGB triple, out of range when unset.
+async fn portal_accent() -> Result<gdk::RGBA, glib::Error> {
+    let connection = gio::bus_get_future(gio::BusType::Session).await?;
+    let reply = connection
+        .call_future(
+            Some("org.freedesktop.portal.Desktop"),
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.portal.Settings",
+            "ReadOne",
+            Some(&("org.freedesktop.appearance", "accent-color").to_variant()),
+            None,
+            gio::DBusCallFlags::NONE,
+            -1,
+        )
+        .await?;
+
+    let (red, green, blue) = reply
+        .child_value(0)
+        .as_variant()
+        .and_then(|value| value.get::<(f64, f64, f64)>())
+        .ok_or_else(not_found)?;
+
+    if ![red, green, blue]
+        .iter()
+        .all(|channel| (0.0..=1.0).contains(channel))
+    {
+        return Err(not_found());
+    }
+
+    Ok(gdk::RGBA::new(red as f32, green as f32, blue as f32, 1.0))
+}
+
+/// Reads GNOME's named accent color directly, for hosts whose portal
+/// doesn't know about accents yet.
+fn settings_accent() -> Option<gdk::RGBA> {
+    let schema = gio::SettingsSchemaSource::default()?.lookup(INTERFACE_SCHEMA, true)?;
+    if !schema.has_key("accent-color") {
+        return None;
+    }
+
+    let name = gio::Settings::new(INTERFACE_SCHEMA).string("accent-color");
+    GNOME_ACCENTS
+        .iter()
+        .find(|(accent, _)| *accent == name.as_str())
+        .and_then(|(_, hex)| gdk::RGBA::parse(*hex).ok())
+}
+
+/// The desktop's accent color.
+pub async fn accent_color() -> Result<gdk::RGBA, glib::Error> {
+    match portal_accent().await {
+        Ok(rgba) => Ok(rgba),
+        Err(err) => settings_accent().ok_or(err),
+    }
+}
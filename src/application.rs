@@ -18,20 +18,28 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 
+use std::cell::Cell;
+
 use gettextrs::gettext;
+use glib::clone;
 use gtk::prelude::*;
 use gtk::{gio, glib};
 
 use adw::subclass::prelude::*;
 
+use crate::autosave;
 use crate::config::VERSION;
+use crate::library;
 use crate::VibrantWindow;
 
 mod imp {
     use super::*;
 
     #[derive(Debug, Default)]
-    pub struct VibrantApplication {}
+    pub struct VibrantApplication {
+        /// Whether gradients autosaved before a crash wait to be restored.
+        pub recovery: Cell<bool>,
+    }
 
     #[glib::object_subclass]
     impl ObjectSubclass for VibrantApplication {
@@ -46,11 +54,47 @@ mod imp {
             let obj = self.obj();
             obj.setup_gactions();
             obj.set_accels_for_action("app.quit", &["<primary>q"]);
-            obj.set_accels_for_action("window.close", &["<primary>w"]);
+            obj.set_accels_for_action("app.new-window", &["<primary>n"]);
+            obj.set_accels_for_action("win.preferences", &["<primary>comma"]);
+            obj.set_accels_for_action("win.new-tab", &["<primary>t"]);
+            obj.set_accels_for_action("win.close-tab", &["<primary>w"]);
+            obj.set_accels_for_action("window.close", &["<primary><shift>w"]);
+            obj.set_accels_for_action("win.open", &["<primary>o"]);
+            obj.set_accels_for_action("win.save", &["<primary>s"]);
+            obj.set_accels_for_action("win.save-as", &["<primary><shift>s"]);
+            obj.set_accels_for_action("win.undo", &["<primary>z"]);
+            obj.set_accels_for_action("win.redo", &["<primary><shift>z"]);
+            obj.set_accels_for_action("win.swap-stops", &["<primary>i"]);
+            obj.set_accels_for_action("win.shuffle-stops", &["<primary><shift>i"]);
         }
     }
 
     impl ApplicationImpl for VibrantApplication {
+        fn startup(&self) {
+            self.parent_startup();
+
+            self.recovery.set(autosave::take_recovery());
+            if let Err(err) = library::purge(false) {
+                glib::g_warning!("vibrant", "Could not purge the trash: {}", err);
+            }
+
+            let obj = self.obj();
+            glib::timeout_add_seconds_local(
+                autosave::INTERVAL,
+                clone!(@weak obj => @default-return glib::ControlFlow::Break, move || {
+                    obj.autosave();
+                    glib::ControlFlow::Continue
+                }),
+            );
+        }
+
+        // Unsaved changes were dealt with while closing the windows, so
+        // nothing is left to recover.
+        fn shutdown(&self) {
+            autosave::clear();
+            self.parent_shutdown();
+        }
+
         // We connect to the activate callback to create a window when the application
         // has been launched. Additionally, this callback notifies us when the user
         // tries to launch a "second instance" of the application. When they try
@@ -67,6 +111,25 @@ mod imp {
 
             // Ask the window manager/compositor to present the window
             window.present();
+
+            if let Some(window) = window.downcast_ref::<VibrantWindow>() {
+                application.offer_recovery(window);
+            }
+        }
+
+        // Files opened from the file manager or the command line each get
+        // their own window. Launching this way skips activate, so the
+        // autosaved gradients are offered here too.
+        fn open(&self, files: &[gio::File], _hint: &str) {
+            let application = self.obj();
+            let mut window = None;
+            for file in files {
+                window = Some(application.open_file(file));
+            }
+
+            if let Some(window) = window {
+                application.offer_recovery(&window);
+            }
         }
     }
 
@@ -90,12 +153,91 @@ impl VibrantApplication {
 
     fn setup_gactions(&self) {
         let quit_action = gio::ActionEntry::builder("quit")
-            .activate(move |app: &Self, _, _| app.quit())
+            .activate(move |app: &Self, _, _| app.close_windows())
+            .build();
+        let new_window_action = gio::ActionEntry::builder("new-window")
+            .activate(move |app: &Self, _, _| VibrantWindow::new(app).present())
             .build();
         let about_action = gio::ActionEntry::builder("about")
             .activate(move |app: &Self, _, _| app.show_about())
             .build();
-        self.add_action_entries([quit_action, about_action]);
+        self.add_action_entries([quit_action, new_window_action, about_action]);
+    }
+
+    /// Closes every window, letting each one ask about its unsaved changes.
+    /// The application quits once the last one is gone.
+    fn close_windows(&self) {
+        for window in self.windows() {
+            window.close();
+        }
+    }
+
+    /// Opens a saved gradient, presenting the window and tab already
+    /// showing it.
+    /// The active window is reused while it holds an untouched gradient,
+    /// otherwise the file gets a new window, which is returned.
+    pub fn open_file(&self, file: &gio::File) -> VibrantWindow {
+        let windows = self.windows();
+        let existing = windows
+            .iter()
+            .filter_map(|window| window.downcast_ref::<VibrantWindow>())
+            .find(|window| window.select_file(file));
+        if let Some(window) = existing {
+            window.present();
+            return window.clone();
+        }
+
+        let window = self
+            .active_window()
+            .and_downcast::<VibrantWindow>()
+            .filter(VibrantWindow::is_pristine)
+            .unwrap_or_else(|| VibrantWindow::new(self));
+
+        window.open_file(file);
+        window.present();
+        window
+    }
+
+    /// Writes the gradients with unsaved changes, so they survive a crash.
+    fn autosave(&self) {
+        let documents = self
+            .windows()
+            .iter()
+            .filter_map(|window| window.downcast_ref::<VibrantWindow>())
+            .flat_map(VibrantWindow::unsaved_documents)
+            .collect::<Vec<_>>();
+
+        if let Err(err) = autosave::save(&documents) {
+            glib::g_warning!("vibrant", "Could not autosave gradients: {}", err);
+        }
+    }
+
+    /// Offers to restore the gradients autosaved before a crash, the first
+    /// time a window is shown. They are discarded once the toast goes away.
+    fn offer_recovery(&self, window: &VibrantWindow) {
+        if !self.imp().recovery.replace(false) {
+            return;
+        }
+
+        let toast = adw::Toast::builder()
+            .title(gettext("Vibrant did not close properly"))
+            .button_label(gettext("_Restore Gradients"))
+            .timeout(0)
+            .build();
+
+        toast.connect_button_clicked(clone!(@weak window => move |_| {
+            let documents = autosave::recovered();
+            autosave::discard_recovery();
+
+            glib::MainContext::default().spawn_local(async move {
+                for (document, file) in documents {
+                    window.restore_document(document, file).await;
+                }
+            });
+        }));
+        toast.connect_dismissed(|_| autosave::discard_recovery());
+
+        window.add_toast(toast);
     }
 
     fn show_about(&self) {
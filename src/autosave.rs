@@ -0,0 +1,150 @@
+/* autosave.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::path::{Path, PathBuf};
+
+use gtk::prelude::*;
+use gtk::{gio, glib};
+
+use crate::document::{self, Document};
+use crate::palette;
+
+/// Seconds between autosaves.
+pub const INTERVAL: u32 = 30;
+
+/// A document with unsaved changes, and the file it was opened from.
+pub type Unsaved = (Document, Option<gio::File>);
+
+fn autosave_dir() -> PathBuf {
+    palette::library_dir().join("autosave")
+}
+
+/// Where the next autosave is written before it replaces the previous one.
+fn pending_dir() -> PathBuf {
+    palette::library_dir().join("autosave.pending")
+}
+
+/// Where the autosave of a session that didn't quit cleanly is kept until
+/// it is restored or dismissed.
+fn recovery_dir() -> PathBuf {
+    palette::library_dir().join("recovery")
+}
+
+fn failed(message: impl ToString) -> glib::Error {
+    glib::Error::new(gio::IOErrorEnum::Failed, &message.to_string())
+}
+
+fn index_path(dir: &Path) -> PathBuf {
+    dir.join("index.gvariant")
+}
+
+/// Writes the documents with unsaved changes, replacing the previous
+/// autosave. The index lists the URI of each document's file, empty for
+/// documents never saved, stored as a GVariant of type `as`.
+pub fn save(documents: &[Unsaved]) -> Result<(), glib::Error> {
+    if documents.is_empty() {
+        clear();
+        return Ok(());
+    }
+
+    //written aside, so the previous autosave stays whole until this one is
+    let dir = pending_dir();
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).map_err(failed)?;
+
+    let mut uris = Vec::new();
+    for (i, (document, file)) in documents.iter().enumerate() {
+        let path = dir.join(format!("{i}.{}", document::SUFFIX));
+        std::fs::write(path, document.to_json()).map_err(failed)?;
+        uris.push(
+            file.as_ref()
+                .map(|file| file.uri().to_string())
+                .unwrap_or_default(),
+        );
+    }
+
+    //the index goes last and whole, marking the autosave as complete
+    let index = dir.join("index.partial");
+    std::fs::write(&index, uris.to_variant().print(true).as_str()).map_err(failed)?;
+    std::fs::rename(index, index_path(&dir)).map_err(failed)?;
+
+    let _ = std::fs::remove_dir_all(autosave_dir());
+    std::fs::rename(&dir, autosave_dir()).map_err(failed)
+}
+
+/// Removes the autosave, once the session ends without unsaved changes.
+pub fn clear() {
+    let _ = std::fs::remove_dir_all(autosave_dir());
+    let _ = std::fs::remove_dir_all(pending_dir());
+}
+
+/// Sets aside documents autosaved by a session that didn't quit cleanly, so
+/// the next autosave doesn't overwrite them. Returns whether there are any
+/// to restore.
+pub fn take_recovery() -> bool {
+    let recovery = recovery_dir();
+
+    //a pending autosave with its index is complete and newer, it was only
+    //left aside by a crash while replacing the previous one
+    let autosave = [pending_dir(), autosave_dir()]
+        .into_iter()
+        .find(|dir| index_path(dir).exists());
+    if let Some(autosave) = autosave {
+        let _ = std::fs::remove_dir_all(&recovery);
+        match std::fs::rename(&autosave, &recovery) {
+            //drops what an older autosave left behind
+            Ok(()) => clear(),
+            Err(err) => {
+                glib::g_warning!("vibrant", "Could not keep autosaved gradients: {}", err)
+            }
+        }
+    }
+
+    index_path(&recovery).exists()
+}
+
+/// Reads the documents set aside by `take_recovery`, skipping the ones
+/// that can't be read anymore.
+pub fn recovered() -> Vec<Unsaved> {
+    let dir = recovery_dir();
+    let Ok(text) = std::fs::read_to_string(index_path(&dir)) else {
+        return Vec::new();
+    };
+
+    let uris = glib::Variant::parse(Some(glib::VariantTy::STRING_ARRAY), &text)
+        .ok()
+        .and_then(|variant| variant.get::<Vec<String>>())
+        .unwrap_or_default();
+
+    uris.into_iter()
+        .enumerate()
+        .filter_map(|(i, uri)| {
+            let path = dir.join(format!("{i}.{}", document::SUFFIX));
+            let text = std::fs::read_to_string(path).ok()?;
+            let document = Document::from_json(&text).ok()?;
+            let file = (!uri.is_empty()).then(|| gio::File::for_uri(&uri));
+            Some((document, file))
+        })
+        .collect()
+}
+
+pub fn discard_recovery() {
+    let _ = std::fs::remove_dir_all(recovery_dir());
+}
@@ -0,0 +1,170 @@
+/* chart.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::f32::consts::PI;
+use std::fmt::Write;
+
+use gtk::gdk;
+
+use crate::color;
+use crate::gradient::{ColorStop, Gradient, GradientType};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Slice {
+    pub value: f32,
+    pub color: gdk::RGBA,
+}
+
+/// A pie chart, drawn as a conic gradient with hard stops.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Chart {
+    pub slices: Vec<Slice>,
+    /// Radius of the hole as a fraction of the chart's radius, zero for a pie.
+    pub hole: f32,
+}
+
+impl Chart {
+    /// A chart with one equal slice per color.
+    pub fn from_colors(colors: impl IntoIterator<Item = gdk::RGBA>) -> Self {
+        Self {
+            slices: colors
+                .into_iter()
+                .map(|color| Slice { value: 1.0, color })
+                .collect(),
+            hole: 0.0,
+        }
+    }
+
+    /// Start and end of each slice as fractions of the full turn. Slices are
+    /// split evenly while all values are zero.
+    pub fn arcs(&self) -> Vec<(f32, f32)> {
+        let total: f32 = self.slices.iter().map(|slice| slice.value.max(0.0)).sum();
+        let count = self.slices.len() as f32;
+
+        let mut start = 0.0;
+        self.slices
+            .iter()
+            .map(|slice| {
+                let fraction = if total > 0.0 {
+                    slice.value.max(0.0) / total
+                } else {
+                    1.0 / count
+                };
+
+                let arc = (start, (start + fraction).min(1.0));
+                start = arc.1;
+                arc
+            })
+            .collect()
+    }
+
+    pub fn to_gradient(&self) -> Gradient {
+        Gradient {
+            kind: GradientType::Conic,
+            angle: 0.0,
+            stops: self
+                .slices
+                .iter()
+                .zip(self.arcs())
+                .map(|(slice, (start, _))| ColorStop {
+                    color: slice.color,
+                    position: Some(start),
+                    linked: false,
+                })
+                .collect(),
+            hard_stops: true,
+            ..Gradient::default()
+        }
+    }
+
+    /// CSS declarations for a round element, masking out the hole of a donut.
+    pub fn to_css(&self) -> String {
+        let mut css = format!(
+            "background: {};\nborder-radius: 50%;",
+            self.to_gradient().to_css()
+        );
+
+        if self.hole > 0.0 {
+            let hole = (self.hole * 100.0).round();
+            let _ = write!(
+                css,
+                "\nmask: radial-gradient(closest-side, transparent {hole}%, #000 {hole}%);"
+            );
+        }
+
+        css
+    }
+
+    pub fn to_svg(&self, size: u32) -> String {
+        let radius = size as f32 / 2.0;
+        let inner = radius * self.hole;
+
+        //angles start at the top and grow clockwise, like conic gradients
+        let point = |r: f32, turn: f32| {
+            let angle = turn * 2.0 * PI;
+            (radius + r * angle.sin(), radius - r * angle.cos())
+        };
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" viewBox=\"0 0 {size} {size}\">\n"
+        );
+
+        for (slice, (start, end)) in self.slices.iter().zip(self.arcs()) {
+            if end <= start {
+                continue;
+            }
+
+            let fill = color::to_hex(&slice.color);
+
+            //a full turn can't be drawn as a single arc
+            if end - start >= 1.0 {
+                let _ = writeln!(
+                    svg,
+                    "  <circle cx=\"{radius}\" cy=\"{radius}\" r=\"{}\" fill=\"none\" stroke=\"{fill}\" stroke-width=\"{}\"/>",
+                    (radius + inner) / 2.0,
+                    radius - inner,
+                );
+                continue;
+            }
+
+            let large = (end - start > 0.5) as u8;
+            let (x0, y0) = point(radius, start);
+            let (x1, y1) = point(radius, end);
+
+            let mut path =
+                format!("M{x0:.2},{y0:.2} A{radius},{radius} 0 {large} 1 {x1:.2},{y1:.2}");
+            if inner > 0.0 {
+                let (x2, y2) = point(inner, end);
+                let (x3, y3) = point(inner, start);
+                let _ = write!(
+                    path,
+                    " L{x2:.2},{y2:.2} A{inner},{inner} 0 {large} 0 {x3:.2},{y3:.2}"
+                );
+            } else {
+                let _ = write!(path, " L{radius},{radius}");
+            }
+
+            let _ = writeln!(svg, "  <path d=\"{path} Z\" fill=\"{fill}\"/>");
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
@@ -0,0 +1,334 @@
+/* chart_dialog.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::cell::RefCell;
+use std::f64::consts::PI;
+
+use gettextrs::{gettext, pgettext};
+use glib::clone;
+
+use gtk::prelude::*;
+use gtk::{gdk, gio, glib};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use crate::chart::{Chart, Slice};
+
+const SVG_SIZE: u32 = 512;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/com/felipekinoshita/Vibrant/chart_dialog.ui")]
+    pub struct VibrantChartDialog {
+        pub chart: RefCell<Chart>,
+        pub slice_rows: RefCell<Vec<adw::ActionRow>>,
+        pub high_contrast_handler: RefCell<Option<glib::SignalHandlerId>>,
+
+        // Template widgets
+        #[template_child]
+        pub toast_overlay: TemplateChild<adw::ToastOverlay>,
+        #[template_child]
+        pub chart_area: TemplateChild<gtk::DrawingArea>,
+        #[template_child]
+        pub slices_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub donut_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub hole_row: TemplateChild<adw::SpinRow>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for VibrantChartDialog {
+        const NAME: &'static str = "VibrantChartDialog";
+        type Type = super::VibrantChartDialog;
+        type ParentType = adw::Window;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            Self::Type::bind_template_callbacks(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for VibrantChartDialog {
+        //the style manager outlives the dialog
+        fn dispose(&self) {
+            if let Some(handler) = self.high_contrast_handler.take() {
+                adw::StyleManager::default().disconnect(handler);
+            }
+        }
+    }
+    impl WidgetImpl for VibrantChartDialog {}
+    impl WindowImpl for VibrantChartDialog {}
+    impl AdwWindowImpl for VibrantChartDialog {}
+}
+
+glib::wrapper! {
+    pub struct VibrantChartDialog(ObjectSubclass<imp::VibrantChartDialog>)
+        @extends gtk::Widget, gtk::Window, adw::Window;
+}
+
+#[gtk::template_callbacks]
+impl VibrantChartDialog {
+    pub fn new<P: IsA<gtk::Window>>(parent: &P, chart: Chart) -> Self {
+        let dialog: Self = glib::Object::builder()
+            .property("transient-for", parent)
+            .build();
+
+        dialog.imp().chart.replace(chart);
+        dialog.setup_signals();
+        dialog.update_slice_rows();
+
+        dialog
+    }
+
+    fn setup_signals(&self) {
+        let imp = self.imp();
+
+        imp.chart_area
+            .set_draw_func(clone!(@weak self as this => move |_, cr, width, height| {
+                this.draw_chart(cr, width, height);
+            }));
+        let handler = adw::StyleManager::default().connect_high_contrast_notify(
+            clone!(@weak self as this => move |_| {
+                this.imp().chart_area.queue_draw();
+            }),
+        );
+        imp.high_contrast_handler.replace(Some(handler));
+
+        imp.donut_row
+            .connect_active_notify(clone!(@weak self as this => move |_| {
+                this.update_hole();
+            }));
+        imp.hole_row
+            .connect_value_notify(clone!(@weak self as this => move |_| {
+                this.update_hole();
+            }));
+    }
+
+    fn update_hole(&self) {
+        let imp = self.imp();
+
+        let hole = if imp.donut_row.is_active() {
+            imp.hole_row.value() as f32 / 100.0
+        } else {
+            0.0
+        };
+        self.modify_chart(|chart| chart.hole = hole);
+    }
+
+    fn modify_chart(&self, f: impl FnOnce(&mut Chart)) {
+        f(&mut self.imp().chart.borrow_mut());
+        self.imp().chart_area.queue_draw();
+    }
+
+    #[template_callback]
+    fn add_slice(&self, _button: gtk::Button) {
+        self.modify_chart(|chart| {
+            let color = chart
+                .slices
+                .last()
+                .map(|slice| slice.color)
+                .unwrap_or(gdk::RGBA::BLACK);
+            chart.slices.push(Slice { value: 1.0, color });
+        });
+        self.update_slice_rows();
+    }
+
+    fn remove_slice(&self, index: usize) {
+        self.modify_chart(|chart| {
+            if chart.slices.len() > 1 && index < chart.slices.len() {
+                chart.slices.remove(index);
+            }
+        });
+        self.update_slice_rows();
+    }
+
+    fn update_slice_rows(&self) {
+        let imp = self.imp();
+
+        for row in imp.slice_rows.take() {
+            imp.slices_group.remove(&row);
+        }
+
+        let slices = imp.chart.borrow().slices.clone();
+        let removable = slices.len() > 1;
+
+        let mut rows = Vec::new();
+        for (index, slice) in slices.iter().enumerate() {
+            let row = adw::ActionRow::builder()
+                .title(
+                    pgettext("Slice in a list", "Slice {}").replace("{}", &(index + 1).to_string()),
+                )
+                .build();
+
+            let color_button = gtk::ColorDialogButton::builder()
+                .dialog(&gtk::ColorDialog::new())
+                .rgba(&slice.color)
+                .valign(gtk::Align::Center)
+                .build();
+            color_button.connect_rgba_notify(clone!(@weak self as this => move |button| {
+                let color = button.rgba();
+                this.modify_chart(|chart| chart.slices[index].color = color);
+            }));
+            row.add_prefix(&color_button);
+
+            let value_button = gtk::SpinButton::builder()
+                .adjustment(&gtk::Adjustment::new(
+                    slice.value as f64,
+                    0.0,
+                    1_000_000.0,
+                    1.0,
+                    10.0,
+                    0.0,
+                ))
+                .digits(1)
+                .tooltip_text(gettext("Value"))
+                .valign(gtk::Align::Center)
+                .build();
+            value_button.connect_value_changed(clone!(@weak self as this => move |button| {
+                let value = button.value() as f32;
+                this.modify_chart(|chart| chart.slices[index].value = value);
+            }));
+            row.add_suffix(&value_button);
+
+            let remove_button = gtk::Button::builder()
+                .icon_name("list-remove-symbolic")
+                .tooltip_text(gettext("Remove Slice"))
+                .valign(gtk::Align::Center)
+                .sensitive(removable)
+                .css_classes(["flat"])
+                .build();
+            remove_button.connect_clicked(clone!(@weak self as this => move |_| {
+                this.remove_slice(index);
+            }));
+            row.add_suffix(&remove_button);
+
+            imp.slices_group.add(&row);
+            rows.push(row);
+        }
+
+        imp.slice_rows.replace(rows);
+    }
+
+    fn draw_chart(&self, cr: &gtk::cairo::Context, width: i32, height: i32) {
+        let chart = self.imp().chart.borrow();
+
+        let (cx, cy) = (width as f64 / 2.0, height as f64 / 2.0);
+        let radius = cx.min(cy);
+        let inner = radius * chart.hole as f64;
+
+        //slices get outlines in high contrast, colors alone may not tell them apart
+        let outlined = adw::StyleManager::default().is_high_contrast();
+        let outline = self.imp().chart_area.color();
+
+        //cairo angles start at the right, chart angles at the top
+        let angle = |turn: f32| turn as f64 * 2.0 * PI - PI / 2.0;
+
+        for (slice, (start, end)) in chart.slices.iter().zip(chart.arcs()) {
+            if end <= start {
+                continue;
+            }
+
+            cr.new_path();
+            cr.arc(cx, cy, radius, angle(start), angle(end));
+            if inner > 0.0 {
+                cr.arc_negative(cx, cy, inner, angle(end), angle(start));
+            } else {
+                cr.line_to(cx, cy);
+            }
+            cr.close_path();
+
+            let color = slice.color;
+            cr.set_source_rgba(
+                color.red() as f64,
+                color.green() as f64,
+                color.blue() as f64,
+                color.alpha() as f64,
+            );
+            if outlined {
+                let _ = cr.fill_preserve();
+                cr.set_source_rgba(
+                    outline.red() as f64,
+                    outline.green() as f64,
+                    outline.blue() as f64,
+                    outline.alpha() as f64,
+                );
+                cr.set_line_width(2.0);
+                let _ = cr.stroke();
+            } else {
+                let _ = cr.fill();
+            }
+        }
+    }
+
+    #[template_callback]
+    fn copy_css(&self, _button: gtk::Button) {
+        self.clipboard()
+            .set_text(&self.imp().chart.borrow().to_css());
+
+        self.imp()
+            .toast_overlay
+            .add_toast(adw::Toast::new(&gettext("Copied CSS to clipboard")));
+    }
+
+    #[template_callback]
+    fn export_svg(&self, _button: gtk::Button) {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(&gettext("SVG Images")));
+        filter.add_suffix("svg");
+
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        filters.append(&filter);
+
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Export SVG"))
+            .modal(true)
+            .filters(&filters)
+            .initial_name(format!("{}.svg", gettext("chart")))
+            .build();
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let Ok(file) = dialog.save_future(Some(&this)).await else {
+                return;
+            };
+
+            let svg = this.imp().chart.borrow().to_svg(SVG_SIZE);
+            let result = file
+                .replace_contents_future(svg, None, false, gio::FileCreateFlags::REPLACE_DESTINATION)
+                .await;
+
+            let message = match result {
+                Ok(_) => gettext("Exported chart"),
+                Err((_, err)) => {
+                    glib::g_warning!("vibrant", "Could not export chart: {}", err);
+                    gettext("Could not export chart")
+                }
+            };
+            this.imp().toast_overlay.add_toast(adw::Toast::new(&message));
+        }));
+    }
+}
@@ -0,0 +1,96 @@
+/* code_style.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::prelude::*;
+use gtk::{gdk, gio};
+
+use crate::color_format::ColorFormat;
+
+/// Relocatable schema holding the style of one kind of copied code.
+const SCHEMA: &str = "com.felipekinoshita.Vibrant.CodeStyle";
+
+/// Color formats code can be written with.
+pub const COLOR_FORMATS: [ColorFormat; 4] = [
+    ColorFormat::Hex,
+    ColorFormat::Rgb,
+    ColorFormat::Hsl,
+    ColorFormat::Oklch,
+];
+
+/// How copied code is laid out, remembered separately for each target like
+/// `css`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeStyle {
+    pub color_format: ColorFormat,
+    /// Spaces per level of indentation.
+    pub indent: usize,
+    /// Whether the last declaration of a block ends with a semicolon.
+    pub semicolons: bool,
+    /// Prepended to the names of variables the code declares.
+    pub variable_prefix: String,
+}
+
+impl Default for CodeStyle {
+    fn default() -> Self {
+        Self {
+            color_format: ColorFormat::Hex,
+            indent: 2,
+            semicolons: true,
+            variable_prefix: String::new(),
+        }
+    }
+}
+
+/// The settings of `target`, under its own path.
+pub fn settings(target: &str) -> gio::Settings {
+    gio::Settings::with_path(
+        SCHEMA,
+        &format!("/com/felipekinoshita/Vibrant/code-style/{target}/"),
+    )
+}
+
+impl CodeStyle {
+    pub fn load(target: &str) -> Self {
+        let settings = settings(target);
+
+        Self {
+            color_format: ColorFormat::from_name(&settings.string("color-format"))
+                .filter(|format| COLOR_FORMATS.contains(format))
+                .unwrap_or(ColorFormat::Hex),
+            indent: settings.uint("indent") as usize,
+            semicolons: settings.boolean("semicolons"),
+            variable_prefix: settings.string("variable-prefix").trim().to_owned(),
+        }
+    }
+
+    pub fn indentation(&self) -> String {
+        " ".repeat(self.indent)
+    }
+
+    pub fn format_color(&self, rgba: &gdk::RGBA) -> String {
+        self.color_format.format(rgba)
+    }
+
+    /// A block of declarations on one line, like `a {b: c; d: e;}`.
+    pub fn rule(&self, selector: &str, declarations: &[String]) -> String {
+        let end = if self.semicolons { ";" } else { "" };
+        format!("{selector} {{{}{end}}}", declarations.join("; "))
+    }
+}
@@ -0,0 +1,609 @@
+/* color.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::gdk;
+
+fn channel(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Formats the color as `#rrggbb`, or `#rrggbbaa` when translucent.
+pub fn to_hex(rgba: &gdk::RGBA) -> String {
+    let hex = format!(
+        "#{:02x}{:02x}{:02x}",
+        channel(rgba.red()),
+        channel(rgba.green()),
+        channel(rgba.blue())
+    );
+
+    if rgba.alpha() < 1.0 {
+        format!("{}{:02x}", hex, channel(rgba.alpha()))
+    } else {
+        hex
+    }
+}
+
+/// Decodes an sRGB channel to linear light.
+pub fn to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// How colors falling outside sRGB are brought back into it.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum GamutMapping {
+    /// Clamps each channel, which can shift the hue.
+    #[default]
+    Clip,
+    /// Lowers the chroma until the color fits, keeping its lightness and
+    /// hue like CSS gamut mapping does.
+    ReduceChroma,
+}
+
+impl From<u32> for GamutMapping {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::ReduceChroma,
+            _ => Self::Clip,
+        }
+    }
+}
+
+impl From<GamutMapping> for u32 {
+    fn from(value: GamutMapping) -> Self {
+        match value {
+            GamutMapping::Clip => 0,
+            GamutMapping::ReduceChroma => 1,
+        }
+    }
+}
+
+impl GamutMapping {
+    /// The name used in saved documents.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Clip => "clip",
+            Self::ReduceChroma => "reduce-chroma",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "clip" => Some(Self::Clip),
+            "reduce-chroma" => Some(Self::ReduceChroma),
+            _ => None,
+        }
+    }
+}
+
+/// A color in the OKLab perceptual space.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Oklab {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32,
+    pub alpha: f32,
+}
+
+/// OKLab in polar form, with the hue in degrees.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Oklch {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32,
+    pub alpha: f32,
+}
+
+impl From<gdk::RGBA> for Oklab {
+    fn from(rgba: gdk::RGBA) -> Self {
+        let r = to_linear(rgba.red());
+        let g = to_linear(rgba.green());
+        let b = to_linear(rgba.blue());
+
+        let l = (0.4122215 * r + 0.5363325 * g + 0.0514460 * b).cbrt();
+        let m = (0.2119035 * r + 0.6806995 * g + 0.107397 * b).cbrt();
+        let s = (0.0883025 * r + 0.2817189 * g + 0.6299787 * b).cbrt();
+
+        Self {
+            l: 0.2104543 * l + 0.7936178 * m - 0.0040720 * s,
+            a: 1.9779985 * l - 2.4285922 * m + 0.4505937 * s,
+            b: 0.0259040 * l + 0.7827718 * m - 0.8086758 * s,
+            alpha: rgba.alpha(),
+        }
+    }
+}
+
+impl Oklab {
+    /// Converts back to sRGB without clamping, so callers can detect colors
+    /// outside the gamut.
+    pub fn to_linear_rgb(self) -> [f32; 3] {
+        let l = (self.l + 0.3963377 * self.a + 0.2158038 * self.b).powi(3);
+        let m = (self.l - 0.1055613 * self.a - 0.0638542 * self.b).powi(3);
+        let s = (self.l - 0.0894842 * self.a - 1.2914855 * self.b).powi(3);
+
+        [
+            4.0767417 * l - 3.3077116 * m + 0.2309699 * s,
+            -1.268438 * l + 2.6097574 * m - 0.3413194 * s,
+            -0.0041961 * l - 0.7034186 * m + 1.7076147 * s,
+        ]
+    }
+
+    /// The Display-P3 channels of the color, clipped to that wider gamut.
+    pub fn to_display_p3(self) -> [f32; 3] {
+        let [r, g, b] = self.to_linear_rgb();
+
+        [
+            0.8224621 * r + 0.177538 * g,
+            0.0331941 * r + 0.9668058 * g,
+            0.0170827 * r + 0.0723974 * g + 0.9105199 * b,
+        ]
+        .map(|channel| from_linear(channel.clamp(0.0, 1.0)))
+    }
+
+    /// Whether the color fits in sRGB, allowing for rounding errors.
+    pub fn in_gamut(self) -> bool {
+        const EPSILON: f32 = 0.0005;
+
+        self.to_linear_rgb()
+            .iter()
+            .all(|channel| (-EPSILON..=1.0 + EPSILON).contains(channel))
+    }
+
+    /// Converts to sRGB, bringing colors outside it back in with `mapping`.
+    pub fn to_rgba_mapped(self, mapping: GamutMapping) -> gdk::RGBA {
+        if mapping == GamutMapping::Clip || self.in_gamut() {
+            return self.to_rgba();
+        }
+
+        //search for the largest chroma that still fits
+        let mut lch = Oklch::from(self);
+        lch.l = lch.l.clamp(0.0, 1.0);
+        let (mut low, mut high) = (0.0, lch.c);
+        for _ in 0..16 {
+            let chroma = (low + high) / 2.0;
+            if Oklab::from(Oklch { c: chroma, ..lch }).in_gamut() {
+                low = chroma;
+            } else {
+                high = chroma;
+            }
+        }
+
+        Oklch { c: low, ..lch }.to_rgba()
+    }
+
+    pub fn to_rgba(self) -> gdk::RGBA {
+        let [r, g, b] = self.to_linear_rgb();
+
+        gdk::RGBA::new(
+            from_linear(r).clamp(0.0, 1.0),
+            from_linear(g).clamp(0.0, 1.0),
+            from_linear(b).clamp(0.0, 1.0),
+            self.alpha,
+        )
+    }
+}
+
+impl From<Oklab> for Oklch {
+    fn from(lab: Oklab) -> Self {
+        Self {
+            l: lab.l,
+            c: lab.a.hypot(lab.b),
+            h: lab.b.atan2(lab.a).to_degrees().rem_euclid(360.0),
+            alpha: lab.alpha,
+        }
+    }
+}
+
+impl From<Oklch> for Oklab {
+    fn from(lch: Oklch) -> Self {
+        let (sin, cos) = lch.h.to_radians().sin_cos();
+
+        Self {
+            l: lch.l,
+            a: lch.c * cos,
+            b: lch.c * sin,
+            alpha: lch.alpha,
+        }
+    }
+}
+
+impl From<gdk::RGBA> for Oklch {
+    fn from(rgba: gdk::RGBA) -> Self {
+        Oklab::from(rgba).into()
+    }
+}
+
+impl Oklch {
+    pub fn to_rgba(self) -> gdk::RGBA {
+        Oklab::from(self).to_rgba()
+    }
+}
+
+/// Interpolates between two colors through OKLab, which avoids the gray
+/// zone sRGB blending shows between complementary colors.
+pub fn mix_oklab(from: &gdk::RGBA, to: &gdk::RGBA, t: f32) -> gdk::RGBA {
+    let from = Oklab::from(*from);
+    let to = Oklab::from(*to);
+
+    Oklab {
+        l: from.l + (to.l - from.l) * t,
+        a: from.a + (to.a - from.a) * t,
+        b: from.b + (to.b - from.b) * t,
+        alpha: from.alpha + (to.alpha - from.alpha) * t,
+    }
+    .to_rgba()
+}
+
+/// Interpolates between two colors through OKLCH, turning the hue the
+/// shorter way around so the blend keeps its chroma instead of crossing
+/// through gray.
+pub fn mix_oklch(from: &gdk::RGBA, to: &gdk::RGBA, t: f32) -> gdk::RGBA {
+    let from = Oklch::from(*from);
+    let to = Oklch::from(*to);
+    let turn = (to.h - from.h + 540.0).rem_euclid(360.0) - 180.0;
+
+    Oklab::from(Oklch {
+        l: from.l + (to.l - from.l) * t,
+        c: from.c + (to.c - from.c) * t,
+        h: (from.h + turn * t).rem_euclid(360.0),
+        alpha: from.alpha + (to.alpha - from.alpha) * t,
+    })
+    .to_rgba_mapped(GamutMapping::ReduceChroma)
+}
+
+/// Euclidean distance in OKLab, a perceptual color difference.
+pub fn delta_e(from: &gdk::RGBA, to: &gdk::RGBA) -> f32 {
+    let from = Oklab::from(*from);
+    let to = Oklab::from(*to);
+
+    ((from.l - to.l).powi(2) + (from.a - to.a).powi(2) + (from.b - to.b).powi(2)).sqrt()
+}
+
+/// Relative luminance as defined by WCAG, ignoring alpha.
+pub fn luminance(rgba: &gdk::RGBA) -> f32 {
+    0.2126 * to_linear(rgba.red())
+        + 0.7152 * to_linear(rgba.green())
+        + 0.0722 * to_linear(rgba.blue())
+}
+
+/// WCAG contrast ratio between two colors, from 1 to 21.
+pub fn contrast_ratio(from: &gdk::RGBA, to: &gdk::RGBA) -> f32 {
+    let (from, to) = (luminance(from), luminance(to));
+    (from.max(to) + 0.05) / (from.min(to) + 0.05)
+}
+
+/// Moves the lightness of a color away from `against` until their contrast
+/// reaches `ratio`, or it can't go further, keeping its hue.
+pub fn with_contrast(rgba: &gdk::RGBA, against: &gdk::RGBA, ratio: f32) -> gdk::RGBA {
+    let mut lch = Oklch::from(*rgba);
+    //darken against light colors, lighten against dark ones
+    let step = if luminance(against) > 0.18 {
+        -0.02
+    } else {
+        0.02
+    };
+
+    loop {
+        let candidate = Oklab::from(lch).to_rgba_mapped(GamutMapping::ReduceChroma);
+        if contrast_ratio(&candidate, against) >= ratio || !(0.0..=1.0).contains(&(lch.l + step)) {
+            return candidate;
+        }
+        lch.l += step;
+    }
+}
+
+/// A kind of color vision deficiency, simulated at full severity.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorBlindness {
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+impl ColorBlindness {
+    pub const ALL: [Self; 3] = [Self::Protanopia, Self::Deuteranopia, Self::Tritanopia];
+
+    /// How the color looks with this deficiency, using the matrices of
+    /// Machado et al. (2009) in linear light.
+    pub fn simulate(self, rgba: &gdk::RGBA) -> gdk::RGBA {
+        let matrix = match self {
+            Self::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Self::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Self::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        };
+        let linear = [rgba.red(), rgba.green(), rgba.blue()].map(to_linear);
+        let [r, g, b] = matrix.map(|row| {
+            let value: f32 = row.iter().zip(linear).map(|(m, c)| m * c).sum();
+            from_linear(value.clamp(0.0, 1.0))
+        });
+
+        gdk::RGBA::new(r, g, b, rgba.alpha())
+    }
+}
+
+/// Colors written anywhere in a text, in order: hex codes, functions such
+/// as `rgb()` or `hsl()`, and named colors.
+pub fn find_colors(text: &str) -> Vec<gdk::RGBA> {
+    let mut colors = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find(|c: char| c == '#' || c.is_ascii_alphabetic()) {
+        rest = &rest[start..];
+
+        let len = if let Some(hex) = rest.strip_prefix('#') {
+            1 + hex
+                .find(|c: char| !c.is_ascii_hexdigit())
+                .unwrap_or(hex.len())
+        } else {
+            let word = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            if rest[word..].starts_with('(') {
+                rest.find(')').map_or(rest.len(), |end| end + 1)
+            } else {
+                word
+            }
+        };
+
+        if let Ok(rgba) = gdk::RGBA::parse(&rest[..len]) {
+            colors.push(rgba);
+        }
+        rest = &rest[len.max(1)..];
+    }
+
+    colors
+}
+
+/// The CSS named color closest to `rgba`, ignoring alpha, to put colors
+/// into words.
+pub fn nearest_name(rgba: &gdk::RGBA) -> &'static str {
+    let opaque = gdk::RGBA::new(rgba.red(), rgba.green(), rgba.blue(), 1.0);
+
+    CSS_NAMED_COLORS
+        .iter()
+        .filter_map(|&(name, hex)| Some((name, gdk::RGBA::parse(hex).ok()?)))
+        .min_by(|(_, a), (_, b)| delta_e(&opaque, a).total_cmp(&delta_e(&opaque, b)))
+        .map(|(name, _)| name)
+        .unwrap_or("black")
+}
+
+/// The named colors from CSS Color Module Level 4.
+pub const CSS_NAMED_COLORS: &[(&str, &str)] = &[
+    ("aliceblue", "#f0f8ff"),
+    ("antiquewhite", "#faebd7"),
+    ("aqua", "#00ffff"),
+    ("aquamarine", "#7fffd4"),
+    ("azure", "#f0ffff"),
+    ("beige", "#f5f5dc"),
+    ("bisque", "#ffe4c4"),
+    ("black", "#000000"),
+    ("blanchedalmond", "#ffebcd"),
+    ("blue", "#0000ff"),
+    ("blueviolet", "#8a2be2"),
+    ("brown", "#a52a2a"),
+    ("burlywood", "#deb887"),
+    ("cadetblue", "#5f9ea0"),
+    ("chartreuse", "#7fff00"),
+    ("chocolate", "#d2691e"),
+    ("coral", "#ff7f50"),
+    ("cornflowerblue", "#6495ed"),
+    ("cornsilk", "#fff8dc"),
+    ("crimson", "#dc143c"),
+    ("cyan", "#00ffff"),
+    ("darkblue", "#00008b"),
+    ("darkcyan", "#008b8b"),
+    ("darkgoldenrod", "#b8860b"),
+    ("darkgray", "#a9a9a9"),
+    ("darkgreen", "#006400"),
+    ("darkgrey", "#a9a9a9"),
+    ("darkkhaki", "#bdb76b"),
+    ("darkmagenta", "#8b008b"),
+    ("darkolivegreen", "#556b2f"),
+    ("darkorange", "#ff8c00"),
+    ("darkorchid", "#9932cc"),
+    ("darkred", "#8b0000"),
+    ("darksalmon", "#e9967a"),
+    ("darkseagreen", "#8fbc8f"),
+    ("darkslateblue", "#483d8b"),
+    ("darkslategray", "#2f4f4f"),
+    ("darkslategrey", "#2f4f4f"),
+    ("darkturquoise", "#00ced1"),
+    ("darkviolet", "#9400d3"),
+    ("deeppink", "#ff1493"),
+    ("deepskyblue", "#00bfff"),
+    ("dimgray", "#696969"),
+    ("dimgrey", "#696969"),
+    ("dodgerblue", "#1e90ff"),
+    ("firebrick", "#b22222"),
+    ("floralwhite", "#fffaf0"),
+    ("forestgreen", "#228b22"),
+    ("fuchsia", "#ff00ff"),
+    ("gainsboro", "#dcdcdc"),
+    ("ghostwhite", "#f8f8ff"),
+    ("gold", "#ffd700"),
+    ("goldenrod", "#daa520"),
+    ("gray", "#808080"),
+    ("green", "#008000"),
+    ("greenyellow", "#adff2f"),
+    ("grey", "#808080"),
+    ("honeydew", "#f0fff0"),
+    ("hotpink", "#ff69b4"),
+    ("indianred", "#cd5c5c"),
+    ("indigo", "#4b0082"),
+    ("ivory", "#fffff0"),
+    ("khaki", "#f0e68c"),
+    ("lavender", "#e6e6fa"),
+    ("lavenderblush", "#fff0f5"),
+    ("lawngreen", "#7cfc00"),
+    ("lemonchiffon", "#fffacd"),
+    ("lightblue", "#add8e6"),
+    ("lightcoral", "#f08080"),
+    ("lightcyan", "#e0ffff"),
+    ("lightgoldenrodyellow", "#fafad2"),
+    ("lightgray", "#d3d3d3"),
+    ("lightgreen", "#90ee90"),
+    ("lightgrey", "#d3d3d3"),
+    ("lightpink", "#ffb6c1"),
+    ("lightsalmon", "#ffa07a"),
+    ("lightseagreen", "#20b2aa"),
+    ("lightskyblue", "#87cefa"),
+    ("lightslategray", "#778899"),
+    ("lightslategrey", "#778899"),
+    ("lightsteelblue", "#b0c4de"),
+    ("lightyellow", "#ffffe0"),
+    ("lime", "#00ff00"),
+    ("limegreen", "#32cd32"),
+    ("linen", "#faf0e6"),
+    ("magenta", "#ff00ff"),
+    ("maroon", "#800000"),
+    ("mediumaquamarine", "#66cdaa"),
+    ("mediumblue", "#0000cd"),
+    ("mediumorchid", "#ba55d3"),
+    ("mediumpurple", "#9370db"),
+    ("mediumseagreen", "#3cb371"),
+    ("mediumslateblue", "#7b68ee"),
+    ("mediumspringgreen", "#00fa9a"),
+    ("mediumturquoise", "#48d1cc"),
+    ("mediumvioletred", "#c71585"),
+    ("midnightblue", "#191970"),
+    ("mintcream", "#f5fffa"),
+    ("mistyrose", "#ffe4e1"),
+    ("moccasin", "#ffe4b5"),
+    ("navajowhite", "#ffdead"),
+    ("navy", "#000080"),
+    ("oldlace", "#fdf5e6"),
+    ("olive", "#808000"),
+    ("olivedrab", "#6b8e23"),
+    ("orange", "#ffa500"),
+    ("orangered", "#ff4500"),
+    ("orchid", "#da70d6"),
+    ("palegoldenrod", "#eee8aa"),
+    ("palegreen", "#98fb98"),
+    ("paleturquoise", "#afeeee"),
+    ("palevioletred", "#db7093"),
+    ("papayawhip", "#ffefd5"),
+    ("peachpuff", "#ffdab9"),
+    ("peru", "#cd853f"),
+    ("pink", "#ffc0cb"),
+    ("plum", "#dda0dd"),
+    ("powderblue", "#b0e0e6"),
+    ("purple", "#800080"),
+    ("rebeccapurple", "#663399"),
+    ("red", "#ff0000"),
+    ("rosybrown", "#bc8f8f"),
+    ("royalblue", "#4169e1"),
+    ("saddlebrown", "#8b4513"),
+    ("salmon", "#fa8072"),
+    ("sandybrown", "#f4a460"),
+    ("seagreen", "#2e8b57"),
+    ("seashell", "#fff5ee"),
+    ("sienna", "#a0522d"),
+    ("silver", "#c0c0c0"),
+    ("skyblue", "#87ceeb"),
+    ("slateblue", "#6a5acd"),
+    ("slategray", "#708090"),
+    ("slategrey", "#708090"),
+    ("snow", "#fffafa"),
+    ("springgreen", "#00ff7f"),
+    ("steelblue", "#4682b4"),
+    ("tan", "#d2b48c"),
+    ("teal", "#008080"),
+    ("thistle", "#d8bfd8"),
+    ("tomato", "#ff6347"),
+    ("turquoise", "#40e0d0"),
+    ("violet", "#ee82ee"),
+    ("wheat", "#f5deb3"),
+    ("white", "#ffffff"),
+    ("whitesmoke", "#f5f5f5"),
+    ("yellow", "#ffff00"),
+    ("yellowgreen", "#9acd32"),
+];
+
+/// The GNOME Human Interface Guidelines palette.
+pub const GNOME_PALETTE: &[(&str, &str)] = &[
+    ("Blue 1", "#99c1f1"),
+    ("Blue 2", "#62a0ea"),
+    ("Blue 3", "#3584e4"),
+    ("Blue 4", "#1c71d8"),
+    ("Blue 5", "#1a5fb4"),
+    ("Green 1", "#8ff0a4"),
+    ("Green 2", "#57e389"),
+    ("Green 3", "#33d17a"),
+    ("Green 4", "#2ec27e"),
+    ("Green 5", "#26a269"),
+    ("Yellow 1", "#f9f06b"),
+    ("Yellow 2", "#f8e45c"),
+    ("Yellow 3", "#f6d32d"),
+    ("Yellow 4", "#f5c211"),
+    ("Yellow 5", "#e5a50a"),
+    ("Orange 1", "#ffbe6f"),
+    ("Orange 2", "#ffa348"),
+    ("Orange 3", "#ff7800"),
+    ("Orange 4", "#e66100"),
+    ("Orange 5", "#c64600"),
+    ("Red 1", "#f66151"),
+    ("Red 2", "#ed333b"),
+    ("Red 3", "#e01b24"),
+    ("Red 4", "#c01c28"),
+    ("Red 5", "#a51d2d"),
+    ("Purple 1", "#dc8add"),
+    ("Purple 2", "#c061cb"),
+    ("Purple 3", "#9141ac"),
+    ("Purple 4", "#813d9c"),
+    ("Purple 5", "#613583"),
+    ("Brown 1", "#cdab8f"),
+    ("Brown 2", "#b5835a"),
+    ("Brown 3", "#986a44"),
+    ("Brown 4", "#865e3c"),
+    ("Brown 5", "#63452c"),
+    ("Light 1", "#ffffff"),
+    ("Light 2", "#f6f5f4"),
+    ("Light 3", "#deddda"),
+    ("Light 4", "#c0bfbc"),
+    ("Light 5", "#9a9996"),
+    ("Dark 1", "#77767b"),
+    ("Dark 2", "#5e5c64"),
+    ("Dark 3", "#3d3846"),
+    ("Dark 4", "#241f31"),
+    ("Dark 5", "#000000"),
+];
@@ -0,0 +1,201 @@
+/* color_format.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gettextrs::gettext;
+use gtk::prelude::*;
+use gtk::{gdk, gio};
+
+use crate::color::{self, Oklab, Oklch};
+
+/// Ways of writing a single color, for copying it or exporting gradients.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorFormat {
+    Hex,
+    Rgb,
+    Hsl,
+    Oklch,
+    /// A C compound literal of a `GdkRGBA`.
+    GdkRgba,
+    SwiftUi,
+}
+
+/// Rounds to a few decimals, dropping trailing zeros.
+fn number(value: f32, decimals: i32) -> String {
+    let scale = 10f32.powi(decimals);
+    let value = (value * scale).round() / scale;
+    //avoid printing -0
+    format!("{}", value + 0.0)
+}
+
+/// The CSS alpha suffix, empty for opaque colors.
+fn css_alpha(rgba: &gdk::RGBA) -> String {
+    if rgba.alpha() < 1.0 {
+        format!(" / {}%", number(rgba.alpha() * 100.0, 1))
+    } else {
+        String::new()
+    }
+}
+
+/// Hue in degrees, saturation and lightness from 0 to 1.
+fn to_hsl(rgba: &gdk::RGBA) -> (f32, f32, f32) {
+    let (r, g, b) = (rgba.red(), rgba.green(), rgba.blue());
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.0;
+
+    let delta = max - min;
+    if delta <= f32::EPSILON {
+        return (0.0, 0.0, lightness);
+    }
+
+    let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+    let hue = if max == r {
+        ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / delta + 2.0
+    } else {
+        (r - g) / delta + 4.0
+    };
+
+    (hue * 60.0, saturation, lightness)
+}
+
+impl ColorFormat {
+    pub const ALL: [Self; 6] = [
+        Self::Hex,
+        Self::Rgb,
+        Self::Hsl,
+        Self::Oklch,
+        Self::GdkRgba,
+        Self::SwiftUi,
+    ];
+
+    /// The name used by the `copy-stop-color` action.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Hex => "hex",
+            Self::Rgb => "rgb",
+            Self::Hsl => "hsl",
+            Self::Oklch => "oklch",
+            Self::GdkRgba => "gdk-rgba",
+            Self::SwiftUi => "swiftui",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|format| format.name() == name)
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            Self::Hex => gettext("Hex"),
+            Self::Rgb => "rgb()".to_owned(),
+            Self::Hsl => "hsl()".to_owned(),
+            Self::Oklch => "oklch()".to_owned(),
+            Self::GdkRgba => "GdkRGBA".to_owned(),
+            Self::SwiftUi => gettext("SwiftUI Color"),
+        }
+    }
+
+    pub fn format(self, rgba: &gdk::RGBA) -> String {
+        match self {
+            Self::Hex => color::to_hex(rgba),
+            Self::Rgb => format!(
+                "rgb({} {} {}{})",
+                number(rgba.red() * 255.0, 0),
+                number(rgba.green() * 255.0, 0),
+                number(rgba.blue() * 255.0, 0),
+                css_alpha(rgba)
+            ),
+            Self::Hsl => {
+                let (h, s, l) = to_hsl(rgba);
+                format!(
+                    "hsl({}deg {}% {}%{})",
+                    number(h, 1),
+                    number(s * 100.0, 1),
+                    number(l * 100.0, 1),
+                    css_alpha(rgba)
+                )
+            }
+            Self::Oklch => {
+                let oklch = Oklch::from(*rgba);
+                format!(
+                    "oklch({}% {} {}{})",
+                    number(oklch.l * 100.0, 1),
+                    number(oklch.c, 3),
+                    number(oklch.h, 1),
+                    css_alpha(rgba)
+                )
+            }
+            Self::GdkRgba => format!(
+                "(GdkRGBA) {{ {}, {}, {}, {} }}",
+                number(rgba.red(), 3),
+                number(rgba.green(), 3),
+                number(rgba.blue(), 3),
+                number(rgba.alpha(), 3)
+            ),
+            Self::SwiftUi => {
+                let opacity = if rgba.alpha() < 1.0 {
+                    format!(", opacity: {}", number(rgba.alpha(), 3))
+                } else {
+                    String::new()
+                };
+                format!(
+                    "Color(red: {}, green: {}, blue: {}{})",
+                    number(rgba.red(), 3),
+                    number(rgba.green(), 3),
+                    number(rgba.blue(), 3),
+                    opacity
+                )
+            }
+        }
+    }
+}
+
+/// A CSS `color(display-p3 …)`, for colors that may not fit in sRGB.
+pub fn display_p3(lab: Oklab) -> String {
+    let [r, g, b] = lab.to_display_p3();
+    let alpha = gdk::RGBA::new(0.0, 0.0, 0.0, lab.alpha);
+
+    format!(
+        "color(display-p3 {} {} {}{})",
+        number(r, 4),
+        number(g, 4),
+        number(b, 4),
+        css_alpha(&alpha)
+    )
+}
+
+/// A menu copying the color of the stop at `index` in every format.
+pub fn copy_menu(index: usize) -> gio::Menu {
+    let menu = gio::Menu::new();
+    for format in ColorFormat::ALL {
+        let item = gio::MenuItem::new(
+            Some(&gettext("Copy as {}").replace("{}", &format.label())),
+            None,
+        );
+        item.set_action_and_target_value(
+            Some("win.copy-stop-color"),
+            Some(&(index as u32, format.name()).to_variant()),
+        );
+        menu.append_item(&item);
+    }
+    menu
+}
@@ -0,0 +1,116 @@
+/* community.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::path::PathBuf;
+
+use gtk::{gio, glib};
+
+use crate::document::Document;
+use crate::http;
+use crate::json::{self, Value};
+
+/// Identifies the JSON feeds of shared gradients.
+const FORMAT: &str = "vibrant-community";
+
+/// A gradient shared by someone else.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub document: Document,
+    pub author: String,
+}
+
+impl Entry {
+    pub fn to_value(&self) -> Value {
+        Value::object([
+            ("author", self.author.as_str().into()),
+            ("document", self.document.to_value()),
+        ])
+    }
+}
+
+fn cache_path() -> PathBuf {
+    glib::user_cache_dir()
+        .join("vibrant")
+        .join("community.json")
+}
+
+/// Reads a feed, an object holding a `gradients` array of saved documents
+/// along with their `author`. Entries that don't parse are skipped, so a
+/// feed written by a newer version still shows what it can.
+pub fn parse(text: &str) -> Result<Vec<Entry>, glib::Error> {
+    let invalid_data = |message: &str| glib::Error::new(gio::IOErrorEnum::InvalidData, message);
+
+    let value = json::parse(text).map_err(|err| invalid_data(&err))?;
+    if value.get("format").and_then(Value::as_str) != Some(FORMAT) {
+        return Err(invalid_data("Not a community feed"));
+    }
+
+    let entries = value
+        .get("gradients")
+        .and_then(Value::as_array)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|entry| {
+            let document = Document::from_value(entry.get("document")?).ok()?;
+            let author = entry
+                .get("author")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_owned();
+            Some(Entry { document, author })
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+/// The feed as last downloaded, for showing something right away and
+/// while offline.
+pub fn cached() -> Vec<Entry> {
+    std::fs::read_to_string(cache_path())
+        .ok()
+        .and_then(|text| parse(&text).ok())
+        .unwrap_or_default()
+}
+
+/// Downloads the feed at `url`, keeping a copy for `cached`.
+pub async fn fetch(url: &str) -> Result<Vec<Entry>, glib::Error> {
+    let bytes = http::get(url).await?;
+    let text = String::from_utf8(bytes)
+        .map_err(|_| glib::Error::new(gio::IOErrorEnum::InvalidData, "Invalid text"))?;
+    let entries = parse(&text)?;
+
+    //an outdated cache only shows older gradients when offline
+    let saved = std::fs::create_dir_all(cache_path().parent().unwrap())
+        .and_then(|()| std::fs::write(cache_path(), &text));
+    if let Err(err) = saved {
+        glib::g_warning!("vibrant", "Could not cache the community feed: {}", err);
+    }
+
+    Ok(entries)
+}
+
+/// Sends a gradient to the gallery at `url`, as a JSON entry like the ones
+/// of the feed.
+pub async fn submit(url: &str, entry: &Entry) -> Result<(), glib::Error> {
+    let body = entry.to_value().to_json();
+    http::post(url, "application/json", body.as_bytes()).await?;
+    Ok(())
+}
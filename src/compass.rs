@@ -0,0 +1,138 @@
+/* compass.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::cell::{Cell, OnceCell};
+use std::f64::consts::PI;
+
+use adw::prelude::*;
+use glib::clone;
+use gtk::subclass::prelude::*;
+use gtk::{cairo, glib, graphene};
+
+/// Diameter of the compass, in logical pixels.
+const SIZE: i32 = 24;
+/// How long the needle takes to turn to a new angle, in milliseconds.
+const TURN_DURATION: u32 = 150;
+
+mod imp {
+    use super::*;
+
+    /// A dial pointing where a CSS angle does, clockwise from the top.
+    #[derive(Debug, Default)]
+    pub struct VibrantCompass {
+        /// Where the needle points, on its way to the angle while turning.
+        pub angle: Cell<f32>,
+        pub animation: OnceCell<adw::TimedAnimation>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for VibrantCompass {
+        const NAME: &'static str = "VibrantCompass";
+        type Type = super::VibrantCompass;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_css_name("compass");
+            klass.set_accessible_role(gtk::AccessibleRole::Img);
+        }
+    }
+
+    impl ObjectImpl for VibrantCompass {
+        fn constructed(&self) {
+            self.parent_constructed();
+            let obj = self.obj();
+            obj.set_size_request(SIZE, SIZE);
+
+            let target = adw::CallbackAnimationTarget::new(clone!(@weak obj => move |angle| {
+                obj.imp().angle.set(angle as f32);
+                obj.queue_draw();
+            }));
+            let animation = adw::TimedAnimation::builder()
+                .widget(&*obj)
+                .duration(TURN_DURATION)
+                .easing(adw::Easing::EaseOutCubic)
+                .target(&target)
+                .build();
+            self.animation.set(animation).unwrap();
+        }
+    }
+
+    impl WidgetImpl for VibrantCompass {
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let widget = self.obj();
+            let (width, height) = (widget.width() as f64, widget.height() as f64);
+            let bounds = graphene::Rect::new(0.0, 0.0, width as f32, height as f32);
+            let color = widget.color();
+
+            let cr = snapshot.append_cairo(&bounds);
+            let radius = width.min(height) / 2.0 - 1.0;
+            cr.translate(width / 2.0, height / 2.0);
+            cr.set_source_rgba(
+                color.red() as f64,
+                color.green() as f64,
+                color.blue() as f64,
+                color.alpha() as f64,
+            );
+
+            cr.set_line_width(1.0);
+            cr.arc(0.0, 0.0, radius, 0.0, 2.0 * PI);
+            let _ = cr.stroke();
+
+            //CSS angles start at the top and go clockwise
+            let (sin, cos) = (self.angle.get() as f64).to_radians().sin_cos();
+            cr.set_line_width(2.0);
+            cr.set_line_cap(cairo::LineCap::Round);
+            cr.move_to(0.0, 0.0);
+            cr.line_to(sin * (radius - 3.0), -cos * (radius - 3.0));
+            let _ = cr.stroke();
+
+            cr.arc(0.0, 0.0, 2.0, 0.0, 2.0 * PI);
+            let _ = cr.fill();
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct VibrantCompass(ObjectSubclass<imp::VibrantCompass>)
+        @extends gtk::Widget,
+        @implements gtk::Accessible;
+}
+
+impl VibrantCompass {
+    /// Turns the needle the shortest way to `angle`, or points it there at
+    /// once when animations are turned off.
+    pub fn set_angle(&self, angle: f32) {
+        let imp = self.imp();
+        let animation = imp.animation.get().unwrap();
+
+        let from = imp.angle.get();
+        let to = from + (angle - from + 180.0).rem_euclid(360.0) - 180.0;
+        if !self.is_mapped() || !self.settings().is_gtk_enable_animations() {
+            animation.reset();
+            imp.angle.set(to);
+            self.queue_draw();
+            return;
+        }
+
+        animation.set_value_from(from as f64);
+        animation.set_value_to(to as f64);
+        animation.play();
+    }
+}
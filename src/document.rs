@@ -0,0 +1,641 @@
+/* document.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::prelude::*;
+use gtk::{gdk, gio, glib};
+
+use crate::color::{self, GamutMapping};
+use crate::gradient::{Adjustments, ColorStop, Gradient, GradientType, Vignette};
+use crate::json::{self, Value};
+use crate::pattern::{Pattern, PatternKind};
+
+/// MIME type of saved gradients, registered in the shared MIME database.
+pub const MIME_TYPE: &str = "application/x-vibrant-gradient";
+
+pub const SUFFIX: &str = "gradient";
+
+/// Identifies the JSON documents written by Vibrant.
+const FORMAT: &str = "vibrant-gradient";
+
+/// Version of the format, increased on incompatible changes.
+const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    pub name: String,
+    /// Creation and last modification times, in ISO 8601.
+    pub created: Option<String>,
+    pub modified: Option<String>,
+    /// Labels the user sorts their saved gradients with.
+    pub tags: Vec<String>,
+}
+
+/// A gradient of a collection, with the name it goes by.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Member {
+    pub name: String,
+    pub gradient: Gradient,
+}
+
+/// Related gradients kept in one document, like the ones of a brand or an
+/// app. The edited gradient is the `active` member, whose copy here is only
+/// brought up to date when switching to another one or saving. Documents
+/// holding a single gradient have no members.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Collection {
+    pub members: Vec<Member>,
+    pub active: usize,
+}
+
+impl Collection {
+    /// The collection with the active member replaced by `gradient`.
+    pub fn with_active(&self, gradient: &Gradient) -> Self {
+        let mut collection = self.clone();
+        if let Some(member) = collection.members.get_mut(self.active) {
+            member.gradient = gradient.clone();
+        }
+        collection
+    }
+
+    /// Whether the members differ from those of `saved`, with `gradient` as
+    /// the active one. Which member is active doesn't count as a change.
+    pub fn is_modified(&self, gradient: &Gradient, saved: &Collection) -> bool {
+        self.with_active(gradient).members != saved.members
+    }
+}
+
+/// A saved gradient, kept without its adjustments applied so they stay
+/// editable after reopening it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Document {
+    pub gradient: Gradient,
+    pub metadata: Metadata,
+    pub collection: Collection,
+}
+
+fn invalid_data(message: &str) -> glib::Error {
+    glib::Error::new(gio::IOErrorEnum::InvalidData, message)
+}
+
+fn now() -> Option<String> {
+    glib::DateTime::now_utc()
+        .and_then(|now| now.format_iso8601())
+        .ok()
+        .map(Into::into)
+}
+
+fn color_to_json(rgba: &gdk::RGBA) -> Value {
+    color::to_hex(rgba).into()
+}
+
+fn color_from_json(value: &Value) -> Result<gdk::RGBA, glib::Error> {
+    value
+        .as_str()
+        .and_then(|text| gdk::RGBA::parse(text).ok())
+        .ok_or_else(|| invalid_data("Invalid color"))
+}
+
+fn gradient_to_json(gradient: &Gradient) -> Value {
+    let stops = gradient
+        .stops
+        .iter()
+        .map(|stop| {
+            Value::object([
+                ("color", color_to_json(&stop.color)),
+                ("position", stop.position.into()),
+                ("linked", stop.linked.into()),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    let adjustments = gradient.adjustments;
+    let pattern = gradient.pattern;
+    let vignette = gradient.vignette;
+
+    let mut value = Value::object([
+        ("type", gradient.kind.name().into()),
+        ("angle", gradient.angle.into()),
+        ("hard-stops", gradient.hard_stops.into()),
+        ("symmetric", gradient.symmetric.into()),
+        ("tint-transparent", gradient.tint_transparent.into()),
+        ("stops", stops.into()),
+        (
+            "adjustments",
+            Value::object([
+                ("lightness", adjustments.lightness.into()),
+                ("chroma", adjustments.chroma.into()),
+                ("hue", adjustments.hue.into()),
+                ("temperature", adjustments.temperature.into()),
+                ("gamut-mapping", adjustments.gamut_mapping.name().into()),
+            ]),
+        ),
+        (
+            "layers",
+            Value::object([
+                (
+                    "pattern",
+                    Value::object([
+                        ("kind", pattern.kind.name().into()),
+                        ("color", color_to_json(&pattern.color)),
+                        ("opacity", pattern.opacity.into()),
+                        ("scale", pattern.scale.into()),
+                    ]),
+                ),
+                (
+                    "vignette",
+                    Value::object([
+                        ("strength", vignette.strength.into()),
+                        ("size", vignette.size.into()),
+                    ]),
+                ),
+                ("readable-top", gradient.readable_top.into()),
+                ("grain", gradient.grain.into()),
+            ]),
+        ),
+    ]);
+
+    if let (Some(dark), Value::Object(entries)) = (&gradient.dark, &mut value) {
+        entries.push(("dark".to_owned(), gradient_to_json(dark)));
+    }
+
+    value
+}
+
+/// Reads a gradient, using defaults for the optional parts missing from it.
+fn gradient_from_json(value: &Value) -> Result<Gradient, glib::Error> {
+    let number = |value: &Value, key: &str, default: f32| {
+        value.get(key).and_then(Value::as_f32).unwrap_or(default)
+    };
+    //kept within what the controls can set
+    let ranged = |value: &Value, key: &str, default: f32, min: f32, max: f32| {
+        number(value, key, default).clamp(min, max)
+    };
+    let boolean =
+        |value: &Value, key: &str| value.get(key).and_then(Value::as_bool).unwrap_or_default();
+
+    let kind = value
+        .get("type")
+        .and_then(Value::as_str)
+        .and_then(GradientType::from_name)
+        .ok_or_else(|| invalid_data("Unknown gradient type"))?;
+
+    let stops = value
+        .get("stops")
+        .and_then(Value::as_array)
+        .ok_or_else(|| invalid_data("Missing color stops"))?
+        .iter()
+        .map(|stop| {
+            Ok(ColorStop {
+                color: color_from_json(stop.get("color").unwrap_or(&Value::Null))?,
+                position: stop
+                    .get("position")
+                    .and_then(Value::as_f32)
+                    .map(|position| position.clamp(0.0, 1.0)),
+                linked: boolean(stop, "linked"),
+            })
+        })
+        .collect::<Result<Vec<_>, glib::Error>>()?;
+    if stops.is_empty() {
+        return Err(invalid_data("Missing color stops"));
+    }
+
+    let mut gradient = Gradient {
+        kind,
+        angle: number(value, "angle", 0.0).rem_euclid(360.0),
+        stops,
+        hard_stops: boolean(value, "hard-stops"),
+        symmetric: boolean(value, "symmetric"),
+        tint_transparent: boolean(value, "tint-transparent"),
+        ..Gradient::default()
+    };
+
+    if let Some(adjustments) = value.get("adjustments") {
+        gradient.adjustments = Adjustments {
+            lightness: ranged(adjustments, "lightness", 0.0, -0.5, 0.5),
+            chroma: ranged(adjustments, "chroma", 0.0, -1.0, 1.0),
+            hue: ranged(adjustments, "hue", 0.0, -180.0, 180.0),
+            temperature: ranged(adjustments, "temperature", 0.0, -1.0, 1.0),
+            gamut_mapping: adjustments
+                .get("gamut-mapping")
+                .and_then(Value::as_str)
+                .and_then(GamutMapping::from_name)
+                .unwrap_or_default(),
+        };
+    }
+
+    if let Some(layers) = value.get("layers") {
+        if let Some(pattern) = layers.get("pattern") {
+            let default = Pattern::default();
+            gradient.pattern = Pattern {
+                kind: pattern
+                    .get("kind")
+                    .and_then(Value::as_str)
+                    .and_then(PatternKind::from_name)
+                    .unwrap_or_default(),
+                color: pattern
+                    .get("color")
+                    .map(color_from_json)
+                    .transpose()?
+                    .unwrap_or(default.color),
+                opacity: ranged(pattern, "opacity", default.opacity, 0.0, 1.0),
+                scale: ranged(pattern, "scale", default.scale, 0.5, 4.0),
+            };
+        }
+
+        if let Some(vignette) = layers.get("vignette") {
+            let default = Vignette::default();
+            gradient.vignette = Vignette {
+                strength: ranged(vignette, "strength", default.strength, 0.0, 1.0),
+                size: ranged(vignette, "size", default.size, 0.0, 0.95),
+            };
+        }
+
+        gradient.readable_top = boolean(layers, "readable-top");
+        gradient.grain = ranged(layers, "grain", 0.0, 0.0, 1.0);
+    }
+
+    gradient.dark = value
+        .get("dark")
+        .filter(|dark| !dark.is_null())
+        .map(|dark| gradient_from_json(dark).map(Box::new))
+        .transpose()?;
+
+    Ok(gradient)
+}
+
+fn collection_to_json(collection: &Collection) -> Value {
+    let members = collection
+        .members
+        .iter()
+        .map(|member| {
+            Value::object([
+                ("name", member.name.as_str().into()),
+                ("gradient", gradient_to_json(&member.gradient)),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    Value::object([
+        ("active", (collection.active as u32).into()),
+        ("gradients", members.into()),
+    ])
+}
+
+fn collection_from_json(value: &Value) -> Result<Collection, glib::Error> {
+    let members = value
+        .get("gradients")
+        .and_then(Value::as_array)
+        .unwrap_or_default()
+        .iter()
+        .map(|member| {
+            Ok(Member {
+                name: member
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_owned(),
+                gradient: gradient_from_json(
+                    member
+                        .get("gradient")
+                        .ok_or_else(|| invalid_data("Missing gradient"))?,
+                )?,
+            })
+        })
+        .collect::<Result<Vec<_>, glib::Error>>()?;
+    let active = value
+        .get("active")
+        .and_then(Value::as_f32)
+        .unwrap_or_default() as usize;
+
+    Ok(Collection {
+        active: active.min(members.len().saturating_sub(1)),
+        members,
+    })
+}
+
+impl Document {
+    pub fn to_json(&self) -> String {
+        self.to_value().to_json()
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, glib::Error> {
+        Self::from_value(&json::parse(text).map_err(|err| invalid_data(&err))?)
+    }
+
+    /// The document as a JSON value, for embedding it in other formats.
+    pub fn to_value(&self) -> Value {
+        let mut metadata = Value::object([
+            ("name", self.metadata.name.as_str().into()),
+            ("created", self.metadata.created.clone().into()),
+            ("modified", self.metadata.modified.clone().into()),
+        ]);
+        if let (false, Value::Object(entries)) = (self.metadata.tags.is_empty(), &mut metadata) {
+            entries.push(("tags".to_owned(), self.metadata.tags.clone().into()));
+        }
+
+        let mut value = Value::object([
+            ("format", FORMAT.into()),
+            ("version", VERSION.into()),
+            ("metadata", metadata),
+            ("gradient", gradient_to_json(&self.gradient)),
+        ]);
+
+        //readers without collections still find the active gradient above
+        if let (false, Value::Object(entries)) = (self.collection.members.is_empty(), &mut value) {
+            entries.push((
+                "collection".to_owned(),
+                collection_to_json(&self.collection),
+            ));
+        }
+
+        value
+    }
+
+    pub fn from_value(value: &Value) -> Result<Self, glib::Error> {
+        if value.get("format").and_then(Value::as_str) != Some(FORMAT) {
+            return Err(invalid_data("Not a Vibrant gradient"));
+        }
+        if value
+            .get("version")
+            .and_then(Value::as_f32)
+            .is_some_and(|version| version > VERSION as f32)
+        {
+            return Err(invalid_data("Saved by a newer version of Vibrant"));
+        }
+
+        let gradient = gradient_from_json(
+            value
+                .get("gradient")
+                .ok_or_else(|| invalid_data("Missing gradient"))?,
+        )?;
+
+        let metadata = value.get("metadata");
+        let text = |key: &str| {
+            metadata
+                .and_then(|metadata| metadata.get(key))
+                .and_then(Value::as_str)
+                .map(str::to_owned)
+        };
+
+        let collection = value
+            .get("collection")
+            .map(collection_from_json)
+            .transpose()?
+            .unwrap_or_default();
+
+        Ok(Self {
+            gradient,
+            metadata: Metadata {
+                name: text("name").unwrap_or_default(),
+                created: text("created"),
+                modified: text("modified"),
+                tags: metadata
+                    .and_then(|metadata| metadata.get("tags"))
+                    .and_then(Value::as_array)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(|tag| tag.as_str().map(str::to_owned))
+                    .collect(),
+            },
+            collection,
+        })
+    }
+}
+
+/// Name of a document shown to the user, from its file name.
+pub fn display_name(file: &gio::File) -> String {
+    let name = file
+        .basename()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    name.strip_suffix(&format!(".{SUFFIX}"))
+        .map(str::to_owned)
+        .unwrap_or(name)
+}
+
+pub async fn load(file: &gio::File) -> Result<Document, glib::Error> {
+    let (data, _) = file.load_contents_future().await?;
+    Document::from_json(&String::from_utf8_lossy(&data))
+}
+
+/// Writes the document, stamping its creation and modification times.
+pub async fn save(file: &gio::File, document: &mut Document) -> Result<(), glib::Error> {
+    let now = now();
+    if document.metadata.created.is_none() {
+        document.metadata.created = now.clone();
+    }
+    document.metadata.modified = now;
+    if document.metadata.name.is_empty() {
+        document.metadata.name = display_name(file);
+    }
+
+    file.replace_contents_future(
+        document.to_json(),
+        None,
+        false,
+        gio::FileCreateFlags::REPLACE_DESTINATION,
+    )
+    .await
+    .map_err(|(_, err)| err)?;
+
+    Ok(())
+}
+
+/// File chooser filters for saved gradients.
+pub fn file_filters() -> gio::ListStore {
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some(&gettextrs::gettext("Gradients")));
+    filter.add_mime_type(MIME_TYPE);
+    filter.add_suffix(SUFFIX);
+
+    let filters = gio::ListStore::new::<gtk::FileFilter>();
+    filters.append(&filter);
+    filters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba(text: &str) -> gdk::RGBA {
+        gdk::RGBA::parse(text).unwrap()
+    }
+
+    fn document() -> Document {
+        let gradient = Gradient {
+            kind: GradientType::Conic,
+            angle: 292.5,
+            stops: vec![
+                ColorStop {
+                    position: Some(0.25),
+                    linked: true,
+                    ..ColorStop::new(rgba("#ff8000"))
+                },
+                ColorStop::new(rgba("#00336680")),
+            ],
+            hard_stops: true,
+            adjustments: Adjustments {
+                lightness: -0.1,
+                hue: 30.0,
+                ..Adjustments::default()
+            },
+            pattern: Pattern {
+                kind: PatternKind::Dots,
+                scale: 2.5,
+                ..Pattern::default()
+            },
+            vignette: Vignette {
+                strength: 0.4,
+                ..Vignette::default()
+            },
+            grain: 0.3,
+            ..Gradient::default()
+        };
+        let dark = Gradient {
+            kind: GradientType::Radial,
+            ..Gradient::default()
+        };
+
+        Document {
+            gradient: Gradient {
+                dark: Some(Box::new(dark)),
+                ..gradient.clone()
+            },
+            metadata: Metadata {
+                name: "Dusk \"Ω\"".to_owned(),
+                created: Some("2023-05-01T10:00:00Z".to_owned()),
+                modified: None,
+                tags: vec!["warm".to_owned(), "brand".to_owned()],
+            },
+            collection: Collection {
+                members: vec![
+                    Member {
+                        name: "Light".to_owned(),
+                        gradient: gradient.clone(),
+                    },
+                    Member {
+                        name: "Default".to_owned(),
+                        gradient: Gradient::default(),
+                    },
+                ],
+                active: 1,
+            },
+        }
+    }
+
+    /// A document holding `gradient`, written by hand.
+    fn with_gradient(gradient: &str) -> String {
+        format!(r#"{{"format": "vibrant-gradient", "version": 1, "gradient": {gradient}}}"#)
+    }
+
+    #[test]
+    fn round_trip() {
+        let document = document();
+        let text = document.to_json();
+        assert_eq!(Document::from_json(&text).unwrap(), document);
+        assert_eq!(json::parse(&text).unwrap(), document.to_value());
+
+        let single = Document::default();
+        assert_eq!(Document::from_json(&single.to_json()).unwrap(), single);
+    }
+
+    #[test]
+    fn malformed() {
+        let stops = r##"[{"color": "#fff"}]"##;
+        for text in [
+            String::new(),
+            "{".to_owned(),
+            "[]".to_owned(),
+            r#"{"format": "something-else", "gradient": {}}"#.to_owned(),
+            r#"{"format": "vibrant-gradient"}"#.to_owned(),
+            with_gradient(&format!(r#"{{"type": "diamond", "stops": {stops}}}"#)),
+            with_gradient(r#"{"type": "linear", "stops": []}"#),
+            with_gradient(r#"{"type": "linear", "stops": [{"color": "fuzzy"}]}"#),
+            with_gradient(&format!(
+                r#"{{"type": "linear", "stops": {stops}, "dark": {{"type": "linear"}}}}"#
+            )),
+        ] {
+            assert!(Document::from_json(&text).is_err(), "{text} loaded");
+        }
+    }
+
+    #[test]
+    fn versions() {
+        let text = Document::default().to_json();
+        let newer = text.replace(r#""version": 1"#, r#""version": 2"#);
+        assert_ne!(newer, text);
+        assert!(Document::from_json(&newer).is_err());
+
+        //documents from before the version was written
+        let unversioned = text.replace(r#""version": 1,"#, "");
+        assert_eq!(
+            Document::from_json(&unversioned).unwrap(),
+            Document::default()
+        );
+    }
+
+    #[test]
+    fn out_of_range() {
+        let text = with_gradient(
+            r##"{
+                "type": "linear",
+                "angle": -90,
+                "stops": [{"color": "#fff", "position": 2}, {"color": "#000", "position": -1}],
+                "adjustments": {"lightness": 5, "chroma": -5, "hue": 720, "temperature": 1e300},
+                "layers": {
+                    "pattern": {"kind": "grid", "opacity": 3, "scale": 100},
+                    "vignette": {"strength": -1, "size": 1},
+                    "grain": 1e38
+                }
+            }"##,
+        );
+        let gradient = Document::from_json(&text).unwrap().gradient;
+
+        assert_eq!(gradient.angle, 270.0);
+        assert_eq!(gradient.stops[0].position, Some(1.0));
+        assert_eq!(gradient.stops[1].position, Some(0.0));
+
+        let adjustments = gradient.adjustments;
+        assert_eq!(adjustments.lightness, 0.5);
+        assert_eq!(adjustments.chroma, -1.0);
+        assert_eq!(adjustments.hue, 180.0);
+        //too large for an f32, so left at the default
+        assert_eq!(adjustments.temperature, 0.0);
+
+        assert_eq!(gradient.pattern.opacity, 1.0);
+        assert_eq!(gradient.pattern.scale, 4.0);
+        assert_eq!(gradient.vignette.strength, 0.0);
+        assert_eq!(gradient.vignette.size, 0.95);
+        assert_eq!(gradient.grain, 1.0);
+
+        assert!(
+            Document::from_json(&with_gradient(r#"{"type": "linear", "angle": 1e999}"#)).is_err()
+        );
+    }
+
+    #[test]
+    fn active_member() {
+        let mut document = document();
+        document.collection.active = 7;
+        let loaded = Document::from_json(&document.to_json()).unwrap();
+        assert_eq!(loaded.collection.active, 1);
+    }
+}
@@ -0,0 +1,130 @@
+/* golden.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::path::PathBuf;
+
+use gtk::prelude::*;
+use gtk::{gdk, graphene, gsk};
+
+use crate::gradient::{ColorStop, Gradient};
+
+/// Writes the rendered images over the references instead of comparing,
+/// for changes meant to alter the output.
+const BLESS_VARIABLE: &str = "VIBRANT_BLESS";
+
+fn reference_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{name}.png"))
+}
+
+/// A horizontal ramp from red to blue, simple enough to check by hand.
+pub fn ramp() -> Gradient {
+    Gradient {
+        angle: 90.0,
+        stops: vec![
+            ColorStop::new(gdk::RGBA::RED),
+            ColorStop::new(gdk::RGBA::BLUE),
+        ],
+        ..Default::default()
+    }
+}
+
+/// Renders a node with cairo, like exports do. Nodes are built without a
+/// `gtk::Snapshot`, so tests don't need GTK initialized.
+pub fn render(node: impl AsRef<gsk::RenderNode>, bounds: &graphene::Rect) -> gdk::Texture {
+    let renderer = gsk::CairoRenderer::new();
+    renderer
+        .realize(None::<&gdk::Surface>)
+        .expect("could not realize the renderer");
+    let texture = renderer.render_texture(node, Some(bounds));
+    renderer.unrealize();
+
+    texture
+}
+
+/// The channels of every pixel at 16 bits, so 8 bit and 16 bit images
+/// compare alike.
+fn channels(texture: &gdk::Texture) -> Vec<u16> {
+    let mut downloader = gdk::TextureDownloader::new(texture);
+    downloader.set_format(gdk::MemoryFormat::R16g16b16a16);
+    let (data, stride) = downloader.download_bytes();
+
+    let row = texture.width() as usize * 8;
+    data.chunks(stride)
+        .take(texture.height() as usize)
+        .flat_map(|line| {
+            line[..row]
+                .chunks_exact(2)
+                .map(|value| u16::from_ne_bytes([value[0], value[1]]))
+        })
+        .collect()
+}
+
+/// Compares an image against the reference named `name`, allowing each
+/// channel to be off by `tolerance`, a fraction of its full range.
+///
+/// A missing reference is written from the image, to be checked and
+/// committed, and fails the test.
+pub fn assert_matches(texture: &gdk::Texture, name: &str, tolerance: f32) {
+    let path = reference_path(name);
+    let bless = std::env::var_os(BLESS_VARIABLE).is_some();
+    if bless || !path.exists() {
+        texture
+            .save_to_png(&path)
+            .expect("could not write the reference");
+        assert!(bless, "wrote a new reference to {}", path.display());
+        return;
+    }
+
+    let reference = gdk::Texture::from_filename(&path).expect("could not read the reference");
+    assert_eq!(
+        (texture.width(), texture.height()),
+        (reference.width(), reference.height()),
+        "{name} has the wrong size"
+    );
+
+    let tolerance = (tolerance * u16::MAX as f32).round() as u16;
+    let width = texture.width() as usize;
+    let (actual, expected) = (channels(texture), channels(&reference));
+    let differences: Vec<_> = actual
+        .chunks_exact(4)
+        .zip(expected.chunks_exact(4))
+        .enumerate()
+        .filter(|(_, (actual, expected))| {
+            actual
+                .iter()
+                .zip(expected.iter())
+                .any(|(a, b)| a.abs_diff(*b) > tolerance)
+        })
+        .collect();
+
+    if let Some((index, (actual, expected))) = differences.first() {
+        panic!(
+            "{} pixels of {name} differ from the reference, the first at {},{}: {:?} instead of {:?}",
+            differences.len(),
+            index % width,
+            index / width,
+            actual,
+            expected
+        );
+    }
+}
@@ -0,0 +1,1473 @@
+/* gradient.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::fmt::Write;
+
+use gtk::prelude::*;
+use gtk::{gdk, glib, graphene, gsk};
+
+use crate::code_style::CodeStyle;
+use crate::color::{self, GamutMapping};
+use crate::color_format::{self, ColorFormat};
+use crate::grain;
+use crate::pattern::Pattern;
+use crate::readability;
+use crate::stats;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GradientType {
+    Linear,
+    Radial,
+    Conic,
+}
+
+impl From<u32> for GradientType {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => GradientType::Radial,
+            2 => GradientType::Conic,
+            //default to Linear, including 0
+            _ => GradientType::Linear,
+        }
+    }
+}
+
+impl From<GradientType> for u32 {
+    fn from(value: GradientType) -> Self {
+        match value {
+            GradientType::Linear => 0,
+            GradientType::Radial => 1,
+            GradientType::Conic => 2,
+        }
+    }
+}
+
+impl GradientType {
+    /// The name used in saved documents.
+    pub fn name(self) -> &'static str {
+        match self {
+            GradientType::Linear => "linear",
+            GradientType::Radial => "radial",
+            GradientType::Conic => "conic",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "linear" => Some(GradientType::Linear),
+            "radial" => Some(GradientType::Radial),
+            "conic" => Some(GradientType::Conic),
+            _ => None,
+        }
+    }
+}
+
+/// Shifts applied to every stop at once, in OKLCH space.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct Adjustments {
+    /// Added to the lightness, from -1 to 1.
+    pub lightness: f32,
+    /// Relative change of the chroma, -1 removing all saturation.
+    pub chroma: f32,
+    /// Hue rotation in degrees.
+    pub hue: f32,
+    /// Shift toward orange when positive or blue when negative, from -1 to 1.
+    pub temperature: f32,
+    /// How colors pushed outside sRGB are brought back.
+    pub gamut_mapping: GamutMapping,
+}
+
+impl Adjustments {
+    /// Whether the colors are left as they are, whatever the gamut mapping.
+    pub fn is_identity(&self) -> bool {
+        *self
+            == Self {
+                gamut_mapping: self.gamut_mapping,
+                ..Self::default()
+            }
+    }
+
+    pub fn apply(&self, rgba: &gdk::RGBA) -> gdk::RGBA {
+        if self.is_identity() {
+            return *rgba;
+        }
+
+        self.apply_unmapped(rgba).to_rgba_mapped(self.gamut_mapping)
+    }
+
+    /// Whether adjusting the color pushes it outside sRGB.
+    pub fn is_out_of_gamut(&self, rgba: &gdk::RGBA) -> bool {
+        !self.is_identity() && !self.apply_unmapped(rgba).in_gamut()
+    }
+
+    /// The adjusted color, before it's brought back into sRGB.
+    pub fn apply_unmapped(&self, rgba: &gdk::RGBA) -> color::Oklab {
+        if self.is_identity() {
+            return color::Oklab::from(*rgba);
+        }
+
+        let mut lch = color::Oklch::from(*rgba);
+        lch.l = (lch.l + self.lightness).clamp(0.0, 1.0);
+        lch.c = (lch.c * (1.0 + self.chroma)).max(0.0);
+        lch.h = (lch.h + self.hue).rem_euclid(360.0);
+
+        //move along the OKLab orange-blue axis
+        let mut lab = color::Oklab::from(lch);
+        lab.a += self.temperature * 0.03;
+        lab.b += self.temperature * 0.08;
+        lab
+    }
+}
+
+/// Darkening toward the edges, drawn as a radial gradient over the others.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Vignette {
+    /// Opacity of the black at the corners, 0 disabling the vignette.
+    pub strength: f32,
+    /// Fraction of the radius left untouched around the center.
+    pub size: f32,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self {
+            strength: 0.0,
+            size: 0.5,
+        }
+    }
+}
+
+impl Vignette {
+    pub fn is_visible(&self) -> bool {
+        self.strength > 0.0
+    }
+
+    fn edge(&self) -> gdk::RGBA {
+        gdk::RGBA::new(0.0, 0.0, 0.0, self.strength)
+    }
+
+    pub fn css_layer(&self) -> Option<String> {
+        self.is_visible().then(|| {
+            format!(
+                "radial-gradient(transparent {}%, {})",
+                format_number(self.size * 100.0),
+                color::to_hex(&self.edge())
+            )
+        })
+    }
+
+    fn svg_stops(&self) -> String {
+        format!(
+            "<stop offset=\"{}\" stop-color=\"#000\" stop-opacity=\"0\"/><stop offset=\"1\" stop-color=\"#000\" stop-opacity=\"{}\"/>",
+            format_number(self.size),
+            self.strength
+        )
+    }
+
+    pub fn node(&self, bounds: &graphene::Rect) -> Option<gsk::RenderNode> {
+        if !self.is_visible() {
+            return None;
+        }
+
+        let stops = [
+            gsk::ColorStop::new(self.size, gdk::RGBA::TRANSPARENT),
+            gsk::ColorStop::new(1.0, self.edge()),
+        ];
+        let center = graphene::Point::new(
+            bounds.x() + bounds.width() / 2.0,
+            bounds.y() + bounds.height() / 2.0,
+        );
+        let hradius = bounds.width() / 2.0 * std::f32::consts::SQRT_2;
+        let vradius = bounds.height() / 2.0 * std::f32::consts::SQRT_2;
+
+        let node =
+            gsk::RadialGradientNode::new(bounds, &center, hradius, vradius, 0.0, 1.0, &stops);
+        Some(node.upcast())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ColorStop {
+    pub color: gdk::RGBA,
+    /// Position from 0 to 1 along the gradient, or the angle divided by 360
+    /// for conic gradients. Stops without one are spread evenly between
+    /// their neighbors, like CSS does.
+    pub position: Option<f32>,
+    /// Whether a change of hue to this stop turns the other linked stops by
+    /// as much, keeping the hue differences between them.
+    pub linked: bool,
+}
+
+impl ColorStop {
+    pub fn new(color: gdk::RGBA) -> Self {
+        Self {
+            color,
+            position: None,
+            linked: false,
+        }
+    }
+}
+
+/// How copied CSS is written, beyond the gradient itself.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CssOptions {
+    pub style: CodeStyle,
+    /// Writes angles along an axis as keywords, like `to right` for `90deg`.
+    pub angle_keywords: bool,
+    /// Writes diagonal angles as corner keywords, like `to bottom right` for
+    /// `135deg`. Those aim at the corner of the box, so they only match the
+    /// angle on squares.
+    pub corner_keywords: bool,
+    /// Precedes the background with a solid color and vendor prefixed
+    /// gradients for old browsers and email clients.
+    pub legacy_fallbacks: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    pub kind: GradientType,
+    /// Angle in CSS degrees, 0 pointing up and growing clockwise.
+    pub angle: f32,
+    pub stops: Vec<ColorStop>,
+    /// Whether each color fills its own segment without blending into the
+    /// next one, like a pie chart.
+    pub hard_stops: bool,
+    /// Whether the stops repeat backwards after the last one, from A to B
+    /// and back to A. Only the first half is stored and edited.
+    pub symmetric: bool,
+    /// Whether fully transparent stops take the color of their neighbors,
+    /// so blending into them fades out instead of darkening toward the
+    /// black of CSS `transparent`.
+    pub tint_transparent: bool,
+    pub adjustments: Adjustments,
+    pub pattern: Pattern,
+    pub vignette: Vignette,
+    /// Whether to darken or lighten the top when status bar text wouldn't
+    /// be readable over it.
+    pub readable_top: bool,
+    /// Strength of the noise drawn over everything, from 0 to 1.
+    pub grain: f32,
+    /// The gradient shown while the desktop prefers a dark style, when it
+    /// has its own variant for it.
+    pub dark: Option<Box<Gradient>>,
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Self {
+            kind: GradientType::Linear,
+            angle: 0.0,
+            stops: vec![
+                ColorStop::new(gdk::RGBA::parse("blue").unwrap()),
+                ColorStop::new(gdk::RGBA::parse("pink").unwrap()),
+            ],
+            hard_stops: false,
+            symmetric: false,
+            tint_transparent: false,
+            adjustments: Adjustments::default(),
+            pattern: Pattern::default(),
+            vignette: Vignette::default(),
+            readable_top: false,
+            grain: 0.0,
+            dark: None,
+        }
+    }
+}
+
+/// Fills in missing positions the way CSS does: the ends default to 0 and 1,
+/// positions never go backwards, and unset ones are spread evenly.
+fn resolve_positions(positions: &[Option<f32>]) -> Vec<f32> {
+    let Some(last) = positions.len().checked_sub(1) else {
+        return Vec::new();
+    };
+
+    let mut fixed: Vec<Option<f32>> = positions.to_vec();
+    fixed[0] = fixed[0].or(Some(0.0));
+    fixed[last] = fixed[last].or(Some(1.0));
+
+    let mut max = f32::MIN;
+    for position in fixed.iter_mut().flatten() {
+        *position = position.max(max);
+        max = *position;
+    }
+
+    let mut resolved = vec![0.0; fixed.len()];
+    let mut start = 0;
+    for (i, position) in fixed.iter().enumerate() {
+        let Some(position) = position else {
+            continue;
+        };
+
+        let from = resolved[start];
+        for (k, value) in resolved.iter_mut().enumerate().take(i).skip(start + 1) {
+            *value = from + (position - from) * (k - start) as f32 / (i - start) as f32;
+        }
+
+        resolved[i] = *position;
+        start = i;
+    }
+
+    resolved
+}
+
+/// Chroma in OKLCH below which a color has no hue to follow.
+const LINK_MIN_CHROMA: f32 = 0.01;
+
+/// CSS direction keywords, every 45 degrees clockwise from the top.
+pub const DIRECTION_KEYWORDS: [&str; 8] = [
+    "to top",
+    "to top right",
+    "to right",
+    "to bottom right",
+    "to bottom",
+    "to bottom left",
+    "to left",
+    "to top left",
+];
+
+/// The direction keyword closest to a CSS angle, and whether it is exactly
+/// that angle.
+pub fn nearest_direction(angle: f32) -> (&'static str, bool) {
+    let angle = angle.rem_euclid(360.0);
+    let index = (angle / 45.0).round() as usize % DIRECTION_KEYWORDS.len();
+
+    (DIRECTION_KEYWORDS[index], angle == index as f32 * 45.0)
+}
+
+/// An SVG rectangle covering the whole image.
+fn svg_rect(fill: &str) -> String {
+    format!("  <rect width=\"100%\" height=\"100%\" fill=\"{fill}\"/>\n")
+}
+
+fn format_number(value: f32) -> String {
+    let text = format!("{:.2}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_owned()
+}
+
+impl Gradient {
+    /// Formats a stop position in the unit CSS output uses for this gradient.
+    pub fn format_position(&self, position: f32) -> String {
+        match self.kind {
+            GradientType::Conic => format!("{}deg", format_number(position * 360.0)),
+            _ => format!("{}%", format_number(position * 100.0)),
+        }
+    }
+
+    /// Parses a position typed as a percentage, in degrees for conic
+    /// gradients or in pixels along a gradient line `length` long, with bare
+    /// numbers read in the unit of the gradient type. Positions past the
+    /// ends are clamped, and an empty text unsets the position.
+    pub fn parse_position(&self, text: &str, length: Option<f32>) -> Option<Option<f32>> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Some(None);
+        }
+
+        let conic = self.kind == GradientType::Conic;
+        let (number, scale) = if let Some(number) = text.strip_suffix("deg") {
+            (number, conic.then_some(360.0)?)
+        } else if let Some(number) = text.strip_suffix("px") {
+            (number, length.filter(|length| *length > 0.0)?)
+        } else if let Some(number) = text.strip_suffix('%') {
+            (number, 100.0)
+        } else if conic {
+            (text, 360.0)
+        } else {
+            (text, 100.0)
+        };
+
+        let value = number
+            .trim()
+            .parse::<f32>()
+            .ok()
+            .filter(|value| value.is_finite())?;
+        Some(Some((value / scale).clamp(0.0, 1.0)))
+    }
+
+    /// Length of the line stop positions are measured along, when the
+    /// gradient fills `width` by `height`. Conic gradients measure angles
+    /// instead.
+    pub fn line_length(&self, width: f32, height: f32) -> Option<f32> {
+        match self.kind {
+            GradientType::Linear => {
+                let (sin, cos) = self.angle.to_radians().sin_cos();
+                Some(width * sin.abs() + height * cos.abs())
+            }
+            //CSS measures ellipses along their horizontal radius
+            GradientType::Radial => Some(width / 2.0 * std::f32::consts::SQRT_2),
+            GradientType::Conic => None,
+        }
+    }
+
+    /// Final position of every stop.
+    pub fn positions(&self) -> Vec<f32> {
+        let positions: Vec<_> = self.stops.iter().map(|stop| stop.position).collect();
+        resolve_positions(&positions)
+    }
+
+    /// Start and end of the segment every color fills with hard stops. Each
+    /// stop's position marks where its segment starts.
+    pub fn segments(&self) -> Vec<(f32, f32)> {
+        let mut positions: Vec<_> = self.stops.iter().map(|stop| stop.position).collect();
+        positions.push(Some(1.0));
+
+        resolve_positions(&positions)
+            .windows(2)
+            .map(|window| (window[0], window[1]))
+            .collect()
+    }
+
+    /// How many colors the adjustments push outside sRGB.
+    pub fn out_of_gamut_stops(&self) -> usize {
+        self.stops
+            .iter()
+            .filter(|stop| self.adjustments.is_out_of_gamut(&stop.color))
+            .count()
+    }
+
+    /// A copy with the adjustments applied to the colors themselves, and
+    /// the second half of symmetric gradients spelled out.
+    pub fn adjusted(&self) -> Self {
+        let mut adjusted = Self {
+            stops: self
+                .stops
+                .iter()
+                .map(|stop| ColorStop {
+                    color: self.adjustments.apply(&stop.color),
+                    ..*stop
+                })
+                .collect(),
+            adjustments: Adjustments::default(),
+            dark: self.dark.as_ref().map(|dark| Box::new(dark.adjusted())),
+            ..self.clone()
+        };
+
+        if adjusted.symmetric {
+            adjusted.mirror();
+            adjusted.symmetric = false;
+        }
+        if adjusted.tint_transparent {
+            adjusted.tint_transparent_stops();
+            adjusted.tint_transparent = false;
+        }
+
+        adjusted
+    }
+
+    /// The variant for the light or dark style, falling back to this one.
+    pub fn variant(&self, dark: bool) -> &Gradient {
+        match &self.dark {
+            Some(variant) if dark => variant,
+            _ => self,
+        }
+    }
+
+    pub fn variant_mut(&mut self, dark: bool) -> &mut Gradient {
+        if dark && self.dark.is_some() {
+            self.dark.as_deref_mut().unwrap()
+        } else {
+            self
+        }
+    }
+
+    fn css_stops(&self) -> String {
+        self.css_stops_with(|color| ColorFormat::Hex.format(color))
+    }
+
+    fn css_stops_with(&self, format_color: impl Fn(&gdk::RGBA) -> String) -> String {
+        let stops: Vec<String> = if self.hard_stops {
+            self.stops
+                .iter()
+                .zip(self.segments())
+                .map(|(stop, (start, end))| {
+                    format!(
+                        "{} {} {}",
+                        format_color(&stop.color),
+                        self.format_position(start),
+                        self.format_position(end)
+                    )
+                })
+                .collect()
+        } else {
+            self.stops
+                .iter()
+                .map(|stop| match stop.position {
+                    Some(position) => {
+                        format!(
+                            "{} {}",
+                            format_color(&stop.color),
+                            self.format_position(position)
+                        )
+                    }
+                    None => format_color(&stop.color),
+                })
+                .collect()
+        };
+
+        stops.join(", ")
+    }
+
+    /// The gradient as a CSS `<image>` value.
+    pub fn to_css(&self) -> String {
+        self.css_image(self.css_stops(), &CssOptions::default())
+    }
+
+    /// The gradient with its adjustments applied in Display-P3, where colors
+    /// they push outside sRGB keep more of their saturation.
+    fn to_display_p3_css(&self, options: &CssOptions) -> String {
+        self.css_image(
+            self.css_stops_with(|color| {
+                color_format::display_p3(self.adjustments.apply_unmapped(color))
+            }),
+            options,
+        )
+    }
+
+    /// A copy with its colors as Display-P3 channels, adjusted in P3 like
+    /// the wide gamut CSS. Drawn as if it were sRGB, it gives an image to tag
+    /// as Display P3.
+    pub fn to_display_p3(&self) -> Self {
+        let p3 = |color: &gdk::RGBA, adjustments: &Adjustments| {
+            let lab = adjustments.apply_unmapped(color);
+            let [red, green, blue] = lab.to_display_p3();
+            gdk::RGBA::new(red, green, blue, lab.alpha)
+        };
+
+        Self {
+            stops: self
+                .stops
+                .iter()
+                .map(|stop| ColorStop {
+                    color: p3(&stop.color, &self.adjustments),
+                    ..*stop
+                })
+                .collect(),
+            adjustments: Adjustments::default(),
+            pattern: Pattern {
+                color: p3(&self.pattern.color, &Adjustments::default()),
+                ..self.pattern
+            },
+            dark: self
+                .dark
+                .as_ref()
+                .map(|dark| Box::new(dark.to_display_p3())),
+            ..self.clone()
+        }
+    }
+
+    /// The direction of a linear gradient.
+    fn css_direction(&self, options: &CssOptions) -> String {
+        let angle = self.angle.rem_euclid(360.0);
+
+        if angle.fract() == 0.0 && (angle as u32).is_multiple_of(45) {
+            let index = angle as usize / 45;
+            let enabled = if index.is_multiple_of(2) {
+                options.angle_keywords
+            } else {
+                options.corner_keywords
+            };
+            if enabled {
+                return DIRECTION_KEYWORDS[index].to_owned();
+            }
+        }
+
+        format!("{}deg", format_number(self.angle))
+    }
+
+    fn css_image(&self, stops: String, options: &CssOptions) -> String {
+        match self.kind {
+            GradientType::Linear => {
+                format!(
+                    "linear-gradient({}, {})",
+                    self.css_direction(options),
+                    stops
+                )
+            }
+            GradientType::Radial => format!("radial-gradient({})", stops),
+            GradientType::Conic => format!(
+                "conic-gradient(from {}deg, {})",
+                format_number(self.angle),
+                stops
+            ),
+        }
+    }
+
+    /// The overlay keeping the status bar readable, when enabled and needed.
+    /// CSS output has no size, so it is analyzed as a square.
+    fn top_band(&self, bounds: &graphene::Rect) -> Option<gdk::RGBA> {
+        self.readable_top
+            .then(|| readability::band_overlay(self, bounds))
+            .flatten()
+    }
+
+    /// The value of the CSS `background` property, with the grain, the top
+    /// band, the vignette and the pattern layered over the gradient.
+    pub fn to_background(&self) -> String {
+        self.background_with(self.to_css())
+    }
+
+    fn background_with(&self, image: String) -> String {
+        let layers: Vec<String> = grain::css_layer(self.grain)
+            .into_iter()
+            .chain(
+                self.top_band(&graphene::Rect::new(0.0, 0.0, 1.0, 1.0))
+                    .map(|overlay| readability::css_layer(&overlay)),
+            )
+            .chain(self.vignette.css_layer())
+            .chain(self.pattern.css_layers())
+            .chain([image])
+            .collect();
+
+        layers.join(", ")
+    }
+
+    /// Colors the way browsers from before CSS Color 4 read them.
+    fn legacy_color(&self, rgba: &gdk::RGBA) -> String {
+        if rgba.alpha() < 1.0 {
+            rgba.to_str().into()
+        } else {
+            color::to_hex(rgba)
+        }
+    }
+
+    /// The gradient in the vendor prefixed syntax of old browsers, where
+    /// angles start at the right and grow counterclockwise and stops take a
+    /// single position. Conic gradients were never prefixed.
+    fn legacy_css(&self, prefix: &str) -> Option<String> {
+        let stops: Vec<String> = if self.hard_stops {
+            self.stops
+                .iter()
+                .zip(self.segments())
+                .flat_map(|(stop, (start, end))| {
+                    let color = self.legacy_color(&stop.color);
+                    [
+                        format!("{color} {}", self.format_position(start)),
+                        format!("{color} {}", self.format_position(end)),
+                    ]
+                })
+                .collect()
+        } else {
+            self.stops
+                .iter()
+                .map(|stop| match stop.position {
+                    Some(position) => format!(
+                        "{} {}",
+                        self.legacy_color(&stop.color),
+                        self.format_position(position)
+                    ),
+                    None => self.legacy_color(&stop.color),
+                })
+                .collect()
+        };
+        let stops = stops.join(", ");
+
+        match self.kind {
+            GradientType::Linear => Some(format!(
+                "{prefix}linear-gradient({}deg, {stops})",
+                format_number((90.0 - self.angle).rem_euclid(360.0))
+            )),
+            GradientType::Radial => Some(format!("{prefix}radial-gradient({stops})")),
+            GradientType::Conic => None,
+        }
+    }
+
+    /// The mean of colors sampled along the gradient, for places that can
+    /// only show one.
+    pub fn average_color(&self) -> gdk::RGBA {
+        const SAMPLES: usize = 32;
+
+        let colors = self.sample(SAMPLES);
+        let mean = |channel: fn(&gdk::RGBA) -> f32| {
+            colors.iter().map(channel).sum::<f32>() / colors.len().max(1) as f32
+        };
+
+        gdk::RGBA::new(
+            mean(gdk::RGBA::red),
+            mean(gdk::RGBA::green),
+            mean(gdk::RGBA::blue),
+            mean(gdk::RGBA::alpha),
+        )
+    }
+
+    /// Declarations giving the background. With a variable prefix it goes
+    /// through a custom property, so overriding rules only change that.
+    fn background_declarations(
+        background: String,
+        options: &CssOptions,
+        first: bool,
+    ) -> Vec<String> {
+        let prefix = &options.style.variable_prefix;
+        if prefix.is_empty() {
+            return vec![format!("background: {background}")];
+        }
+
+        let mut declarations = vec![format!("--{prefix}background: {background}")];
+        if first {
+            declarations.push(format!("background: var(--{prefix}background)"));
+        }
+        declarations
+    }
+
+    /// A rule inside a media query, indented as the options ask.
+    fn media_rule(
+        query: &str,
+        selector: &str,
+        declarations: &[String],
+        options: &CssOptions,
+    ) -> String {
+        format!(
+            "\n@media {query} {{\n{}{}\n}}",
+            options.style.indentation(),
+            options.style.rule(selector, declarations)
+        )
+    }
+
+    /// A stylesheet giving `selector` this background, switching to the dark
+    /// variant through a media query. `light-dark()` only takes colors, so it
+    /// can't be used for images.
+    pub fn to_stylesheet(&self, selector: &str, options: &CssOptions) -> String {
+        let background = |gradient: &Gradient| {
+            let stops = gradient.css_stops_with(|color| options.style.format_color(color));
+            gradient.background_with(gradient.css_image(stops, options))
+        };
+
+        //browsers old enough to need these have no dark mode
+        let mut declarations = Vec::new();
+        if options.legacy_fallbacks {
+            declarations.push(format!(
+                "background: {}",
+                self.legacy_color(&self.average_color())
+            ));
+            for prefix in ["-webkit-", "-moz-"] {
+                if let Some(image) = self.legacy_css(prefix) {
+                    declarations.push(format!("background: {image}"));
+                }
+            }
+        }
+        declarations.extend(Self::background_declarations(
+            background(self),
+            options,
+            true,
+        ));
+        let mut css = options.style.rule(selector, &declarations);
+
+        if let Some(dark) = &self.dark {
+            css.push_str(&Self::media_rule(
+                "(prefers-color-scheme: dark)",
+                selector,
+                &Self::background_declarations(background(dark), options, false),
+                options,
+            ));
+        }
+
+        css
+    }
+
+    /// Like `to_stylesheet`, overriding the background with Display-P3 colors
+    /// on screens that can show them, while sRGB ones stay as the fallback.
+    /// Called before adjusting, since the adjustments are applied in P3.
+    pub fn to_wide_gamut_stylesheet(&self, selector: &str, options: &CssOptions) -> String {
+        let adjusted = self.adjusted();
+        let mut css = adjusted.to_stylesheet(selector, options);
+
+        css.push_str(&Self::media_rule(
+            "(color-gamut: p3)",
+            selector,
+            &Self::background_declarations(
+                adjusted.background_with(self.to_display_p3_css(options)),
+                options,
+                false,
+            ),
+            options,
+        ));
+        if let (Some(dark), Some(adjusted_dark)) = (&self.dark, &adjusted.dark) {
+            css.push_str(&Self::media_rule(
+                "(color-gamut: p3) and (prefers-color-scheme: dark)",
+                selector,
+                &Self::background_declarations(
+                    adjusted_dark.background_with(dark.to_display_p3_css(options)),
+                    options,
+                    false,
+                ),
+                options,
+            ));
+        }
+
+        css
+    }
+
+    /// Inserts stops interpolated in OKLab between each pair of colors, more
+    /// of them the further apart the colors are. Every pair gets the same
+    /// number of stops so the existing ones keep their place.
+    pub fn smooth(&mut self) {
+        //perceptual distance covered by each inserted step
+        const STEP: f32 = 0.08;
+        const MAX_STEPS: usize = 4;
+
+        let steps = self
+            .stops
+            .windows(2)
+            .map(|pair| {
+                ((color::delta_e(&pair[0].color, &pair[1].color) / STEP).ceil() as usize)
+                    .saturating_sub(1)
+            })
+            .max()
+            .unwrap_or(0)
+            .min(MAX_STEPS);
+
+        let mut stops = Vec::new();
+        for pair in self.stops.windows(2) {
+            let [from, to] = pair else {
+                continue;
+            };
+
+            stops.push(*from);
+            for step in 1..=steps {
+                let t = step as f32 / (steps + 1) as f32;
+                stops.push(ColorStop::new(color::mix_oklab(&from.color, &to.color, t)));
+            }
+        }
+
+        if let Some(last) = self.stops.last() {
+            stops.push(*last);
+        }
+        self.stops = stops;
+    }
+
+    /// Replaces the color of every stop, in both variants, with the closest
+    /// of `colors` while keeping its opacity. Returns whether any changed.
+    pub fn snap_to_colors(&mut self, colors: &[gdk::RGBA]) -> bool {
+        let mut changed = false;
+        for stop in &mut self.stops {
+            let Some(nearest) = colors.iter().min_by(|a, b| {
+                color::delta_e(&stop.color, a).total_cmp(&color::delta_e(&stop.color, b))
+            }) else {
+                return false;
+            };
+
+            let snapped = nearest.with_alpha(stop.color.alpha());
+            if snapped != stop.color {
+                stop.color = snapped;
+                changed = true;
+            }
+        }
+
+        if let Some(dark) = &mut self.dark {
+            changed |= dark.snap_to_colors(colors);
+        }
+        changed
+    }
+
+    /// Inserts stops interpolated in OKLCH between the pairs of colors that
+    /// blend through gray in sRGB. Every stop gets the position it already
+    /// had, so the inserted ones don't push the others around.
+    pub fn fix_muddy_midpoints(&mut self) {
+        const STEPS: usize = 3;
+
+        let muddy = |pair: &[ColorStop]| stats::is_muddy(&pair[0].color, &pair[1].color);
+        if self.hard_stops || !self.stops.windows(2).any(muddy) {
+            return;
+        }
+
+        let positions = self.positions();
+        let mut stops = Vec::new();
+        for (i, pair) in self.stops.windows(2).enumerate() {
+            stops.push(ColorStop {
+                position: Some(positions[i]),
+                ..pair[0]
+            });
+            if !muddy(pair) {
+                continue;
+            }
+
+            let span = positions[i + 1] - positions[i];
+            for step in 1..=STEPS {
+                let t = step as f32 / (STEPS + 1) as f32;
+                stops.push(ColorStop {
+                    position: Some(positions[i] + span * t),
+                    ..ColorStop::new(color::mix_oklch(&pair[0].color, &pair[1].color, t))
+                });
+            }
+        }
+
+        if let (Some(last), Some(position)) = (self.stops.last(), positions.last()) {
+            stops.push(ColorStop {
+                position: Some(*position),
+                ..*last
+            });
+        }
+        self.stops = stops;
+    }
+
+    /// Swaps the colors of the first and last stops, keeping positions.
+    pub fn swap_ends(&mut self) {
+        if let Some(last) = self.stops.len().checked_sub(1) {
+            let first = self.stops[0].color;
+            self.stops[0].color = self.stops[last].color;
+            self.stops[last].color = first;
+        }
+    }
+
+    /// Spreads the stops evenly, leaving their positions to CSS.
+    pub fn distribute(&mut self) {
+        for stop in &mut self.stops {
+            stop.position = None;
+        }
+    }
+
+    /// Fixes every stop at its current position, rounded to the nearest 5%.
+    pub fn snap(&mut self) {
+        let positions = self.positions();
+        for (stop, position) in self.stops.iter_mut().zip(positions) {
+            stop.position = Some((position * 20.0).round() / 20.0);
+        }
+    }
+
+    /// Follows the colors with themselves in reverse, squeezing both halves
+    /// into the gradient so it ends where it started.
+    pub fn mirror(&mut self) {
+        let positions = self.positions();
+        let first_half = self
+            .stops
+            .iter()
+            .zip(&positions)
+            .map(|(stop, position)| ColorStop {
+                position: Some(position / 2.0),
+                ..*stop
+            });
+        //the last stop sits in the middle once, not twice
+        let second_half =
+            self.stops
+                .iter()
+                .zip(&positions)
+                .rev()
+                .skip(1)
+                .map(|(stop, position)| ColorStop {
+                    position: Some(1.0 - position / 2.0),
+                    ..*stop
+                });
+
+        self.stops = first_half.chain(second_half).collect();
+    }
+
+    /// Whether any stop is fully transparent.
+    pub fn has_transparent_stops(&self) -> bool {
+        self.stops.iter().any(|stop| stop.color.alpha() == 0.0)
+    }
+
+    /// Gives fully transparent stops the color of their neighbors with no
+    /// alpha. A stop between two different colors becomes one for each
+    /// side, at the same position. Hard stops don't blend, so they are left
+    /// alone.
+    fn tint_transparent_stops(&mut self) {
+        if self.hard_stops {
+            return;
+        }
+
+        let positions = self.positions();
+        let neighbor = |index: Option<usize>| {
+            index
+                .and_then(|index| self.stops.get(index))
+                .filter(|stop| stop.color.alpha() > 0.0)
+                .map(|stop| stop.color.with_alpha(0.0))
+        };
+
+        let mut stops = Vec::with_capacity(self.stops.len());
+        for (i, stop) in self.stops.iter().enumerate() {
+            if stop.color.alpha() > 0.0 {
+                stops.push(*stop);
+                continue;
+            }
+
+            let tinted = |color: gdk::RGBA| ColorStop {
+                color,
+                position: Some(positions[i]),
+                ..*stop
+            };
+            match (neighbor(i.checked_sub(1)), neighbor(Some(i + 1))) {
+                (Some(before), Some(after)) if before != after => {
+                    stops.push(tinted(before));
+                    stops.push(tinted(after));
+                }
+                (Some(color), _) | (None, Some(color)) => stops.push(tinted(color)),
+                (None, None) => stops.push(*stop),
+            }
+        }
+
+        self.stops = stops;
+    }
+
+    /// Sets the color of a stop. When it is linked, the other linked stops
+    /// turn by the same change of hue in OKLCH, unless one of the colors is
+    /// too gray to have a meaningful hue.
+    pub fn set_stop_color(&mut self, index: usize, color: gdk::RGBA) {
+        let Some(stop) = self.stops.get(index).copied() else {
+            return;
+        };
+
+        let from = color::Oklch::from(stop.color);
+        let to = color::Oklch::from(color);
+        if stop.linked && from.c > LINK_MIN_CHROMA && to.c > LINK_MIN_CHROMA {
+            let delta = to.h - from.h;
+            for (i, other) in self.stops.iter_mut().enumerate() {
+                if i != index && other.linked {
+                    let mut oklch = color::Oklch::from(other.color);
+                    oklch.h = (oklch.h + delta).rem_euclid(360.0);
+                    other.color = oklch.to_rgba();
+                }
+            }
+        }
+
+        self.stops[index].color = color;
+    }
+
+    pub fn shuffle(&mut self) {
+        //Fisher-Yates, using GLib's random generator
+        for i in (1..self.stops.len()).rev() {
+            let j = glib::random_int_range(0, i as i32 + 1) as usize;
+            let color = self.stops[i].color;
+            self.stops[i].color = self.stops[j].color;
+            self.stops[j].color = color;
+        }
+    }
+
+    fn color_stops(&self) -> Vec<gsk::ColorStop> {
+        if self.hard_stops {
+            return self
+                .stops
+                .iter()
+                .zip(self.segments())
+                .flat_map(|(stop, (start, end))| {
+                    [
+                        gsk::ColorStop::new(start, stop.color),
+                        gsk::ColorStop::new(end, stop.color),
+                    ]
+                })
+                .collect();
+        }
+
+        self.stops
+            .iter()
+            .zip(self.positions())
+            .map(|(stop, position)| gsk::ColorStop::new(position, stop.color))
+            .collect()
+    }
+
+    /// The gradient filling `bounds` with its layers, following the same
+    /// geometry CSS uses so renders match the preview.
+    pub fn node(&self, bounds: &graphene::Rect) -> Option<gsk::RenderNode> {
+        let layers: Vec<_> = [
+            self.gradient_node(bounds),
+            self.pattern.node(bounds),
+            self.vignette.node(bounds),
+            self.top_band(bounds)
+                .map(|overlay| readability::node(bounds, &overlay)),
+            grain::node(bounds, self.grain),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        match layers.len() {
+            0 => None,
+            1 => layers.into_iter().next(),
+            _ => Some(gsk::ContainerNode::new(&layers).upcast()),
+        }
+    }
+
+    fn gradient_node(&self, bounds: &graphene::Rect) -> Option<gsk::RenderNode> {
+        //GSK gradients need at least two stops
+        if self.stops.len() < 2 {
+            let stop = self.stops.first()?;
+            return Some(gsk::ColorNode::new(&stop.color, bounds).upcast());
+        }
+        let stops = self.color_stops();
+        let center = graphene::Point::new(
+            bounds.x() + bounds.width() / 2.0,
+            bounds.y() + bounds.height() / 2.0,
+        );
+
+        let node = match self.kind {
+            GradientType::Linear => {
+                let radians = self.angle.to_radians();
+                let (sin, cos) = radians.sin_cos();
+                //length of the gradient line, so corners get the first and last colors
+                let half = (bounds.width() * sin.abs() + bounds.height() * cos.abs()) / 2.0;
+
+                let start = graphene::Point::new(center.x() - sin * half, center.y() + cos * half);
+                let end = graphene::Point::new(center.x() + sin * half, center.y() - cos * half);
+
+                gsk::LinearGradientNode::new(bounds, &start, &end, &stops).upcast()
+            }
+            GradientType::Radial => {
+                //CSS defaults to an ellipse reaching the farthest corner
+                let hradius = bounds.width() / 2.0 * std::f32::consts::SQRT_2;
+                let vradius = bounds.height() / 2.0 * std::f32::consts::SQRT_2;
+
+                gsk::RadialGradientNode::new(bounds, &center, hradius, vradius, 0.0, 1.0, &stops)
+                    .upcast()
+            }
+            GradientType::Conic => {
+                gsk::ConicGradientNode::new(bounds, &center, self.angle, &stops).upcast()
+            }
+        };
+
+        Some(node)
+    }
+
+    /// Color of the gradient alone at a point of `bounds`, following the
+    /// geometry `snapshot` draws with.
+    pub fn color_at_point(&self, point: &graphene::Point, bounds: &graphene::Rect) -> gdk::RGBA {
+        self.color_at(self.offset_at_point(point, bounds))
+    }
+
+    /// Offset along the gradient line of a point of `bounds`, from 0 to 1.
+    pub fn offset_at_point(&self, point: &graphene::Point, bounds: &graphene::Rect) -> f32 {
+        let dx = point.x() - (bounds.x() + bounds.width() / 2.0);
+        let dy = point.y() - (bounds.y() + bounds.height() / 2.0);
+
+        let offset = match self.kind {
+            GradientType::Linear => {
+                let (sin, cos) = self.angle.to_radians().sin_cos();
+                let half = (bounds.width() * sin.abs() + bounds.height() * cos.abs()) / 2.0;
+                ((dx * sin - dy * cos) / half.max(f32::EPSILON) + 1.0) / 2.0
+            }
+            GradientType::Radial => {
+                let hradius = bounds.width() / 2.0 * std::f32::consts::SQRT_2;
+                let vradius = bounds.height() / 2.0 * std::f32::consts::SQRT_2;
+                (dx / hradius).hypot(dy / vradius)
+            }
+            GradientType::Conic => {
+                let angle = dx.atan2(-dy).to_degrees() - self.angle;
+                angle.rem_euclid(360.0) / 360.0
+            }
+        };
+
+        offset.clamp(0.0, 1.0)
+    }
+
+    /// The offset along the stored stops of an offset along the drawn
+    /// gradient, which repeats them backwards when symmetric.
+    pub fn stored_offset(&self, offset: f32) -> f32 {
+        if self.symmetric {
+            1.0 - (offset * 2.0 - 1.0).abs()
+        } else {
+            offset
+        }
+    }
+
+    /// Where an offset along the stored stops is first drawn.
+    pub fn drawn_offset(&self, offset: f32) -> f32 {
+        if self.symmetric {
+            offset / 2.0
+        } else {
+            offset
+        }
+    }
+
+    /// The stop closest to an offset along the drawn gradient.
+    pub fn nearest_stop(&self, offset: f32) -> Option<usize> {
+        let offset = self.stored_offset(offset);
+        self.positions()
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| (*a - offset).abs().total_cmp(&(*b - offset).abs()))
+            .map(|(i, _)| i)
+    }
+
+    /// Inserts a stop with the color the gradient has at an offset along
+    /// the drawn gradient, returning its index. Every stop gets the position
+    /// it already had, so the new one doesn't push the others around.
+    pub fn insert_stop_at(&mut self, offset: f32) -> usize {
+        let offset = self.stored_offset(offset);
+        let color = self.color_at(offset);
+
+        let positions = self.positions();
+        for (stop, position) in self.stops.iter_mut().zip(&positions) {
+            stop.position = Some(*position);
+        }
+
+        let index = positions
+            .iter()
+            .position(|position| *position > offset)
+            .unwrap_or(self.stops.len());
+        self.stops.insert(
+            index,
+            ColorStop {
+                position: Some(offset),
+                ..ColorStop::new(color)
+            },
+        );
+        index
+    }
+
+    /// Colors at `count` evenly spaced points along the gradient line, from
+    /// one end to the other. Conic gradients wrap around, so their last
+    /// point stops short of the first.
+    pub fn sample(&self, count: usize) -> Vec<gdk::RGBA> {
+        let steps = if self.kind == GradientType::Conic {
+            count
+        } else {
+            count.saturating_sub(1)
+        };
+
+        (0..count)
+            .map(|i| self.color_at(i as f32 / steps.max(1) as f32))
+            .collect()
+    }
+
+    /// Like `sample`, but spacing the points by the perceptual distance
+    /// between their colors instead of along the gradient line, so stretches
+    /// that barely change get fewer of them.
+    pub fn sample_perceptual(&self, count: usize) -> Vec<gdk::RGBA> {
+        //fine enough to follow every stop
+        const STEPS: usize = 512;
+
+        let dense = self.sample(STEPS + 1);
+        let mut distances = vec![0.0];
+        for pair in dense.windows(2) {
+            let last = distances.last().copied().unwrap_or(0.0);
+            distances.push(last + color::delta_e(&pair[0], &pair[1]));
+        }
+        let total = distances.last().copied().unwrap_or(0.0);
+        if total <= f32::EPSILON {
+            return self.sample(count);
+        }
+
+        let steps = if self.kind == GradientType::Conic {
+            count
+        } else {
+            count.saturating_sub(1)
+        };
+        (0..count)
+            .map(|i| {
+                let target = total * i as f32 / steps.max(1) as f32;
+                let index = distances.partition_point(|distance| *distance < target);
+                dense[index.min(STEPS)]
+            })
+            .collect()
+    }
+
+    /// Color at `offset` along the gradient line, interpolated in sRGB.
+    fn color_at(&self, offset: f32) -> gdk::RGBA {
+        let stops = self.color_stops();
+        let Some(first) = stops.first() else {
+            return gdk::RGBA::TRANSPARENT;
+        };
+
+        let mut previous = first;
+        for stop in &stops {
+            if stop.offset() >= offset {
+                let span = stop.offset() - previous.offset();
+                if span <= 0.0 {
+                    return *stop.color();
+                }
+
+                let t = (offset - previous.offset()) / span;
+                let (from, to) = (previous.color(), stop.color());
+                return gdk::RGBA::new(
+                    from.red() + (to.red() - from.red()) * t,
+                    from.green() + (to.green() - from.green()) * t,
+                    from.blue() + (to.blue() - from.blue()) * t,
+                    from.alpha() + (to.alpha() - from.alpha()) * t,
+                );
+            }
+            previous = stop;
+        }
+
+        *previous.color()
+    }
+
+    fn svg_stops(&self) -> String {
+        self.color_stops()
+            .iter()
+            .map(|stop| {
+                format!(
+                    "<stop offset=\"{}\" stop-color=\"{}\"/>",
+                    format_number(stop.offset()),
+                    color::to_hex(stop.color())
+                )
+            })
+            .collect()
+    }
+
+    /// The gradient as an SVG document. SVG has no conic gradients, so those
+    /// are approximated with thin wedges.
+    pub fn to_svg(&self, width: f32, height: f32) -> String {
+        //wedges drawn for conic gradients
+        const WEDGES: usize = 360;
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" viewBox=\"0 0 {width} {height}\">\n"
+        );
+        let (cx, cy) = (width / 2.0, height / 2.0);
+
+        match self.kind {
+            _ if self.stops.len() < 2 => {
+                let fill = self
+                    .stops
+                    .first()
+                    .map(|stop| color::to_hex(&stop.color))
+                    .unwrap_or_else(|| "none".to_owned());
+                svg.push_str(&svg_rect(&fill));
+            }
+            GradientType::Linear => {
+                let (sin, cos) = self.angle.to_radians().sin_cos();
+                let half = (width * sin.abs() + height * cos.abs()) / 2.0;
+
+                let _ = writeln!(
+                    svg,
+                    "  <linearGradient id=\"gradient\" gradientUnits=\"userSpaceOnUse\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\">{}</linearGradient>",
+                    cx - sin * half,
+                    cy + cos * half,
+                    cx + sin * half,
+                    cy - cos * half,
+                    self.svg_stops()
+                );
+                svg.push_str(&svg_rect("url(#gradient)"));
+            }
+            GradientType::Radial => {
+                //bounding box units stretch the circle into the CSS ellipse
+                let _ = writeln!(
+                    svg,
+                    "  <radialGradient id=\"gradient\" cx=\"0.5\" cy=\"0.5\" r=\"{}\">{}</radialGradient>",
+                    std::f32::consts::FRAC_1_SQRT_2,
+                    self.svg_stops()
+                );
+                svg.push_str(&svg_rect("url(#gradient)"));
+            }
+            GradientType::Conic => {
+                let radius = width.hypot(height);
+                let point = |turn: f32| {
+                    let angle = (self.angle + turn * 360.0).to_radians();
+                    (cx + radius * angle.sin(), cy - radius * angle.cos())
+                };
+
+                for i in 0..WEDGES {
+                    let (start, end) = (i as f32 / WEDGES as f32, (i + 1) as f32 / WEDGES as f32);
+                    let fill = color::to_hex(&self.color_at((start + end) / 2.0));
+                    let (x0, y0) = point(start);
+                    let (x1, y1) = point(end);
+
+                    //the stroke hides seams between neighboring wedges
+                    let _ = writeln!(
+                        svg,
+                        "  <path d=\"M{cx},{cy} L{x0:.2},{y0:.2} L{x1:.2},{y1:.2} Z\" fill=\"{fill}\" stroke=\"{fill}\" stroke-width=\"0.5\"/>"
+                    );
+                }
+            }
+        }
+
+        if self.pattern.is_visible() {
+            let size = self.pattern.tile_size();
+            let _ = writeln!(
+                svg,
+                "  <pattern id=\"pattern\" patternUnits=\"userSpaceOnUse\" width=\"{size}\" height=\"{size}\">{}</pattern>",
+                self.pattern.svg_shapes()
+            );
+            svg.push_str(&svg_rect("url(#pattern)"));
+        }
+
+        if self.vignette.is_visible() {
+            let _ = writeln!(
+                svg,
+                "  <radialGradient id=\"vignette\" cx=\"0.5\" cy=\"0.5\" r=\"{}\">{}</radialGradient>",
+                std::f32::consts::FRAC_1_SQRT_2,
+                self.vignette.svg_stops()
+            );
+            svg.push_str(&svg_rect("url(#vignette)"));
+        }
+
+        if let Some(overlay) = self.top_band(&graphene::Rect::new(0.0, 0.0, width, height)) {
+            let _ = writeln!(svg, "  {}", readability::svg_gradient(&overlay));
+            svg.push_str(&svg_rect("url(#band)"));
+        }
+
+        //turbulence keeps the noise resolution independent
+        if self.grain > 0.0 {
+            let _ = writeln!(
+                svg,
+                "  {}\n  {}",
+                grain::svg_filter(),
+                grain::svg_layer(self.grain)
+            );
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// An HTML email block with the gradient as background: CSS for most
+    /// clients, and a VML fill inside conditional comments for Outlook on
+    /// Windows, which ignores CSS backgrounds. VML only draws linear
+    /// gradients, so the others get a solid fill of their middle color
+    /// there, which is also the fallback for clients without either.
+    pub fn to_outlook_html(&self, width: u32, height: u32) -> String {
+        let middle = color::to_hex(&self.color_at(0.5));
+
+        let fill = match self.kind {
+            GradientType::Linear if self.stops.len() > 1 => {
+                let colors: Vec<String> = self
+                    .color_stops()
+                    .iter()
+                    .map(|stop| {
+                        format!(
+                            "{} {}",
+                            format_number(stop.offset()),
+                            color::to_hex(stop.color())
+                        )
+                    })
+                    .collect();
+                let first = color::to_hex(&self.stops[0].color);
+                let last = color::to_hex(&self.stops[self.stops.len() - 1].color);
+
+                //VML angles start pointing down and go counterclockwise
+                format!(
+                    "<v:fill type=\"gradient\" method=\"linear\" angle=\"{}\" color=\"{first}\" color2=\"{last}\" colors=\"{}\"/>",
+                    format_number((180.0 - self.angle).rem_euclid(360.0)),
+                    colors.join(", ")
+                )
+            }
+            _ => format!("<v:fill type=\"solid\" color=\"{middle}\"/>"),
+        };
+
+        format!(
+            r#"<table role="presentation" width="{width}" cellpadding="0" cellspacing="0" border="0">
+  <tr>
+    <td width="{width}" height="{height}" valign="top" bgcolor="{middle}" style="background-color: {middle}; background-image: {css};">
+      <!--[if gte mso 9]>
+      <v:rect xmlns:v="urn:schemas-microsoft-com:vml" fill="true" stroke="false" style="width: {width}px; height: {height}px;">
+        {fill}
+        <v:textbox inset="0,0,0,0">
+      <![endif]-->
+      <div>
+      </div>
+      <!--[if gte mso 9]>
+        </v:textbox>
+      </v:rect>
+      <![endif]-->
+    </td>
+  </tr>
+</table>
+"#,
+            css = self.to_css()
+        )
+    }
+}
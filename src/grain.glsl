@@ -0,0 +1,21 @@
+// grain.glsl
+//
+// Gray noise for the grain layer of the previews, drawn as a GskGLShader
+// so large previews don't redraw the noise on the CPU.
+//
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+uniform float u_opacity;
+
+// A hash of the pixel position, from "Hash without Sine" by Dave Hoskins
+float hash(vec2 p) {
+  vec3 p3 = fract(vec3(p.xyx) * 0.1031);
+  p3 += dot(p3, p3.yzx + 33.33);
+  return fract((p3.x + p3.y) * p3.z);
+}
+
+void mainImage(out vec4 fragColor, in vec2 fragCoord, in vec2 resolution, in vec2 uv) {
+  float value = hash(floor(fragCoord));
+  // premultiplied, like every GSK output
+  fragColor = vec4(vec3(value), 1.0) * u_opacity;
+}
@@ -0,0 +1,174 @@
+/* grain.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::cell::OnceCell;
+
+use gtk::prelude::*;
+use gtk::{cairo, graphene, gsk};
+
+use crate::pattern;
+
+/// Size of the noise tile used for CSS and raster output, in pixels.
+const TILE_SIZE: i32 = 128;
+
+/// Strongest grain, as the opacity of the noise layer.
+const MAX_OPACITY: f32 = 0.5;
+
+/// Seed of the noise, fixed so exports come out the same every time.
+const SEED: u32 = 1;
+
+/// A desaturated `feTurbulence` filter, using single quotes so it can be
+/// embedded in CSS data URIs.
+pub fn svg_filter() -> String {
+    "<filter id='grain' x='0' y='0' width='100%' height='100%'>\
+     <feTurbulence type='fractalNoise' baseFrequency='0.8' numOctaves='3' stitchTiles='stitch'/>\
+     <feColorMatrix type='saturate' values='0'/></filter>"
+        .to_owned()
+}
+
+/// An SVG rectangle covering the image, filled with noise.
+pub fn svg_layer(amount: f32) -> String {
+    format!(
+        "<rect width='100%' height='100%' filter='url(#grain)' opacity='{}'/>",
+        amount * MAX_OPACITY
+    )
+}
+
+/// The grain as a CSS background layer, an SVG noise tile embedded as a
+/// data URI.
+pub fn css_layer(amount: f32) -> Option<String> {
+    if amount <= 0.0 {
+        return None;
+    }
+
+    let svg = format!(
+        "<svg xmlns='http://www.w3.org/2000/svg' width='{TILE_SIZE}' height='{TILE_SIZE}'>{}{}</svg>",
+        svg_filter(),
+        svg_layer(amount)
+    );
+    Some(format!(
+        "{} 0 0 / {TILE_SIZE}px {TILE_SIZE}px",
+        pattern::svg_url(&svg)
+    ))
+}
+
+thread_local! {
+    static TILE: OnceCell<Option<cairo::ImageSurface>> = const { OnceCell::new() };
+    static SHADER: OnceCell<gsk::GLShader> = const { OnceCell::new() };
+}
+
+/// A tile of gray noise, from a xorshift generator started at `seed`.
+fn make_tile(seed: u32) -> Option<cairo::ImageSurface> {
+    let mut tile = cairo::ImageSurface::create(cairo::Format::Rgb24, TILE_SIZE, TILE_SIZE).ok()?;
+
+    //zero would only ever give zeros
+    let mut state = seed.max(1);
+    if let Ok(mut data) = tile.data() {
+        for pixel in data.chunks_exact_mut(4) {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            pixel[..3].fill((state >> 24) as u8);
+        }
+    }
+
+    Some(tile)
+}
+
+/// The noise tile, made once so redraws don't shimmer.
+fn tile() -> Option<cairo::ImageSurface> {
+    TILE.with(|tile| tile.get_or_init(|| make_tile(SEED)).clone())
+}
+
+/// Gray noise over `bounds`, repeating a single tile.
+pub fn node(bounds: &graphene::Rect, amount: f32) -> Option<gsk::RenderNode> {
+    if amount <= 0.0 {
+        return None;
+    }
+
+    let tile = tile()?;
+    let node = gsk::CairoNode::new(bounds);
+    let cr = node.draw_context();
+    cr.translate(bounds.x() as f64, bounds.y() as f64);
+
+    let pattern = cairo::SurfacePattern::create(&tile);
+    pattern.set_extend(cairo::Extend::Repeat);
+    let _ = cr.set_source(&pattern);
+    let _ = cr.paint_with_alpha((amount * MAX_OPACITY) as f64);
+    drop(cr);
+
+    Some(node.upcast())
+}
+
+/// Draws the noise with a shader when `renderer` can run it, and with cairo
+/// otherwise.
+pub fn snapshot_with(
+    snapshot: &gtk::Snapshot,
+    bounds: &graphene::Rect,
+    amount: f32,
+    renderer: &gsk::Renderer,
+) {
+    if amount <= 0.0 {
+        return;
+    }
+
+    let shader = SHADER.with(|shader| {
+        shader
+            .get_or_init(|| gsk::GLShader::from_resource("/com/felipekinoshita/Vibrant/grain.glsl"))
+            .clone()
+    });
+
+    //compiling is cached by the renderer, and fails on those without GL
+    if shader.compile(renderer).is_err() {
+        if let Some(node) = node(bounds, amount) {
+            snapshot.append_node(node);
+        }
+        return;
+    }
+
+    let args = gsk::ShaderArgsBuilder::new(&shader, None);
+    args.set_float(0, amount * MAX_OPACITY);
+    snapshot.push_gl_shader(&shader, bounds, args.to_args());
+    snapshot.pop();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden;
+
+    #[test]
+    fn cairo_noise() {
+        let bounds = graphene::Rect::new(0.0, 0.0, 160.0, 16.0);
+        let node = gsk::ContainerNode::new(&[
+            gsk::ColorNode::new(&gtk::gdk::RGBA::BLACK, &bounds).upcast(),
+            node(&bounds, 1.0).unwrap(),
+        ]);
+
+        golden::assert_matches(&golden::render(&node, &bounds), "grain", 2.0 / 255.0);
+    }
+
+    #[test]
+    fn seeded_tile() {
+        let data = |seed| make_tile(seed).unwrap().take_data().unwrap().to_vec();
+        assert_eq!(data(7), data(7));
+        assert_ne!(data(7), data(8));
+    }
+}
@@ -0,0 +1,92 @@
+/* grain_layer.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::cell::Cell;
+
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{glib, graphene};
+
+use crate::grain;
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct VibrantGrainLayer {
+        pub amount: Cell<f32>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for VibrantGrainLayer {
+        const NAME: &'static str = "VibrantGrainLayer";
+        type Type = super::VibrantGrainLayer;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_css_name("grain");
+        }
+    }
+
+    impl ObjectImpl for VibrantGrainLayer {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            obj.set_hexpand(true);
+            obj.set_vexpand(true);
+            obj.set_can_target(false);
+        }
+    }
+
+    impl WidgetImpl for VibrantGrainLayer {
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let widget = self.obj();
+            let Some(native) = widget.native() else {
+                return;
+            };
+
+            let bounds =
+                graphene::Rect::new(0.0, 0.0, widget.width() as f32, widget.height() as f32);
+            grain::snapshot_with(snapshot, &bounds, self.amount.get(), &native.renderer());
+        }
+    }
+}
+
+glib::wrapper! {
+    /// The grain of a preview, drawn over its background on the GPU when
+    /// possible instead of as a CSS image.
+    pub struct VibrantGrainLayer(ObjectSubclass<imp::VibrantGrainLayer>)
+        @extends gtk::Widget;
+}
+
+impl Default for VibrantGrainLayer {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl VibrantGrainLayer {
+    pub fn set_amount(&self, amount: f32) {
+        if self.imp().amount.replace(amount) != amount {
+            self.queue_draw();
+        }
+    }
+}
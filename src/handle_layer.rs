@@ -0,0 +1,137 @@
+/* handle_layer.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::cell::{Cell, RefCell};
+use std::f64::consts::PI;
+
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{cairo, gdk, glib, graphene};
+
+use crate::gradient::Gradient;
+use crate::handles::{self, Handle, Zoom};
+
+fn set_source(cr: &cairo::Context, rgba: &gdk::RGBA) {
+    cr.set_source_rgba(
+        rgba.red() as f64,
+        rgba.green() as f64,
+        rgba.blue() as f64,
+        rgba.alpha() as f64,
+    );
+}
+
+/// Strokes the current path twice, dark under light, so it shows over any
+/// color.
+fn stroke_outlined(cr: &cairo::Context, width: f64) {
+    cr.set_source_rgba(0.0, 0.0, 0.0, 0.5);
+    cr.set_line_width(width + 2.0);
+    let _ = cr.stroke_preserve();
+    cr.set_source_rgb(1.0, 1.0, 1.0);
+    cr.set_line_width(width);
+    let _ = cr.stroke();
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct VibrantHandleLayer {
+        pub gradient: RefCell<Gradient>,
+        pub zoom: Cell<Zoom>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for VibrantHandleLayer {
+        const NAME: &'static str = "VibrantHandleLayer";
+        type Type = super::VibrantHandleLayer;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_css_name("handles");
+            klass.set_accessible_role(gtk::AccessibleRole::Presentation);
+        }
+    }
+
+    impl ObjectImpl for VibrantHandleLayer {
+        fn constructed(&self) {
+            self.parent_constructed();
+            self.obj().set_can_target(false);
+        }
+    }
+
+    impl WidgetImpl for VibrantHandleLayer {
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let widget = self.obj();
+            let (width, height) = (widget.width() as f32, widget.height() as f32);
+            let bounds = graphene::Rect::new(0.0, 0.0, width, height);
+            let gradient = self.gradient.borrow();
+            let zoom = self.zoom.get();
+
+            let cr = snapshot.append_cairo(&bounds);
+            let radius = handles::DOT_RADIUS as f64;
+            let center = zoom.map(width as f64 / 2.0, height as f64 / 2.0);
+            for (handle, point) in handles::layout(&gradient, width, height) {
+                let (x, y) = zoom.map(point.x() as f64, point.y() as f64);
+                match handle {
+                    //a hand from the middle, like the compass
+                    Handle::Angle => {
+                        cr.move_to(center.0, center.1);
+                        cr.line_to(x, y);
+                        stroke_outlined(&cr, 2.0);
+                        cr.arc(x, y, radius, 0.0, 2.0 * PI);
+                        stroke_outlined(&cr, 3.0);
+                    }
+                    Handle::Stop(i) => {
+                        cr.arc(x, y, radius, 0.0, 2.0 * PI);
+                        set_source(&cr, &gradient.stops[i].color);
+                        let _ = cr.fill_preserve();
+                        stroke_outlined(&cr, 2.0);
+                    }
+                }
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    /// The handles dragged to edit the gradient of a preview, drawn over it
+    /// where they are, and as large, whatever its zoom.
+    pub struct VibrantHandleLayer(ObjectSubclass<imp::VibrantHandleLayer>)
+        @extends gtk::Widget;
+}
+
+impl Default for VibrantHandleLayer {
+    fn default() -> Self {
+        glib::Object::new()
+    }
+}
+
+impl VibrantHandleLayer {
+    pub fn set_gradient(&self, gradient: Gradient) {
+        self.imp().gradient.replace(gradient);
+        self.queue_draw();
+    }
+
+    pub fn set_zoom(&self, zoom: Zoom) {
+        if self.imp().zoom.replace(zoom) != zoom {
+            self.queue_draw();
+        }
+    }
+}
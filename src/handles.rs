@@ -0,0 +1,319 @@
+/* handles.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::graphene;
+
+use crate::gradient::{Gradient, GradientType};
+
+/// Radius of the dots drawn for handles, in logical pixels.
+pub const DOT_RADIUS: f32 = 8.0;
+/// How far from a handle a press still grabs it, in logical pixels, about
+/// half the size of a fingertip.
+pub const REACH: f32 = 24.0;
+/// Distance kept between handles and the edges of the preview, so their
+/// dots are drawn whole.
+const INSET: f32 = 12.0;
+/// How far in the preview can be zoomed.
+pub const MAX_ZOOM: f64 = 8.0;
+
+/// Something on the preview that can be dragged.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Handle {
+    /// The direction of linear and conic gradients.
+    Angle,
+    /// The position of a stop, by index.
+    Stop(usize),
+}
+
+/// The point closest to `base` along `direction` that is inside the
+/// rectangle from `min` to `max`, or the closest point of the rectangle
+/// when the line misses it.
+fn nearest_inside(
+    base: (f32, f32),
+    direction: (f32, f32),
+    min: f32,
+    max: (f32, f32),
+) -> (f32, f32) {
+    let mut range = (f32::NEG_INFINITY, f32::INFINITY);
+    for (base, direction, max) in [(base.0, direction.0, max.0), (base.1, direction.1, max.1)] {
+        if direction.abs() < f32::EPSILON {
+            if base < min || base > max {
+                range = (1.0, 0.0);
+            }
+            continue;
+        }
+
+        let (a, b) = ((min - base) / direction, (max - base) / direction);
+        range = (range.0.max(a.min(b)), range.1.min(a.max(b)));
+    }
+
+    if range.0 <= range.1 {
+        let t = 0.0f32.clamp(range.0, range.1);
+        (base.0 + direction.0 * t, base.1 + direction.1 * t)
+    } else {
+        (base.0.clamp(min, max.0), base.1.clamp(min, max.1))
+    }
+}
+
+/// Where the handles of `gradient` go over a preview of `width` by
+/// `height`. Every stop sits where the gradient has its offset, as close to
+/// the middle as the edges allow.
+pub fn layout(gradient: &Gradient, width: f32, height: f32) -> Vec<(Handle, graphene::Point)> {
+    let inset = INSET.min(width / 2.0).min(height / 2.0);
+    let max = (width - inset, height - inset);
+    let center = (width / 2.0, height / 2.0);
+    let radius = (width.min(height) / 2.0 - inset).max(0.0);
+    let point = |(x, y): (f32, f32)| graphene::Point::new(x, y);
+    //CSS angles start at the top and go clockwise
+    let direction = |angle: f32| {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        (sin, -cos)
+    };
+
+    let offsets = gradient
+        .positions()
+        .into_iter()
+        .map(|position| gradient.drawn_offset(position));
+    let points: Vec<_> = match gradient.kind {
+        GradientType::Linear => {
+            let (dx, dy) = direction(gradient.angle);
+            let half = (width * dx.abs() + height * dy.abs()) / 2.0;
+            offsets
+                .map(|offset| {
+                    let along = (offset * 2.0 - 1.0) * half;
+                    let base = (center.0 + dx * along, center.1 + dy * along);
+                    point(nearest_inside(base, (-dy, dx), inset, max))
+                })
+                .collect()
+        }
+        //toward the bottom right corner, where the ellipse reaches offset 1
+        GradientType::Radial => offsets
+            .map(|offset| {
+                let x = center.0 + offset * width / 2.0;
+                let y = center.1 + offset * height / 2.0;
+                point((x.clamp(inset, max.0), y.clamp(inset, max.1)))
+            })
+            .collect(),
+        //on a ring inside the one of the angle handle
+        GradientType::Conic => offsets
+            .map(|offset| {
+                let (dx, dy) = direction(gradient.angle + offset * 360.0);
+                point((center.0 + dx * radius * 0.6, center.1 + dy * radius * 0.6))
+            })
+            .collect(),
+    };
+    let mut handles: Vec<_> = points
+        .into_iter()
+        .enumerate()
+        .map(|(i, point)| (Handle::Stop(i), point))
+        .collect();
+
+    if gradient.kind != GradientType::Radial {
+        let (dx, dy) = direction(gradient.angle);
+        handles.push((
+            Handle::Angle,
+            point((center.0 + dx * radius, center.1 + dy * radius)),
+        ));
+    }
+    handles
+}
+
+/// The handle closest to `point`, if one is within `reach`.
+pub fn handle_at(
+    handles: &[(Handle, graphene::Point)],
+    point: &graphene::Point,
+    reach: f32,
+) -> Option<Handle> {
+    handles
+        .iter()
+        .map(|(handle, at)| (*handle, at.distance(point).0))
+        .filter(|(_, distance)| *distance <= reach)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(handle, _)| handle)
+}
+
+/// How far the preview is zoomed in: scaled by `scale`, then moved by `x`
+/// and `y` logical pixels.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Zoom {
+    pub scale: f64,
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Default for Zoom {
+    fn default() -> Self {
+        Self {
+            scale: 1.0,
+            x: 0.0,
+            y: 0.0,
+        }
+    }
+}
+
+impl Zoom {
+    /// Zoomed to `scale`, showing the point `anchor` of the preview at the
+    /// point `at` of the view, as far as the preview still covers the
+    /// view of `width` by `height`.
+    pub fn around(scale: f64, anchor: (f64, f64), at: (f64, f64), width: f64, height: f64) -> Self {
+        let scale = scale.clamp(1.0, MAX_ZOOM);
+        Self {
+            scale,
+            x: (at.0 - anchor.0 * scale).clamp(width * (1.0 - scale), 0.0),
+            y: (at.1 - anchor.1 * scale).clamp(height * (1.0 - scale), 0.0),
+        }
+    }
+
+    /// Where a point of the preview is shown in the view.
+    pub fn map(self, x: f64, y: f64) -> (f64, f64) {
+        (self.x + x * self.scale, self.y + y * self.scale)
+    }
+
+    /// The point of the preview shown at a point of the view.
+    pub fn unmap(self, x: f64, y: f64) -> (f64, f64) {
+        ((x - self.x) / self.scale, (y - self.y) / self.scale)
+    }
+
+    /// The CSS transform zooming a widget of `width` by `height`, which
+    /// transforms around its middle.
+    pub fn to_css(self, width: f64, height: f64) -> Option<String> {
+        if self == Self::default() {
+            return None;
+        }
+
+        let x = self.x - width / 2.0 * (1.0 - self.scale);
+        let y = self.y - height / 2.0 * (1.0 - self.scale);
+        Some(format!(
+            "transform: translate({x:.2}px, {y:.2}px) scale({:.4});",
+            self.scale
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use gtk::gdk;
+
+    use crate::gradient::ColorStop;
+
+    fn gradient(kind: GradientType, angle: f32, symmetric: bool) -> Gradient {
+        Gradient {
+            kind,
+            angle,
+            symmetric,
+            stops: vec![
+                ColorStop::new(gdk::RGBA::RED),
+                ColorStop {
+                    position: Some(0.3),
+                    ..ColorStop::new(gdk::RGBA::GREEN)
+                },
+                ColorStop::new(gdk::RGBA::BLUE),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn stops_sit_at_their_offset() {
+        let (width, height) = (400.0, 120.0);
+        let bounds = graphene::Rect::new(0.0, 0.0, width, height);
+
+        for kind in [
+            GradientType::Linear,
+            GradientType::Radial,
+            GradientType::Conic,
+        ] {
+            for angle in [0.0, 30.0, 90.0, 135.0, 200.0] {
+                for symmetric in [false, true] {
+                    let gradient = gradient(kind, angle, symmetric);
+                    let handles = layout(&gradient, width, height);
+
+                    for (handle, point) in &handles {
+                        assert!(bounds.contains_point(point), "{kind:?} {angle}");
+                        let Handle::Stop(i) = *handle else {
+                            continue;
+                        };
+
+                        //the ends can sit outside the preview, or meet
+                        let position = gradient.positions()[i];
+                        if position == 0.0 || position == 1.0 {
+                            continue;
+                        }
+                        let offset = gradient.offset_at_point(point, &bounds);
+                        let stored = gradient.stored_offset(offset);
+                        assert!((stored - position).abs() < 0.01, "{kind:?} {angle} {i}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn angle_handle() {
+        let handles = layout(&gradient(GradientType::Linear, 90.0, false), 400.0, 120.0);
+        let angle = handles
+            .iter()
+            .find(|(handle, _)| *handle == Handle::Angle)
+            .unwrap()
+            .1;
+        assert_eq!((angle.x(), angle.y()), (248.0, 60.0));
+
+        let handles = layout(&gradient(GradientType::Radial, 90.0, false), 400.0, 120.0);
+        assert!(handles.iter().all(|(handle, _)| *handle != Handle::Angle));
+    }
+
+    #[test]
+    fn closest_handle_in_reach() {
+        let handles = [
+            (Handle::Stop(0), graphene::Point::new(10.0, 10.0)),
+            (Handle::Stop(1), graphene::Point::new(30.0, 10.0)),
+        ];
+        let at = |x, y| handle_at(&handles, &graphene::Point::new(x, y), REACH);
+
+        assert_eq!(at(12.0, 10.0), Some(Handle::Stop(0)));
+        assert_eq!(at(25.0, 30.0), Some(Handle::Stop(1)));
+        assert_eq!(at(100.0, 10.0), None);
+    }
+
+    #[test]
+    fn zoom_stays_over_the_view() {
+        let zoom = Zoom::around(2.0, (100.0, 50.0), (100.0, 50.0), 400.0, 120.0);
+        assert_eq!(zoom.map(100.0, 50.0), (100.0, 50.0));
+        assert_eq!(zoom.unmap(100.0, 50.0), (100.0, 50.0));
+
+        //the edges of the preview never come inside the view
+        let zoom = Zoom::around(2.0, (0.0, 0.0), (50.0, 50.0), 400.0, 120.0);
+        assert_eq!((zoom.x, zoom.y), (0.0, 0.0));
+        let zoom = Zoom::around(2.0, (400.0, 120.0), (300.0, 60.0), 400.0, 120.0);
+        assert_eq!((zoom.x, zoom.y), (-400.0, -120.0));
+
+        assert_eq!(
+            Zoom::around(0.5, (10.0, 10.0), (20.0, 20.0), 400.0, 120.0),
+            Zoom::default()
+        );
+        assert_eq!(Zoom::default().to_css(400.0, 120.0), None);
+        assert_eq!(
+            Zoom::around(2.0, (200.0, 60.0), (200.0, 60.0), 400.0, 120.0).to_css(400.0, 120.0),
+            Some("transform: translate(0.00px, 0.00px) scale(2.0000);".to_owned())
+        );
+    }
+}
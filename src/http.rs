@@ -0,0 +1,183 @@
+/* http.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::cell::OnceCell;
+
+use glib::translate::IntoGlib;
+use gtk::prelude::*;
+use gtk::{gio, glib};
+use soup::prelude::*;
+
+/// Seconds a request may wait for the server before failing.
+const TIMEOUT: u32 = 30;
+
+/// Largest response body read, so a broken or hostile server can't fill
+/// the memory.
+const MAX_RESPONSE: usize = 4 * 1024 * 1024;
+
+thread_local! {
+    /// Shared by all requests, so connections are reused.
+    static SESSION: OnceCell<soup::Session> = const { OnceCell::new() };
+}
+
+fn failed(message: &str) -> glib::Error {
+    glib::Error::new(gio::IOErrorEnum::Failed, message)
+}
+
+fn session() -> soup::Session {
+    SESSION.with(|session| {
+        session
+            .get_or_init(|| {
+                let session = soup::Session::new();
+                session.set_timeout(TIMEOUT);
+                session.set_user_agent("Vibrant");
+                session
+            })
+            .clone()
+    })
+}
+
+/// Sends a request, following redirects, and returns the body of a
+/// successful response.
+async fn request(
+    method: &str,
+    url: &str,
+    content: Option<(&str, &[u8])>,
+) -> Result<Vec<u8>, glib::Error> {
+    let uri = glib::Uri::parse(url, glib::UriFlags::NONE)?;
+    if !matches!(uri.scheme().as_str(), "http" | "https") {
+        return Err(failed("Only HTTP addresses are supported"));
+    }
+
+    let message = soup::Message::from_uri(method, &uri);
+    if let Some((content_type, content)) = content {
+        message.set_request_body_from_bytes(Some(content_type), Some(&glib::Bytes::from(content)));
+    }
+
+    let input = session()
+        .send_future(&message, glib::Priority::DEFAULT)
+        .await?;
+
+    let status = message.status().into_glib();
+    if !(200..300).contains(&status) {
+        return Err(failed(&format!("The server answered with status {status}")));
+    }
+
+    let mut response = Vec::new();
+    loop {
+        let bytes = input
+            .read_bytes_future(64 * 1024, glib::Priority::DEFAULT)
+            .await?;
+        if bytes.is_empty() {
+            break;
+        }
+        response.extend_from_slice(&bytes);
+        if response.len() > MAX_RESPONSE {
+            return Err(failed("The response is too large"));
+        }
+    }
+    let _ = input.close_future(glib::Priority::DEFAULT).await;
+
+    Ok(response)
+}
+
+/// Downloads `url`.
+pub async fn get(url: &str) -> Result<Vec<u8>, glib::Error> {
+    request("GET", url, None).await
+}
+
+/// Uploads `content` of type `content_type` to `url`, returning the
+/// answer of the server.
+pub async fn post(url: &str, content_type: &str, content: &[u8]) -> Result<Vec<u8>, glib::Error> {
+    request("POST", url, Some((content_type, content))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A server redirecting `/old` to `/feed`, which answers with the
+    /// `Host` header it was sent.
+    fn serve() -> (gio::SocketService, u16) {
+        let service = gio::SocketService::new();
+        let port = service.add_any_inet_port(None::<&glib::Object>).unwrap();
+
+        service.connect_incoming(|_, connection, _| {
+            let connection = connection.clone();
+            glib::MainContext::ref_thread_default().spawn_local(async move {
+                let input = connection.input_stream();
+                let mut request = Vec::new();
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match input.read_bytes_future(1024, glib::Priority::DEFAULT).await {
+                        Ok(bytes) if !bytes.is_empty() => request.extend_from_slice(&bytes),
+                        _ => return,
+                    }
+                }
+
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                let host = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Host: "))
+                    .unwrap_or_default();
+                let (status, headers, body) = match path {
+                    "/old" => ("302 Found", "Location: /feed\r\n", ""),
+                    "/feed" => ("200 OK", "", host),
+                    _ => ("404 Not Found", "", ""),
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+
+                let output = connection.output_stream();
+                let _ = output
+                    .write_all_future(response.into_bytes(), glib::Priority::DEFAULT)
+                    .await;
+                let _ = connection.close_future(glib::Priority::DEFAULT).await;
+            });
+            true
+        });
+        service.start();
+
+        (service, port)
+    }
+
+    #[test]
+    fn requests() {
+        //the session and server both run in the context they were made in
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| {
+                let (_service, port) = serve();
+                let url = |path| format!("http://127.0.0.1:{port}{path}");
+
+                let body = context.block_on(get(&url("/old"))).unwrap();
+                assert_eq!(
+                    String::from_utf8(body).unwrap(),
+                    format!("127.0.0.1:{port}")
+                );
+
+                assert!(context.block_on(get(&url("/missing"))).is_err());
+                assert!(context.block_on(get("ftp://127.0.0.1/feed")).is_err());
+            })
+            .unwrap();
+    }
+}
@@ -0,0 +1,215 @@
+/* icc.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+/// The D50 white of the profile connection space.
+const D50: [f64; 3] = [0.9642, 1.0, 0.8249];
+
+/// Bradford adaptation from D65, the white of both spaces, to D50.
+const CHROMATIC_ADAPTATION: [[f64; 3]; 3] = [
+    [1.047886, 0.0229188, -0.0502161],
+    [0.0295818, 0.9904835, -0.0170787],
+    [-0.0092519, 0.0150726, 0.7516781],
+];
+
+/// The sRGB transfer function, which Display P3 shares, as the parameters
+/// of an ICC parametric curve of type 3.
+const TRANSFER: [f64; 5] = [2.4, 1.0 / 1.055, 0.055 / 1.055, 1.0 / 12.92, 0.04045];
+
+/// Color spaces exports can be tagged with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorSpace {
+    Srgb,
+    DisplayP3,
+}
+
+impl ColorSpace {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Srgb => "sRGB",
+            Self::DisplayP3 => "Display P3",
+        }
+    }
+
+    /// Chromaticities of the white point and of the red, green and blue
+    /// primaries.
+    pub fn chromaticities(self) -> [(f64, f64); 4] {
+        match self {
+            Self::Srgb => [(0.3127, 0.329), (0.64, 0.33), (0.3, 0.6), (0.15, 0.06)],
+            Self::DisplayP3 => [(0.3127, 0.329), (0.68, 0.32), (0.265, 0.69), (0.15, 0.06)],
+        }
+    }
+
+    /// XYZ of the red, green and blue primaries, adapted to D50.
+    fn colorants(self) -> [[f64; 3]; 3] {
+        match self {
+            Self::Srgb => [
+                [0.4360413, 0.2224845, 0.0139202],
+                [0.3851129, 0.7169051, 0.0970672],
+                [0.1430458, 0.0606104, 0.7139126],
+            ],
+            Self::DisplayP3 => [
+                [0.5151187, 0.2411892, -0.0010505],
+                [0.2919778, 0.6922441, 0.0418791],
+                [0.1571035, 0.0665668, 0.7840713],
+            ],
+        }
+    }
+
+    /// An ICC v4 display profile of the space, for embedding in images.
+    pub fn profile(self) -> Vec<u8> {
+        let [red, green, blue] = self.colorants();
+        let tags = [
+            (b"desc", text_tag(self.name())),
+            (b"cprt", text_tag("No copyright, use freely")),
+            (b"wtpt", xyz_tag(D50)),
+            (b"chad", matrix_tag(CHROMATIC_ADAPTATION)),
+            (b"rXYZ", xyz_tag(red)),
+            (b"gXYZ", xyz_tag(green)),
+            (b"bXYZ", xyz_tag(blue)),
+            (b"rTRC", curve_tag()),
+            (b"gTRC", curve_tag()),
+            (b"bTRC", curve_tag()),
+        ];
+
+        let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+        let mut data = Vec::new();
+        let start = HEADER_SIZE + 4 + tags.len() * 12;
+        for (signature, tag) in &tags {
+            table.extend_from_slice(*signature);
+            table.extend(((start + data.len()) as u32).to_be_bytes());
+            table.extend((tag.len() as u32).to_be_bytes());
+
+            data.extend(tag);
+            //tags start on four byte boundaries
+            data.resize(data.len().next_multiple_of(4), 0);
+        }
+
+        let mut profile = header(start + data.len());
+        profile.extend(table);
+        profile.extend(data);
+        profile
+    }
+}
+
+const HEADER_SIZE: usize = 128;
+
+fn s15_fixed16(value: f64) -> [u8; 4] {
+    ((value * 65536.0).round() as i32).to_be_bytes()
+}
+
+fn header(size: usize) -> Vec<u8> {
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    header.extend((size as u32).to_be_bytes());
+    //no preferred color management module
+    header.extend([0; 4]);
+    //version 4.3
+    header.extend([4, 0x30, 0, 0]);
+    header.extend(b"mntrRGB XYZ ");
+    //creation date, fixed so exports stay reproducible
+    for value in [2023u16, 1, 1, 0, 0, 0] {
+        header.extend(value.to_be_bytes());
+    }
+    header.extend(b"acsp");
+    //platform, flags, manufacturer, model, attributes and rendering intent
+    header.extend([0; 28]);
+    for value in D50 {
+        header.extend(s15_fixed16(value));
+    }
+    //creator, profile ID left uncomputed, and reserved bytes
+    header.extend([0; 48]);
+    header
+}
+
+fn tag(kind: &[u8; 4]) -> Vec<u8> {
+    let mut tag = kind.to_vec();
+    tag.extend([0; 4]);
+    tag
+}
+
+/// A `multiLocalizedUnicodeType` holding `text` in English.
+fn text_tag(text: &str) -> Vec<u8> {
+    let units: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+
+    let mut tag = tag(b"mluc");
+    //one record of 12 bytes
+    tag.extend(1u32.to_be_bytes());
+    tag.extend(12u32.to_be_bytes());
+    tag.extend(b"enUS");
+    tag.extend((units.len() as u32).to_be_bytes());
+    tag.extend(28u32.to_be_bytes());
+    tag.extend(units);
+    tag
+}
+
+fn xyz_tag(xyz: [f64; 3]) -> Vec<u8> {
+    let mut tag = tag(b"XYZ ");
+    tag.extend(xyz.into_iter().flat_map(s15_fixed16));
+    tag
+}
+
+fn matrix_tag(matrix: [[f64; 3]; 3]) -> Vec<u8> {
+    let mut tag = tag(b"sf32");
+    tag.extend(matrix.into_iter().flatten().flat_map(s15_fixed16));
+    tag
+}
+
+fn curve_tag() -> Vec<u8> {
+    let mut tag = tag(b"para");
+    //function type 3, then a reserved field
+    tag.extend([0, 3, 0, 0]);
+    tag.extend(TRANSFER.into_iter().flat_map(s15_fixed16));
+    tag
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u32_at(data: &[u8], offset: usize) -> usize {
+        u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap()) as usize
+    }
+
+    #[test]
+    fn profile_layout() {
+        for space in [ColorSpace::Srgb, ColorSpace::DisplayP3] {
+            let profile = space.profile();
+            assert_eq!(u32_at(&profile, 0), profile.len());
+            assert_eq!(profile.len() % 4, 0);
+            assert_eq!(&profile[36..40], b"acsp");
+
+            let count = u32_at(&profile, HEADER_SIZE);
+            assert_eq!(count, 10);
+            for i in 0..count {
+                let entry = HEADER_SIZE + 4 + i * 12;
+                let (offset, size) = (u32_at(&profile, entry + 4), u32_at(&profile, entry + 8));
+                assert_eq!(offset % 4, 0);
+                assert!(offset + size <= profile.len());
+            }
+
+            //the primaries add up to the white point
+            let white: Vec<f64> = (0..3)
+                .map(|i| space.colorants().iter().map(|xyz| xyz[i]).sum())
+                .collect();
+            for (white, d50) in white.iter().zip(D50) {
+                assert!((white - d50).abs() < 1e-4);
+            }
+        }
+    }
+}
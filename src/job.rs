@@ -0,0 +1,109 @@
+/* job.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use gettextrs::gettext;
+use glib::clone;
+use gtk::{gio, glib};
+
+/// How often the toast shows the progress of a job.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The progress of a job, shared between its worker thread and the toast
+/// showing it.
+#[derive(Debug, Default, Clone)]
+pub struct Progress {
+    //in thousandths, atomics have no floats
+    done: Arc<AtomicU32>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl Progress {
+    pub fn set(&self, fraction: f64) {
+        let value = (fraction.clamp(0.0, 1.0) * 1000.0).round() as u32;
+        self.done.store(value, Ordering::Relaxed);
+    }
+
+    pub fn fraction(&self) -> f64 {
+        self.done.load(Ordering::Relaxed) as f64 / 1000.0
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Fails once the job was cancelled, for workers to bail out with `?`.
+    pub fn check(&self) -> Result<(), glib::Error> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(glib::Error::new(
+                gio::IOErrorEnum::Cancelled,
+                "The job was cancelled",
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs `work` on a worker thread, showing its progress in a toast with a
+/// button to cancel it. Cancelled jobs fail with `gio::IOErrorEnum::Cancelled`.
+pub async fn run<T, F>(overlay: &adw::ToastOverlay, title: &str, work: F) -> Result<T, glib::Error>
+where
+    T: Send + 'static,
+    F: FnOnce(&Progress) -> Result<T, glib::Error> + Send + 'static,
+{
+    let progress = Progress::default();
+
+    let toast = adw::Toast::builder()
+        .title(title)
+        .button_label(gettext("_Cancel"))
+        .timeout(0)
+        .build();
+    toast.connect_button_clicked(clone!(@strong progress => move |_| progress.cancel()));
+    overlay.add_toast(toast.clone());
+
+    let title = title.to_owned();
+    let source = glib::timeout_add_local(
+        PROGRESS_INTERVAL,
+        clone!(@weak toast, @strong progress => @default-return glib::ControlFlow::Break, move || {
+            let percent = (progress.fraction() * 100.0).round() as u32;
+            toast.set_title(&format!("{title} {percent}%"));
+            glib::ControlFlow::Continue
+        }),
+    );
+
+    let worker = progress.clone();
+    let result = gio::spawn_blocking(move || work(&worker))
+        .await
+        .unwrap_or_else(|_| {
+            Err(glib::Error::new(
+                gio::IOErrorEnum::Failed,
+                "The job stopped unexpectedly",
+            ))
+        });
+
+    source.remove();
+    toast.dismiss();
+
+    result
+}
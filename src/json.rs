@@ -0,0 +1,537 @@
+/* json.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::fmt::Write;
+
+/// A JSON value. Objects keep their keys in order, so written documents
+/// stay stable and diffable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(value: f32) -> Self {
+        //round away the noise of the f32 to f64 conversion
+        Self::Number((value as f64 * 1e6).round() / 1e6)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        Self::Number(value as f64)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Self::Null)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(value: Vec<T>) -> Self {
+        Self::Array(value.into_iter().map(Into::into).collect())
+    }
+}
+
+impl Value {
+    /// An object from its keys and values, in order.
+    pub fn object<const N: usize>(entries: [(&str, Value); N]) -> Self {
+        Self::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+        )
+    }
+
+    /// The value of `key`, when this is an object holding it.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Object(entries) => entries
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The number, unless it is too large for an `f32`.
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Self::Number(value) => Some(*value as f32).filter(|value| value.is_finite()),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Self::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == Self::Null
+    }
+
+    /// The value as indented JSON text.
+    pub fn to_json(&self) -> String {
+        let mut text = String::new();
+        self.write(&mut text, 0);
+        text.push('\n');
+        text
+    }
+
+    fn write(&self, out: &mut String, depth: usize) {
+        let indent = |out: &mut String, depth: usize| {
+            out.push('\n');
+            out.push_str(&"  ".repeat(depth));
+        };
+
+        match self {
+            Self::Null => out.push_str("null"),
+            Self::Bool(value) => {
+                let _ = write!(out, "{value}");
+            }
+            Self::Number(value) if value.is_finite() => {
+                let _ = write!(out, "{value}");
+            }
+            Self::Number(_) => out.push_str("null"),
+            Self::String(value) => write_string(out, value),
+            Self::Array(values) if values.is_empty() => out.push_str("[]"),
+            Self::Array(values) => {
+                out.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    indent(out, depth + 1);
+                    value.write(out, depth + 1);
+                }
+                indent(out, depth);
+                out.push(']');
+            }
+            Self::Object(entries) if entries.is_empty() => out.push_str("{}"),
+            Self::Object(entries) => {
+                out.push('{');
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    indent(out, depth + 1);
+                    write_string(out, key);
+                    out.push_str(": ");
+                    value.write(out, depth + 1);
+                }
+                indent(out, depth);
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Nesting of arrays and objects past which parsing gives up, before
+/// running out of stack on hostile input.
+const MAX_DEPTH: usize = 128;
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+/// The code unit of a `\u` escape, from the four hex digits after it.
+fn hex_unit(chars: &mut std::str::CharIndices) -> Option<u32> {
+    (0..4).try_fold(0, |unit, _| {
+        let digit = chars.next()?.1.to_digit(16)?;
+        Some(unit << 4 | digit)
+    })
+}
+
+/// The length of the number at the start of `text`, following the JSON
+/// grammar, which has no leading zeros, `+` signs or bare dots.
+fn number_length(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let digits = |from: usize| {
+        from + bytes[from..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count()
+    };
+
+    let mut end = usize::from(bytes.first() == Some(&b'-'));
+    match bytes.get(end) {
+        Some(b'0') => end += 1,
+        Some(b'1'..=b'9') => end = digits(end),
+        _ => return None,
+    }
+    if bytes.get(end) == Some(&b'.') {
+        let fraction = digits(end + 1);
+        if fraction == end + 1 {
+            return None;
+        }
+        end = fraction;
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        end += 1;
+        if matches!(bytes.get(end), Some(b'+' | b'-')) {
+            end += 1;
+        }
+        let exponent = digits(end);
+        if exponent == end {
+            return None;
+        }
+        end = exponent;
+    }
+
+    Some(end)
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> String {
+        format!("{message} at byte {}", self.pos)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.pos..];
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), String> {
+        if self.text[self.pos..].starts_with(token) {
+            self.pos += token.len();
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected “{token}”")))
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'n') => self.expect("null").map(|()| Value::Null),
+            Some(b't') => self.expect("true").map(|()| Value::Bool(true)),
+            Some(b'f') => self.expect("false").map(|()| Value::Bool(false)),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'[' | b'{') => {
+                if self.depth >= MAX_DEPTH {
+                    return Err(self.error("Too deeply nested"));
+                }
+                self.depth += 1;
+                let value = if self.peek() == Some(b'[') {
+                    self.array()
+                } else {
+                    self.object()
+                };
+                self.depth -= 1;
+                value
+            }
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            _ => Err(self.error("Unexpected character")),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let rest = &self.text[self.pos..];
+        let number = number_length(rest)
+            .and_then(|len| Some((len, rest[..len].parse::<f64>().ok()?)))
+            //too large numbers would parse as infinity
+            .filter(|(_, number)| number.is_finite());
+        let Some((len, number)) = number else {
+            return Err(self.error("Invalid number"));
+        };
+
+        self.pos += len;
+        Ok(Value::Number(number))
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect("\"")?;
+
+        let mut value = String::new();
+        let mut chars = self.text[self.pos..].char_indices();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += offset + 1;
+                    return Ok(value);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some(c @ ('"' | '\\' | '/')) => Some(c),
+                        Some('n') => Some('\n'),
+                        Some('r') => Some('\r'),
+                        Some('t') => Some('\t'),
+                        Some('b') => Some('\u{8}'),
+                        Some('f') => Some('\u{c}'),
+                        Some('u') => {
+                            let code = match hex_unit(&mut chars) {
+                                //characters outside the BMP come as a pair of surrogates
+                                Some(high @ 0xd800..=0xdbff)
+                                    if chars.as_str().starts_with("\\u") =>
+                                {
+                                    chars.nth(1);
+                                    match hex_unit(&mut chars) {
+                                        Some(low @ 0xdc00..=0xdfff) => {
+                                            Some(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+                                        }
+                                        _ => None,
+                                    }
+                                }
+                                code => code,
+                            };
+                            //lone surrogates aren't characters
+                            code.and_then(char::from_u32)
+                        }
+                        _ => None,
+                    };
+                    match escaped {
+                        Some(escaped) => value.push(escaped),
+                        None => {
+                            self.pos += offset;
+                            return Err(self.error("Invalid escape"));
+                        }
+                    }
+                }
+                c if (c as u32) < 0x20 => {
+                    self.pos += offset;
+                    return Err(self.error("Unescaped control character"));
+                }
+                c => value.push(c),
+            }
+        }
+
+        Err(self.error("Unterminated string"))
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.expect("[")?;
+
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(values));
+        }
+
+        loop {
+            values.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(values));
+                }
+                _ => return Err(self.error("Expected “,” or “]”")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.expect("{")?;
+
+        let mut entries = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(entries));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(":")?;
+            entries.push((key, self.value()?));
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(entries));
+                }
+                _ => return Err(self.error("Expected “,” or “}”")),
+            }
+        }
+    }
+}
+
+/// Parses a JSON document, returning a description of the first error.
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser {
+        text,
+        pos: 0,
+        depth: 0,
+    };
+    let value = parser.value()?;
+
+    parser.skip_whitespace();
+    if parser.pos < text.len() {
+        return Err(parser.error("Trailing characters"));
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let value = Value::object([
+            ("name", "Sunset \"☀\"\n\u{1f305}".into()),
+            ("angle", 22.5.into()),
+            ("stops", Value::Array(vec![Value::Null, true.into()])),
+        ]);
+        assert_eq!(parse(&value.to_json()), Ok(value));
+    }
+
+    #[test]
+    fn numbers() {
+        let number = |text| parse(text).ok().and_then(|value| value.as_f32());
+        assert_eq!(number("-0.5e1"), Some(-5.0));
+        assert_eq!(number("0"), Some(0.0));
+        //finite as f64, but not as f32
+        assert_eq!(number("1e300"), None);
+
+        for text in [
+            "1e999", "-1e999", "+1", "01", ".5", "1.", "1e", "--1", "NaN",
+        ] {
+            assert!(parse(text).is_err(), "{text} parsed");
+        }
+    }
+
+    #[test]
+    fn escapes() {
+        let string = |text| {
+            parse(text)
+                .ok()
+                .and_then(|value| value.as_str().map(String::from))
+        };
+        assert_eq!(
+            string(r#""\"\\\/\b\f\n\r\t""#).as_deref(),
+            Some("\"\\/\u{8}\u{c}\n\r\t")
+        );
+        assert_eq!(string(r#""é🌅""#).as_deref(), Some("é\u{1f305}"));
+
+        for text in [
+            r#""\u+123""#,
+            r#""\u12""#,
+            r#""\ud83c""#,
+            r#""\ud83cA""#,
+            r#""\udf05""#,
+            r#""\x41""#,
+            "\"\t\"",
+            r#""unterminated"#,
+        ] {
+            assert!(parse(text).is_err(), "{text} parsed");
+        }
+    }
+
+    #[test]
+    fn malformed() {
+        for text in [
+            "",
+            "[1,]",
+            "{\"a\" 1}",
+            "{\"a\":1,}",
+            "[1] x",
+            "tru",
+            "\u{a0}1",
+        ] {
+            assert!(parse(text).is_err(), "{text:?} parsed");
+        }
+        assert!(parse(" \t\r\n[ 1 , 2 ]\n").is_ok());
+
+        let nested = |depth| "[".repeat(depth) + &"]".repeat(depth);
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        assert!(parse(&nested(MAX_DEPTH + 1)).is_err());
+    }
+}
@@ -0,0 +1,424 @@
+/* library.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::path::{Path, PathBuf};
+
+use gtk::prelude::*;
+use gtk::{gio, glib};
+
+use crate::color::{self, Oklch};
+use crate::document::{self, Document};
+use crate::gradient::Gradient;
+use crate::palette;
+
+/// Points compared along two gradients to tell whether they look the same.
+const SAMPLES: usize = 32;
+
+/// Largest difference in OKLab between points of gradients that look the
+/// same, about the smallest one people notice.
+const SIMILAR_DIFFERENCE: f32 = 0.02;
+
+/// Chroma in OKLCH under which a gradient counts as gray when sorting by
+/// hue.
+const GRAY_CHROMA: f32 = 0.02;
+
+/// Days a deleted gradient stays in the trash before it is purged.
+pub const TRASH_DAYS: i64 = 30;
+
+/// A gradient saved by the user. The library lists the documents opened or
+/// saved in the app, as they are kept wherever the user saves them.
+pub struct Entry {
+    pub file: gio::File,
+    pub document: Document,
+    /// When it was last opened or saved, in seconds since the Unix epoch.
+    pub used: i64,
+}
+
+/// Orders the library can be listed in.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SortOrder {
+    LastUsed,
+    Name,
+    Created,
+    Hue,
+    Lightness,
+}
+
+impl SortOrder {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "last-used" => Some(Self::LastUsed),
+            "name" => Some(Self::Name),
+            "created" => Some(Self::Created),
+            "hue" => Some(Self::Hue),
+            "lightness" => Some(Self::Lightness),
+            _ => None,
+        }
+    }
+}
+
+/// A document of the library, as its index remembers it.
+#[derive(Debug, Clone, PartialEq)]
+struct Indexed {
+    uri: String,
+    /// When it was last opened or saved, in seconds since the Unix epoch.
+    used: i64,
+}
+
+fn index_path() -> PathBuf {
+    palette::library_dir().join("library.gvariant")
+}
+
+fn failed(message: impl ToString) -> glib::Error {
+    glib::Error::new(gio::IOErrorEnum::Failed, &message.to_string())
+}
+
+/// Reads an index of the library, or `None` when it was never written.
+fn read_index(path: &Path, type_: &str) -> Result<Option<glib::Variant>, glib::Error> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(failed(err)),
+    };
+    glib::Variant::parse(Some(glib::VariantTy::new(type_).unwrap()), &text).map(Some)
+}
+
+fn write_index(path: &Path, index: &glib::Variant) -> Result<(), glib::Error> {
+    std::fs::create_dir_all(path.parent().unwrap()).map_err(failed)?;
+
+    //written aside first, so a failed write leaves the previous index whole
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, index.print(true).as_str()).map_err(failed)?;
+    std::fs::rename(&partial, path).map_err(failed)
+}
+
+/// The documents of the library, stored as a GVariant of type `a(sx)`.
+/// Before the index is first written, the library starts from the
+/// gradients in the recent files. An index that can't be read is an error
+/// rather than an empty library, so it never gets written over.
+fn index() -> Result<Vec<Indexed>, glib::Error> {
+    let Some(index) = read_index(&index_path(), "a(sx)")? else {
+        return Ok(gtk::RecentManager::default()
+            .items()
+            .into_iter()
+            .filter(|item| item.mime_type() == document::MIME_TYPE)
+            .map(|item| Indexed {
+                uri: item.uri().into(),
+                used: item.modified().to_unix(),
+            })
+            .collect());
+    };
+
+    Ok(index
+        .get::<Vec<(String, i64)>>()
+        .ok_or_else(|| failed("Invalid library index"))?
+        .into_iter()
+        .map(|(uri, used)| Indexed { uri, used })
+        .collect())
+}
+
+fn save_index(index: &[Indexed]) -> Result<(), glib::Error> {
+    let index = index
+        .iter()
+        .map(|indexed| (indexed.uri.clone(), indexed.used))
+        .collect::<Vec<_>>();
+    write_index(&index_path(), &index.to_variant())
+}
+
+/// Adds a document to the library, or marks it as used now when it is
+/// already there. It is also added to the recent files, for other apps.
+pub fn remember(file: &gio::File) -> Result<(), glib::Error> {
+    let uri: String = file.uri().into();
+    gtk::RecentManager::default().add_item(&uri);
+
+    let mut index = index()?;
+    index.retain(|indexed| indexed.uri != uri);
+    index.push(Indexed {
+        uri,
+        used: glib::real_time() / 1_000_000,
+    });
+    save_index(&index)
+}
+
+/// Reads every gradient of the library, most recently used first, skipping
+/// those that can't be. They stay in the index, as the drive holding them
+/// may only be unplugged.
+pub async fn load() -> Vec<Entry> {
+    let mut index = match index() {
+        Ok(index) => index,
+        Err(err) => {
+            glib::g_warning!("vibrant", "Could not read the library: {}", err);
+            return Vec::new();
+        }
+    };
+    index.sort_by_key(|indexed| std::cmp::Reverse(indexed.used));
+
+    let mut entries = Vec::new();
+    for indexed in index {
+        let file = gio::File::for_uri(&indexed.uri);
+        if let Ok(document) = document::load(&file).await {
+            entries.push(Entry {
+                file,
+                document,
+                used: indexed.used,
+            });
+        }
+    }
+    entries
+}
+
+/// Sorts the library, keeping the most recently used first among entries
+/// that compare the same.
+pub fn sort(entries: &mut [Entry], order: SortOrder) {
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.used));
+
+    match order {
+        SortOrder::LastUsed => (),
+        SortOrder::Name => {
+            entries.sort_by_cached_key(|entry| document::display_name(&entry.file).to_lowercase())
+        }
+        //newest first, ISO 8601 dates in UTC sort as text
+        SortOrder::Created => entries.sort_by(|a, b| {
+            b.document
+                .metadata
+                .created
+                .cmp(&a.document.metadata.created)
+        }),
+        //grays have no hue worth sorting by, so they go last
+        SortOrder::Hue => entries.sort_by_cached_key(|entry| {
+            let color = Oklch::from(entry.document.gradient.adjusted().average_color());
+            (
+                color.c < GRAY_CHROMA,
+                (color.h.rem_euclid(360.0) * 10.0) as u32,
+            )
+        }),
+        //lightest first
+        SortOrder::Lightness => entries.sort_by_cached_key(|entry| {
+            let color = Oklch::from(entry.document.gradient.adjusted().average_color());
+            std::cmp::Reverse((color.l * 1000.0) as i32)
+        }),
+    }
+}
+
+/// Whether two gradients look the same, point by point, whatever their
+/// stops and adjustments.
+pub fn looks_alike(a: &Gradient, b: &Gradient) -> bool {
+    if a.kind != b.kind {
+        return false;
+    }
+
+    let (a, b) = (a.adjusted().sample(SAMPLES), b.adjusted().sample(SAMPLES));
+    a.iter().zip(&b).all(|(a, b)| {
+        color::delta_e(a, b) <= SIMILAR_DIFFERENCE
+            && (a.alpha() - b.alpha()).abs() <= SIMILAR_DIFFERENCE
+    })
+}
+
+/// A saved gradient looking the same as `gradient`. Collections are left
+/// out, as updating one with a single gradient would lose the others.
+pub fn find_similar<'a>(entries: &'a [Entry], gradient: &Gradient) -> Option<&'a Entry> {
+    entries.iter().find(|entry| {
+        entry.document.collection.members.is_empty()
+            && looks_alike(&entry.document.gradient, gradient)
+    })
+}
+
+/// The saved gradients looking the same as one used more recently, which
+/// is the copy worth keeping.
+pub fn duplicates(entries: &[Entry]) -> Vec<&Entry> {
+    let mut kept: Vec<&Entry> = Vec::new();
+    let mut duplicates = Vec::new();
+    for entry in entries {
+        let is_duplicate = entry.document.collection.members.is_empty()
+            && kept.iter().any(|kept| {
+                kept.document.collection.members.is_empty()
+                    && looks_alike(&kept.document.gradient, &entry.document.gradient)
+            });
+        if is_duplicate {
+            duplicates.push(entry);
+        } else {
+            kept.push(entry);
+        }
+    }
+    duplicates
+}
+
+/// A gradient moved to the trash, which can be put back where it was.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trashed {
+    /// URI of the file it was deleted from.
+    pub uri: String,
+    /// Name of its file in the trash folder.
+    pub name: String,
+    /// When it was deleted, in seconds since the Unix epoch.
+    pub deleted: i64,
+    /// When it was last used before, kept for when it gets restored.
+    pub used: i64,
+}
+
+impl Trashed {
+    /// Name of the document shown to the user, from its original file.
+    pub fn display_name(&self) -> String {
+        document::display_name(&gio::File::for_uri(&self.uri))
+    }
+}
+
+fn trash_dir() -> PathBuf {
+    palette::library_dir().join("trash")
+}
+
+fn trash_index_path() -> PathBuf {
+    trash_dir().join("index.gvariant")
+}
+
+/// The gradients in the trash, most recently deleted first, stored as a
+/// GVariant of type `a(ssxx)`. An index that can't be read is an error
+/// rather than an empty trash, so it never gets written over.
+pub fn trash_contents() -> Result<Vec<Trashed>, glib::Error> {
+    let Some(index) = read_index(&trash_index_path(), "a(ssxx)")? else {
+        return Ok(Vec::new());
+    };
+
+    let mut trashed: Vec<Trashed> = index
+        .get::<Vec<(String, String, i64, i64)>>()
+        .ok_or_else(|| failed("Invalid trash index"))?
+        .into_iter()
+        .map(|(uri, name, deleted, used)| Trashed {
+            uri,
+            name,
+            deleted,
+            used,
+        })
+        .collect();
+    trashed.sort_by_key(|trashed| std::cmp::Reverse(trashed.deleted));
+    Ok(trashed)
+}
+
+fn save_trashed(trashed: &[Trashed]) -> Result<(), glib::Error> {
+    let index = trashed
+        .iter()
+        .map(|trashed| {
+            (
+                trashed.uri.clone(),
+                trashed.name.clone(),
+                trashed.deleted,
+                trashed.used,
+            )
+        })
+        .collect::<Vec<_>>();
+    write_index(&trash_index_path(), &index.to_variant())
+}
+
+/// Moves `file` to `target`, then saves both indexes, moving it back and
+/// keeping the previous trash index when either can't be saved. A file
+/// missing from the indexes could never be restored nor purged.
+fn move_indexed(
+    file: &gio::File,
+    target: &gio::File,
+    previous: &[Trashed],
+    trashed: &[Trashed],
+    library: &[Indexed],
+) -> Result<(), glib::Error> {
+    file.move_(
+        target,
+        gio::FileCopyFlags::NONE,
+        gio::Cancellable::NONE,
+        None,
+    )?;
+
+    let result = save_trashed(trashed).and_then(|()| {
+        save_index(library).inspect_err(|_| {
+            let _ = save_trashed(previous);
+        })
+    });
+    if result.is_err() {
+        let _ = target.move_(file, gio::FileCopyFlags::NONE, gio::Cancellable::NONE, None);
+    }
+    result
+}
+
+/// Moves a saved gradient into the trash folder of the library, and out of
+/// the library.
+pub fn trash(file: &gio::File) -> Result<Trashed, glib::Error> {
+    let previous = trash_contents()?;
+    let mut library = index()?;
+    std::fs::create_dir_all(trash_dir()).map_err(failed)?;
+
+    let uri: String = file.uri().into();
+    let deleted = glib::real_time() / 1_000_000;
+    let trashed = Trashed {
+        //unique even when files of the same name get deleted together
+        name: format!("{deleted}-{}.{}", glib::random_int(), document::SUFFIX),
+        deleted,
+        used: library
+            .iter()
+            .find(|indexed| indexed.uri == uri)
+            .map_or(deleted, |indexed| indexed.used),
+        uri,
+    };
+    library.retain(|indexed| indexed.uri != trashed.uri);
+    let mut contents = previous.clone();
+    contents.push(trashed.clone());
+
+    let target = gio::File::for_path(trash_dir().join(&trashed.name));
+    move_indexed(file, &target, &previous, &contents, &library)?;
+
+    let _ = gtk::RecentManager::default().remove_item(&trashed.uri);
+    Ok(trashed)
+}
+
+/// Puts a gradient back where it was deleted from and in the library, as
+/// last used when it was deleted. It fails rather than replacing a file
+/// saved there since.
+pub fn restore(trashed: &Trashed) -> Result<(), glib::Error> {
+    let previous = trash_contents()?;
+    let mut contents = previous.clone();
+    contents.retain(|other| other != trashed);
+    let mut library = index()?;
+    library.retain(|indexed| indexed.uri != trashed.uri);
+    library.push(Indexed {
+        uri: trashed.uri.clone(),
+        used: trashed.used,
+    });
+
+    let source = gio::File::for_path(trash_dir().join(&trashed.name));
+    let file = gio::File::for_uri(&trashed.uri);
+    move_indexed(&source, &file, &previous, &contents, &library)
+}
+
+/// Deletes the gradients in the trash for good, all of them or only those
+/// deleted more than `TRASH_DAYS` ago.
+pub fn purge(all: bool) -> Result<(), glib::Error> {
+    let now = glib::real_time() / 1_000_000;
+    let (expired, kept): (Vec<_>, Vec<_>) = trash_contents()?
+        .into_iter()
+        .partition(|trashed| all || now - trashed.deleted > TRASH_DAYS * 24 * 60 * 60);
+    if expired.is_empty() {
+        return Ok(());
+    }
+
+    //a file left behind only takes space, an index entry without its file
+    //would offer to restore nothing
+    save_trashed(&kept)?;
+    for trashed in &expired {
+        let _ = std::fs::remove_file(trash_dir().join(&trashed.name));
+    }
+    Ok(())
+}
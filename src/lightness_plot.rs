@@ -0,0 +1,129 @@
+/* lightness_plot.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::cell::RefCell;
+
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{cairo, gdk, glib, graphene};
+
+use crate::color::Oklab;
+
+/// Height of the plot, in logical pixels.
+const HEIGHT: i32 = 40;
+
+mod imp {
+    use super::*;
+
+    /// The OKLab lightness of colors sampled along a gradient, from black at
+    /// the bottom to white at the top, each stretch drawn in its own color.
+    #[derive(Debug, Default)]
+    pub struct VibrantLightnessPlot {
+        pub samples: RefCell<Vec<gdk::RGBA>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for VibrantLightnessPlot {
+        const NAME: &'static str = "VibrantLightnessPlot";
+        type Type = super::VibrantLightnessPlot;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_css_name("lightness-plot");
+            klass.set_accessible_role(gtk::AccessibleRole::Img);
+        }
+    }
+
+    impl ObjectImpl for VibrantLightnessPlot {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+            obj.set_size_request(-1, HEIGHT);
+            obj.set_hexpand(true);
+        }
+    }
+
+    impl WidgetImpl for VibrantLightnessPlot {
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let widget = self.obj();
+            let samples = self.samples.borrow();
+            if samples.len() < 2 {
+                return;
+            }
+
+            let (width, height) = (widget.width() as f64, widget.height() as f64);
+            let bounds = graphene::Rect::new(0.0, 0.0, width as f32, height as f32);
+            let cr = snapshot.append_cairo(&bounds);
+
+            //the middle lightness, for reference
+            let color = widget.color();
+            cr.set_source_rgba(
+                color.red() as f64,
+                color.green() as f64,
+                color.blue() as f64,
+                color.alpha() as f64 * 0.2,
+            );
+            cr.set_line_width(1.0);
+            cr.move_to(0.0, (height / 2.0).floor() + 0.5);
+            cr.line_to(width, (height / 2.0).floor() + 0.5);
+            let _ = cr.stroke();
+
+            let inset = 2.0;
+            let point = |i: usize, rgba: &gdk::RGBA| {
+                let lightness = Oklab::from(*rgba).l.clamp(0.0, 1.0) as f64;
+                (
+                    i as f64 / (samples.len() - 1) as f64 * width,
+                    inset + (1.0 - lightness) * (height - inset * 2.0),
+                )
+            };
+
+            cr.set_line_width(3.0);
+            cr.set_line_cap(cairo::LineCap::Round);
+            for (i, pair) in samples.windows(2).enumerate() {
+                let (x0, y0) = point(i, &pair[0]);
+                let (x1, y1) = point(i + 1, &pair[1]);
+                cr.set_source_rgb(
+                    pair[0].red() as f64,
+                    pair[0].green() as f64,
+                    pair[0].blue() as f64,
+                );
+                cr.move_to(x0, y0);
+                cr.line_to(x1, y1);
+                let _ = cr.stroke();
+            }
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct VibrantLightnessPlot(ObjectSubclass<imp::VibrantLightnessPlot>)
+        @extends gtk::Widget,
+        @implements gtk::Accessible;
+}
+
+impl VibrantLightnessPlot {
+    pub fn set_samples(&self, samples: Vec<gdk::RGBA>) {
+        if *self.imp().samples.borrow() != samples {
+            self.imp().samples.replace(samples);
+            self.queue_draw();
+        }
+    }
+}
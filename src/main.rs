@@ -18,8 +18,54 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 
+mod accent;
 mod application;
+mod autosave;
+mod chart;
+mod chart_dialog;
+mod code_style;
+mod color;
+mod color_format;
+mod community;
+mod compass;
 mod config;
+mod document;
+#[cfg(test)]
+mod golden;
+mod gradient;
+mod grain;
+mod grain_layer;
+mod handle_layer;
+mod handles;
+mod http;
+mod icc;
+mod job;
+mod json;
+mod library;
+mod lightness_plot;
+mod material;
+mod palette;
+mod pattern;
+mod pdf;
+mod plymouth;
+mod png;
+mod precision;
+mod preferences;
+mod presets;
+mod print;
+mod readability;
+mod report;
+mod screenshot;
+mod snippet;
+mod stats;
+mod swatch;
+mod sync;
+mod tab;
+mod terminal;
+mod thumbnail;
+mod tokens;
+mod tiff;
+mod wallpaper;
 mod window;
 
 use self::application::VibrantApplication;
@@ -32,6 +78,12 @@ use gtk::{gio, glib};
 use gtk::prelude::*;
 
 fn main() -> glib::ExitCode {
+    // File managers run us to preview saved gradients, without a display
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some(thumbnail::OPTION) {
+        return thumbnail::run(&args[2..]);
+    }
+
     // Set up gettext translations
     bindtextdomain(GETTEXT_PACKAGE, LOCALEDIR).expect("Unable to bind the text domain");
     bind_textdomain_codeset(GETTEXT_PACKAGE, "UTF-8")
@@ -46,7 +98,7 @@ fn main() -> glib::ExitCode {
     // Create a new GtkApplication. The application manages our main loop,
     // application windows, integration with the window manager/compositor, and
     // desktop features such as file opening and single-instance applications.
-    let app = VibrantApplication::new("com.felipekinoshita.Vibrant", &gio::ApplicationFlags::empty());
+    let app = VibrantApplication::new("com.felipekinoshita.Vibrant", &gio::ApplicationFlags::HANDLES_OPEN);
 
     // Run the application. This function will block until the application
     // exits. Upon return, we have our exit code to return to the shell. (This
@@ -0,0 +1,198 @@
+/* material.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::gdk;
+
+use crate::color::{self, GamutMapping, Oklab, Oklch};
+use crate::gradient::Gradient;
+use crate::json::Value;
+
+/// Tones the Material Theme Builder lists for each palette.
+const TONES: [u32; 18] = [
+    0, 5, 10, 15, 20, 25, 30, 35, 40, 50, 60, 70, 80, 90, 95, 98, 99, 100,
+];
+
+/// Chroma of the palettes kept close to gray, in OKLCH.
+const SECONDARY_CHROMA: f32 = 0.05;
+const NEUTRAL_CHROMA: f32 = 0.012;
+const NEUTRAL_VARIANT_CHROMA: f32 = 0.025;
+
+/// Material's error red.
+const ERROR_SEED: &str = "#b3261e";
+
+/// A hue and chroma, shown at any tone.
+#[derive(Debug, Copy, Clone)]
+struct Palette {
+    hue: f32,
+    chroma: f32,
+}
+
+impl Palette {
+    fn new(rgba: &gdk::RGBA) -> Self {
+        let lch = Oklch::from(*rgba);
+        Self {
+            hue: lch.h,
+            chroma: lch.c,
+        }
+    }
+
+    fn with_chroma(self, chroma: f32) -> Self {
+        Self { chroma, ..self }
+    }
+
+    /// The color with a CIELAB lightness of `tone`, which is what Material
+    /// tones measure, losing chroma where sRGB can't hold it.
+    fn tone(self, tone: u32) -> gdk::RGBA {
+        let color = |l: f32| {
+            Oklab::from(Oklch {
+                l,
+                c: self.chroma,
+                h: self.hue,
+                alpha: 1.0,
+            })
+            .to_rgba_mapped(GamutMapping::ReduceChroma)
+        };
+
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..24 {
+            let middle = (low + high) / 2.0;
+            if lightness(&color(middle)) < tone as f32 {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+
+        color((low + high) / 2.0)
+    }
+
+    fn to_json(self) -> Value {
+        Value::Object(
+            TONES
+                .iter()
+                .map(|tone| (tone.to_string(), hex(&self.tone(*tone)).into()))
+                .collect(),
+        )
+    }
+}
+
+/// CIELAB L* of a color, from 0 to 100.
+fn lightness(rgba: &gdk::RGBA) -> f32 {
+    let y = color::luminance(rgba);
+    if y > 216.0 / 24389.0 {
+        116.0 * y.cbrt() - 16.0
+    } else {
+        y * 24389.0 / 27.0
+    }
+}
+
+fn hex(rgba: &gdk::RGBA) -> String {
+    color::to_hex(rgba).to_uppercase()
+}
+
+/// A Material Theme Builder export built around the gradient, with the
+/// first stop as the primary color and the last one as the tertiary.
+pub fn theme(gradient: &Gradient) -> String {
+    let first = gradient
+        .stops
+        .first()
+        .map(|stop| stop.color)
+        .unwrap_or(gdk::RGBA::BLACK);
+    let last = gradient
+        .stops
+        .last()
+        .map(|stop| stop.color)
+        .unwrap_or(first);
+
+    let primary = Palette::new(&first);
+    let secondary = primary.with_chroma(primary.chroma.min(SECONDARY_CHROMA));
+    let tertiary = Palette::new(&last);
+    let neutral = primary.with_chroma(NEUTRAL_CHROMA);
+    let neutral_variant = primary.with_chroma(NEUTRAL_VARIANT_CHROMA);
+    let error = Palette::new(&gdk::RGBA::parse(ERROR_SEED).unwrap());
+
+    //tones of each role in the light and dark schemes
+    let roles: [(&str, Palette, u32, u32); 25] = [
+        ("primary", primary, 40, 80),
+        ("onPrimary", primary, 100, 20),
+        ("primaryContainer", primary, 90, 30),
+        ("onPrimaryContainer", primary, 10, 90),
+        ("secondary", secondary, 40, 80),
+        ("onSecondary", secondary, 100, 20),
+        ("secondaryContainer", secondary, 90, 30),
+        ("onSecondaryContainer", secondary, 10, 90),
+        ("tertiary", tertiary, 40, 80),
+        ("onTertiary", tertiary, 100, 20),
+        ("tertiaryContainer", tertiary, 90, 30),
+        ("onTertiaryContainer", tertiary, 10, 90),
+        ("error", error, 40, 80),
+        ("onError", error, 100, 20),
+        ("errorContainer", error, 90, 30),
+        ("onErrorContainer", error, 10, 90),
+        ("background", neutral, 98, 6),
+        ("onBackground", neutral, 10, 90),
+        ("surface", neutral, 98, 6),
+        ("onSurface", neutral, 10, 90),
+        ("surfaceVariant", neutral_variant, 90, 30),
+        ("onSurfaceVariant", neutral_variant, 30, 80),
+        ("outline", neutral_variant, 50, 60),
+        ("outlineVariant", neutral_variant, 80, 30),
+        ("inverseSurface", neutral, 20, 90),
+    ];
+    let scheme = |dark: bool| {
+        Value::Object(
+            roles
+                .iter()
+                .map(|(name, palette, light_tone, dark_tone)| {
+                    let tone = if dark { *dark_tone } else { *light_tone };
+                    (name.to_string(), hex(&palette.tone(tone)).into())
+                })
+                .collect(),
+        )
+    };
+
+    Value::object([
+        ("description", "Generated by Vibrant".into()),
+        ("seed", hex(&first).into()),
+        (
+            "coreColors",
+            Value::object([
+                ("primary", hex(&first).into()),
+                ("tertiary", hex(&last).into()),
+            ]),
+        ),
+        ("extendedColors", Value::Array(Vec::new())),
+        (
+            "schemes",
+            Value::object([("light", scheme(false)), ("dark", scheme(true))]),
+        ),
+        (
+            "palettes",
+            Value::object([
+                ("primary", primary.to_json()),
+                ("secondary", secondary.to_json()),
+                ("tertiary", tertiary.to_json()),
+                ("neutral", neutral.to_json()),
+                ("neutral-variant", neutral_variant.to_json()),
+            ]),
+        ),
+    ])
+    .to_json()
+}
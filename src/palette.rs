@@ -0,0 +1,494 @@
+/* palette.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::path::PathBuf;
+
+use gtk::prelude::*;
+use gtk::{gdk, gio, glib};
+
+use crate::color;
+use crate::json::Value;
+
+#[derive(Debug, Clone, Default)]
+pub struct Palette {
+    pub name: String,
+    pub colors: Vec<String>,
+}
+
+/// Directory holding the user's saved gradients and palettes.
+pub fn library_dir() -> PathBuf {
+    glib::user_data_dir().join("vibrant")
+}
+
+/// Name of the file holding the saved palettes in the library.
+pub const PALETTES_FILE: &str = "palettes.gvariant";
+
+fn palettes_path() -> PathBuf {
+    library_dir().join(PALETTES_FILE)
+}
+
+/// Reads the saved palettes, stored as a GVariant of type `a(sas)`.
+pub fn load() -> Vec<Palette> {
+    let Ok(text) = std::fs::read_to_string(palettes_path()) else {
+        return Vec::new();
+    };
+
+    glib::Variant::parse(Some(glib::VariantTy::new("a(sas)").unwrap()), &text)
+        .ok()
+        .and_then(|variant| variant.get::<Vec<(String, Vec<String>)>>())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, colors)| Palette { name, colors })
+        .collect()
+}
+
+pub fn save(palettes: &[Palette]) -> Result<(), glib::Error> {
+    let variant = palettes
+        .iter()
+        .map(|palette| (palette.name.clone(), palette.colors.clone()))
+        .collect::<Vec<_>>()
+        .to_variant();
+
+    std::fs::create_dir_all(library_dir())
+        .and_then(|()| std::fs::write(palettes_path(), variant.print(true).as_str()))
+        .map_err(|err| glib::Error::new(gio::IOErrorEnum::Failed, &err.to_string()))
+}
+
+fn invalid_data(message: &str) -> glib::Error {
+    glib::Error::new(gio::IOErrorEnum::InvalidData, message)
+}
+
+fn rgb_to_hex(red: f32, green: f32, blue: f32) -> String {
+    color::to_hex(&gdk::RGBA::new(red, green, blue, 1.0))
+}
+
+/// Parses a GIMP palette, where each line after the header holds the red,
+/// green and blue values followed by an optional color name.
+pub fn parse_gpl(text: &str, fallback_name: &str) -> Result<Palette, glib::Error> {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some("GIMP Palette") {
+        return Err(invalid_data("Missing GIMP Palette header"));
+    }
+
+    let mut palette = Palette {
+        name: fallback_name.to_owned(),
+        colors: Vec::new(),
+    };
+
+    for line in lines.map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix("Name:") {
+            palette.name = name.trim().to_owned();
+            continue;
+        }
+
+        if line.starts_with("Columns:") {
+            continue;
+        }
+
+        let values: Vec<f32> = line
+            .split_whitespace()
+            .take(3)
+            .map_while(|value| value.parse::<u8>().ok())
+            .map(|value| value as f32 / 255.0)
+            .collect();
+
+        if let [red, green, blue] = values[..] {
+            palette.colors.push(rgb_to_hex(red, green, blue));
+        }
+    }
+
+    Ok(palette)
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], glib::Error> {
+        if self.data.len() < len {
+            return Err(invalid_data("Unexpected end of swatch exchange file"));
+        }
+
+        let (head, tail) = self.data.split_at(len);
+        self.data = tail;
+        Ok(head)
+    }
+
+    fn u16(&mut self) -> Result<u16, glib::Error> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, glib::Error> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn f32(&mut self) -> Result<f32, glib::Error> {
+        Ok(f32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+const ASE_GROUP_START: u16 = 0xc001;
+const ASE_COLOR_ENTRY: u16 = 0x0001;
+
+/// Parses an Adobe Swatch Exchange file. Colors in other models than RGB are
+/// approximated in sRGB, and group names are used as the palette name.
+pub fn parse_ase(data: &[u8], fallback_name: &str) -> Result<Palette, glib::Error> {
+    let mut reader = Reader { data };
+    if reader.take(4)? != b"ASEF" {
+        return Err(invalid_data("Missing ASEF signature"));
+    }
+
+    //major and minor version
+    reader.u16()?;
+    reader.u16()?;
+
+    let mut palette = Palette {
+        name: fallback_name.to_owned(),
+        colors: Vec::new(),
+    };
+
+    let blocks = reader.u32()?;
+    for _ in 0..blocks {
+        let kind = reader.u16()?;
+        let length = reader.u32()? as usize;
+        let mut block = Reader {
+            data: reader.take(length)?,
+        };
+
+        if kind != ASE_COLOR_ENTRY && kind != ASE_GROUP_START {
+            continue;
+        }
+
+        //name is a null terminated UTF-16 string, its length counted in code units
+        let name_len = block.u16()? as usize;
+        let name: Vec<u16> = block
+            .take(name_len * 2)?
+            .chunks_exact(2)
+            .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+            .take_while(|unit| *unit != 0)
+            .collect();
+
+        if kind == ASE_GROUP_START {
+            if palette.colors.is_empty() {
+                palette.name = String::from_utf16_lossy(&name);
+            }
+            continue;
+        }
+
+        let color = match block.take(4)? {
+            b"RGB " => rgb_to_hex(block.f32()?, block.f32()?, block.f32()?),
+            b"CMYK" => {
+                let (c, m, y, k) = (block.f32()?, block.f32()?, block.f32()?, block.f32()?);
+                rgb_to_hex(
+                    (1.0 - c) * (1.0 - k),
+                    (1.0 - m) * (1.0 - k),
+                    (1.0 - y) * (1.0 - k),
+                )
+            }
+            b"Gray" => {
+                let gray = block.f32()?;
+                rgb_to_hex(gray, gray, gray)
+            }
+            b"LAB " => {
+                let (l, a, b) = (block.f32()?, block.f32()?, block.f32()?);
+                let (red, green, blue) = lab_to_rgb(l * 100.0, a, b);
+                rgb_to_hex(red, green, blue)
+            }
+            _ => return Err(invalid_data("Unknown color model")),
+        };
+
+        palette.colors.push(color);
+    }
+
+    Ok(palette)
+}
+
+/// Converts CIE L*a*b* under the D50 illuminant to sRGB.
+fn lab_to_rgb(l: f32, a: f32, b: f32) -> (f32, f32, f32) {
+    let fy = (l + 16.0) / 116.0;
+    let fx = fy + a / 500.0;
+    let fz = fy - b / 200.0;
+
+    let f_inv = |t: f32| {
+        if t.powi(3) > 0.008856 {
+            t.powi(3)
+        } else {
+            (t - 16.0 / 116.0) / 7.787
+        }
+    };
+
+    let x = 0.9642 * f_inv(fx);
+    let y = f_inv(fy);
+    let z = 0.8249 * f_inv(fz);
+
+    //D50 XYZ to linear sRGB, with Bradford adaptation to D65
+    let r = 3.1339 * x - 1.6169 * y - 0.4906 * z;
+    let g = -0.9788 * x + 1.9161 * y + 0.0335 * z;
+    let b = 0.0719 * x - 0.2290 * y + 1.4052 * z;
+
+    let gamma = |c: f32| {
+        if c <= 0.0031308 {
+            12.92 * c
+        } else {
+            1.055 * c.powf(1.0 / 2.4) - 0.055
+        }
+    };
+
+    (gamma(r), gamma(g), gamma(b))
+}
+
+/// Writes a GIMP palette, with the hex code of each color as its name.
+pub fn to_gpl(palette: &Palette) -> String {
+    let mut text = format!("GIMP Palette\nName: {}\nColumns: 0\n#\n", palette.name);
+    for hex in &palette.colors {
+        if let Ok(rgba) = gdk::RGBA::parse(hex) {
+            text.push_str(&format!(
+                "{:3} {:3} {:3}\t{}\n",
+                (rgba.red() * 255.0).round() as u8,
+                (rgba.green() * 255.0).round() as u8,
+                (rgba.blue() * 255.0).round() as u8,
+                hex
+            ));
+        }
+    }
+    text
+}
+
+/// A null terminated UTF-16 string preceded by its length in code units, as
+/// names are stored in ASE files.
+fn ase_name(name: &str) -> Vec<u8> {
+    let mut units: Vec<u16> = name.encode_utf16().collect();
+    units.push(0);
+
+    let mut data = (units.len() as u16).to_be_bytes().to_vec();
+    data.extend(units.iter().flat_map(|unit| unit.to_be_bytes()));
+    data
+}
+
+fn ase_block(kind: u16, data: &[u8]) -> Vec<u8> {
+    let mut block = kind.to_be_bytes().to_vec();
+    block.extend((data.len() as u32).to_be_bytes());
+    block.extend(data);
+    block
+}
+
+/// Writes an Adobe Swatch Exchange file with the colors as global RGB
+/// swatches, grouped under the name of the palette.
+pub fn to_ase(palette: &Palette) -> Vec<u8> {
+    const ASE_GROUP_END: u16 = 0xc002;
+    //global, as opposed to spot or process colors
+    const ASE_GLOBAL: u16 = 0;
+
+    let colors: Vec<gdk::RGBA> = palette
+        .colors
+        .iter()
+        .filter_map(|hex| gdk::RGBA::parse(hex).ok())
+        .collect();
+
+    let mut data = b"ASEF".to_vec();
+    data.extend(1u16.to_be_bytes());
+    data.extend(0u16.to_be_bytes());
+    data.extend((colors.len() as u32 + 2).to_be_bytes());
+
+    data.extend(ase_block(ASE_GROUP_START, &ase_name(&palette.name)));
+    for rgba in &colors {
+        let mut entry = ase_name(&color::to_hex(rgba));
+        entry.extend(b"RGB ");
+        for channel in [rgba.red(), rgba.green(), rgba.blue()] {
+            entry.extend(channel.to_be_bytes());
+        }
+        entry.extend(ASE_GLOBAL.to_be_bytes());
+        data.extend(ase_block(ASE_COLOR_ENTRY, &entry));
+    }
+    data.extend(ase_block(ASE_GROUP_END, &[]));
+
+    data
+}
+
+/// Writes the palette as a JSON object with its name and hex codes.
+pub fn to_json(palette: &Palette) -> String {
+    Value::object([
+        ("name", palette.name.as_str().into()),
+        ("colors", palette.colors.clone().into()),
+    ])
+    .to_json()
+}
+
+/// Writes a palette as `.gpl`, `.ase` or `.json`, chosen by the extension
+/// of the file and defaulting to a GIMP palette.
+pub async fn export(palette: &Palette, file: &gio::File) -> Result<(), glib::Error> {
+    let path = file.path().unwrap_or_default();
+    let data = match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("ase") => to_ase(palette),
+        Some(ext) if ext.eq_ignore_ascii_case("json") => to_json(palette).into_bytes(),
+        _ => to_gpl(palette).into_bytes(),
+    };
+
+    file.replace_contents_future(data, None, false, gio::FileCreateFlags::REPLACE_DESTINATION)
+        .await
+        .map(|_| ())
+        .map_err(|(_, err)| err)
+}
+
+/// Reads a palette from a `.gpl` or `.ase` file, chosen by its extension.
+pub async fn import(file: &gio::File) -> Result<Palette, glib::Error> {
+    let (data, _) = file.load_contents_future().await?;
+
+    let path = file.path().unwrap_or_default();
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if ext.eq_ignore_ascii_case("ase") => parse_ase(&data, &name),
+        _ => parse_gpl(&String::from_utf8_lossy(&data), &name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A block of a swatch exchange file, with its name as UTF-16.
+    fn ase_block(kind: u16, name: &str, body: &[u8]) -> Vec<u8> {
+        let mut data = Vec::new();
+        if !name.is_empty() {
+            let units: Vec<u16> = name.encode_utf16().chain([0]).collect();
+            data.extend((units.len() as u16).to_be_bytes());
+            for unit in units {
+                data.extend(unit.to_be_bytes());
+            }
+        }
+        data.extend(body);
+
+        let mut block = kind.to_be_bytes().to_vec();
+        block.extend((data.len() as u32).to_be_bytes());
+        block.extend(data);
+        block
+    }
+
+    fn ase_color(name: &str, model: &[u8; 4], values: &[f32]) -> Vec<u8> {
+        let mut body = model.to_vec();
+        for value in values {
+            body.extend(value.to_be_bytes());
+        }
+        //global color type
+        body.extend(0u16.to_be_bytes());
+        ase_block(ASE_COLOR_ENTRY, name, &body)
+    }
+
+    fn ase_file(blocks: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = b"ASEF".to_vec();
+        data.extend(1u16.to_be_bytes());
+        data.extend(0u16.to_be_bytes());
+        data.extend((blocks.len() as u32).to_be_bytes());
+        for block in blocks {
+            data.extend(block);
+        }
+        data
+    }
+
+    #[test]
+    fn ase_color_models() {
+        let data = ase_file(&[
+            ase_color("Red", b"RGB ", &[1.0, 0.0, 0.0]),
+            ase_color("Cyan", b"CMYK", &[1.0, 0.0, 0.0, 0.0]),
+            ase_color("Gray", b"Gray", &[0.5]),
+            ase_color("White", b"LAB ", &[1.0, 0.0, 0.0]),
+        ]);
+
+        let palette = parse_ase(&data, "Fallback").unwrap();
+        assert_eq!(palette.name, "Fallback");
+        assert_eq!(palette.colors, ["#ff0000", "#00ffff", "#808080", "#ffffff"]);
+    }
+
+    #[test]
+    fn ase_groups() {
+        let data = ase_file(&[
+            ase_block(ASE_GROUP_START, "Brand", &[]),
+            ase_color("Red", b"RGB ", &[1.0, 0.0, 0.0]),
+            ase_block(0xc002, "", &[]),
+            ase_block(ASE_GROUP_START, "Other", &[]),
+            ase_color("Blue", b"RGB ", &[0.0, 0.0, 1.0]),
+            ase_block(0xc002, "", &[]),
+        ]);
+
+        let palette = parse_ase(&data, "Fallback").unwrap();
+        assert_eq!(palette.name, "Brand");
+        assert_eq!(palette.colors, ["#ff0000", "#0000ff"]);
+    }
+
+    #[test]
+    fn ase_truncated_block() {
+        let mut data = ase_file(&[ase_color("Red", b"RGB ", &[1.0, 0.0, 0.0])]);
+        data.truncate(data.len() - 6);
+
+        assert!(parse_ase(&data, "Fallback").is_err());
+    }
+
+    #[test]
+    fn ase_wrong_block_length() {
+        let mut data = ase_file(&[ase_color("Red", b"RGB ", &[1.0, 0.0, 0.0])]);
+        //claim fewer bytes than the color values need
+        let length_at = 12 + 2;
+        let length = u32::from_be_bytes(data[length_at..length_at + 4].try_into().unwrap());
+        data[length_at..length_at + 4].copy_from_slice(&(length - 8).to_be_bytes());
+
+        assert!(parse_ase(&data, "Fallback").is_err());
+    }
+
+    #[test]
+    fn ase_missing_signature() {
+        assert!(parse_ase(b"ASEX\0\x01\0\0\0\0\0\0", "Fallback").is_err());
+    }
+
+    #[test]
+    fn gpl_with_comments() {
+        let text = "GIMP Palette\n\
+                    Name: Sunset\n\
+                    Columns: 2\n\
+                    # a comment\n\
+                    \n\
+                    255   0   0 Red\n\
+                      0 128 255\tSky\n";
+
+        let palette = parse_gpl(text, "Fallback").unwrap();
+        assert_eq!(palette.name, "Sunset");
+        assert_eq!(palette.colors, ["#ff0000", "#0080ff"]);
+    }
+
+    #[test]
+    fn gpl_without_name() {
+        let palette = parse_gpl("GIMP Palette\n0 0 0\n", "Fallback").unwrap();
+        assert_eq!(palette.name, "Fallback");
+        assert_eq!(palette.colors, ["#000000"]);
+    }
+
+    #[test]
+    fn gpl_missing_header() {
+        assert!(parse_gpl("0 0 0\n", "Fallback").is_err());
+    }
+}
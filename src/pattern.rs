@@ -0,0 +1,313 @@
+/* pattern.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::f64::consts::PI;
+
+use gtk::prelude::*;
+use gtk::{cairo, gdk, graphene, gsk};
+
+use crate::color;
+
+/// Size of a pattern tile at scale 1, in pixels.
+const TILE_SIZE: f32 = 16.0;
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum PatternKind {
+    #[default]
+    None,
+    Dots,
+    Lines,
+    Grid,
+    Waves,
+}
+
+impl From<u32> for PatternKind {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::Dots,
+            2 => Self::Lines,
+            3 => Self::Grid,
+            4 => Self::Waves,
+            _ => Self::None,
+        }
+    }
+}
+
+impl From<PatternKind> for u32 {
+    fn from(value: PatternKind) -> Self {
+        match value {
+            PatternKind::None => 0,
+            PatternKind::Dots => 1,
+            PatternKind::Lines => 2,
+            PatternKind::Grid => 3,
+            PatternKind::Waves => 4,
+        }
+    }
+}
+
+impl PatternKind {
+    /// The name used in saved documents.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Dots => "dots",
+            Self::Lines => "lines",
+            Self::Grid => "grid",
+            Self::Waves => "waves",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Self::None),
+            "dots" => Some(Self::Dots),
+            "lines" => Some(Self::Lines),
+            "grid" => Some(Self::Grid),
+            "waves" => Some(Self::Waves),
+            _ => None,
+        }
+    }
+}
+
+/// A CSS `url()` embedding an SVG image as a data URI.
+pub fn svg_url(svg: &str) -> String {
+    let escaped = svg
+        .replace('%', "%25")
+        .replace('#', "%23")
+        .replace('<', "%3C")
+        .replace('>', "%3E");
+    format!("url(\"data:image/svg+xml,{escaped}\")")
+}
+
+/// A tiled overlay composited over the gradient.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Pattern {
+    pub kind: PatternKind,
+    pub color: gdk::RGBA,
+    pub opacity: f32,
+    pub scale: f32,
+}
+
+impl Default for Pattern {
+    fn default() -> Self {
+        Self {
+            kind: PatternKind::None,
+            color: gdk::RGBA::WHITE,
+            opacity: 0.25,
+            scale: 1.0,
+        }
+    }
+}
+
+impl Pattern {
+    pub fn is_visible(&self) -> bool {
+        self.kind != PatternKind::None && self.opacity > 0.0
+    }
+
+    /// Tile size, rounded so repeated tiles don't leave seams.
+    pub fn tile_size(&self) -> f32 {
+        (TILE_SIZE * self.scale).round().max(2.0)
+    }
+
+    fn stroke_width(&self) -> f32 {
+        (self.tile_size() / 8.0).round().max(1.0)
+    }
+
+    fn dot_radius(&self) -> f32 {
+        (self.tile_size() * 0.15).round().max(1.0)
+    }
+
+    fn ink(&self) -> String {
+        color::to_hex(&gdk::RGBA::new(
+            self.color.red(),
+            self.color.green(),
+            self.color.blue(),
+            self.color.alpha() * self.opacity,
+        ))
+    }
+
+    /// The shapes of one tile as SVG elements, using single quotes so they
+    /// can be embedded in CSS data URIs.
+    pub fn svg_shapes(&self) -> String {
+        let size = self.tile_size();
+        let half = size / 2.0;
+        let width = self.stroke_width();
+        let ink = self.ink();
+
+        match self.kind {
+            PatternKind::None => String::new(),
+            PatternKind::Dots => {
+                format!("<circle cx='{half}' cy='{half}' r='{}' fill='{ink}'/>", self.dot_radius())
+            }
+            PatternKind::Lines => format!(
+                "<path d='M0,{size} L{size},0 M-{width},{width} L{width},-{width} M{},{} L{},{}' stroke='{ink}' stroke-width='{width}'/>",
+                size - width,
+                size + width,
+                size + width,
+                size - width,
+            ),
+            PatternKind::Grid => format!(
+                "<path d='M0,0 H{size} V{width} H{width} V{size} H0 Z' fill='{ink}'/>"
+            ),
+            PatternKind::Waves => {
+                let (near, far, dip) = (size / 6.0, size / 3.0, size / 6.0);
+                format!(
+                    "<path d='M0,{half} C{near},{} {far},{} {half},{half} S{},{} {size},{half}' fill='none' stroke='{ink}' stroke-width='{width}'/>",
+                    half - dip,
+                    half - dip,
+                    size - near,
+                    half + dip,
+                )
+            }
+        }
+    }
+
+    /// The pattern as CSS background layers, or `None` when there's nothing
+    /// to draw. Waves can't be described with gradients, so they are
+    /// embedded as an SVG tile.
+    pub fn css_layers(&self) -> Option<String> {
+        if !self.is_visible() {
+            return None;
+        }
+
+        let size = self.tile_size();
+        let width = self.stroke_width();
+        let ink = self.ink();
+        let tiled = |image: String| format!("{image} 0 0 / {size}px {size}px");
+
+        let layers = match self.kind {
+            PatternKind::None => return None,
+            PatternKind::Dots => tiled(format!(
+                "radial-gradient(circle, {ink} {}px, transparent {}px)",
+                self.dot_radius(),
+                self.dot_radius() + 0.5
+            )),
+            PatternKind::Lines => format!(
+                "repeating-linear-gradient(135deg, {ink} 0 {width}px, transparent {width}px {:.2}px)",
+                size / std::f32::consts::SQRT_2
+            ),
+            PatternKind::Grid => [
+                tiled(format!("linear-gradient({ink} {width}px, transparent {width}px)")),
+                tiled(format!("linear-gradient(90deg, {ink} {width}px, transparent {width}px)")),
+            ]
+            .join(", "),
+            PatternKind::Waves => {
+                let svg = format!(
+                    "<svg xmlns='http://www.w3.org/2000/svg' width='{size}' height='{size}'>{}</svg>",
+                    self.svg_shapes()
+                );
+                tiled(svg_url(&svg))
+            }
+        };
+
+        Some(layers)
+    }
+
+    fn draw_tile(&self, cr: &cairo::Context) {
+        let size = self.tile_size() as f64;
+        let half = size / 2.0;
+        let width = self.stroke_width() as f64;
+
+        cr.set_line_width(width);
+        match self.kind {
+            PatternKind::None => {}
+            PatternKind::Dots => {
+                cr.arc(half, half, self.dot_radius() as f64, 0.0, 2.0 * PI);
+                let _ = cr.fill();
+            }
+            PatternKind::Lines => {
+                //the corners complete the lines of the neighboring tiles
+                for (x, y) in [(0.0, size), (-size, size), (size, size)] {
+                    cr.move_to(x, y);
+                    cr.line_to(x + size, y - size);
+                }
+                let _ = cr.stroke();
+            }
+            PatternKind::Grid => {
+                cr.rectangle(0.0, 0.0, size, width);
+                cr.rectangle(0.0, 0.0, width, size);
+                let _ = cr.fill();
+            }
+            PatternKind::Waves => {
+                let (near, far, dip) = (size / 6.0, size / 3.0, size / 6.0);
+                cr.move_to(0.0, half);
+                cr.curve_to(near, half - dip, far, half - dip, half, half);
+                cr.curve_to(size - far, half + dip, size - near, half + dip, size, half);
+                let _ = cr.stroke();
+            }
+        }
+    }
+
+    /// The pattern over `bounds`, repeating a single tile.
+    pub fn node(&self, bounds: &graphene::Rect) -> Option<gsk::RenderNode> {
+        if !self.is_visible() {
+            return None;
+        }
+
+        let size = self.tile_size() as i32;
+        let tile = cairo::ImageSurface::create(cairo::Format::ARgb32, size, size).ok()?;
+
+        if let Ok(cr) = cairo::Context::new(&tile) {
+            cr.set_source_rgba(
+                self.color.red() as f64,
+                self.color.green() as f64,
+                self.color.blue() as f64,
+                self.color.alpha() as f64,
+            );
+            self.draw_tile(&cr);
+        }
+
+        let node = gsk::CairoNode::new(bounds);
+        let cr = node.draw_context();
+        cr.translate(bounds.x() as f64, bounds.y() as f64);
+
+        let pattern = cairo::SurfacePattern::create(&tile);
+        pattern.set_extend(cairo::Extend::Repeat);
+        let _ = cr.set_source(&pattern);
+        let _ = cr.paint_with_alpha(self.opacity as f64);
+        drop(cr);
+
+        Some(node.upcast())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden;
+
+    #[test]
+    fn scaled_grid() {
+        //tiles of 24 pixels with lines of 3, so nothing is antialiased
+        let pattern = Pattern {
+            kind: PatternKind::Grid,
+            scale: 1.5,
+            ..Default::default()
+        };
+        let bounds = graphene::Rect::new(0.0, 0.0, 64.0, 32.0);
+
+        let node = gsk::ContainerNode::new(&[
+            gsk::ColorNode::new(&gdk::RGBA::BLACK, &bounds).upcast(),
+            pattern.node(&bounds).unwrap(),
+        ]);
+
+        golden::assert_matches(&golden::render(&node, &bounds), "pattern-grid", 2.0 / 255.0);
+    }
+}
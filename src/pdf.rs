@@ -0,0 +1,57 @@
+/* pdf.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::{cairo, gio, glib, graphene};
+
+use crate::gradient::Gradient;
+
+/// PDF sizes are in points.
+const POINTS_PER_MILLIMETER: f64 = 72.0 / 25.4;
+
+fn failed(message: impl ToString) -> glib::Error {
+    glib::Error::new(gio::IOErrorEnum::Failed, &message.to_string())
+}
+
+/// A single page PDF filled with the gradient, `width` by `height`
+/// millimeters. Cairo keeps the gradients as vector shadings, only the grain
+/// ends up as an image.
+pub fn render(gradient: &Gradient, width: f64, height: f64) -> Result<Vec<u8>, glib::Error> {
+    let (width, height) = (
+        width * POINTS_PER_MILLIMETER,
+        height * POINTS_PER_MILLIMETER,
+    );
+
+    let bounds = graphene::Rect::new(0.0, 0.0, width as f32, height as f32);
+    let node = gradient
+        .node(&bounds)
+        .ok_or_else(|| failed("Nothing to draw"))?;
+
+    let surface = cairo::PdfSurface::for_stream(width, height, Vec::<u8>::new()).map_err(failed)?;
+    let context = cairo::Context::new(&surface).map_err(failed)?;
+    node.draw(&context);
+    drop(context);
+
+    surface
+        .finish_output_stream()
+        .map_err(|err| failed(err.error))?
+        .downcast::<Vec<u8>>()
+        .map(|pdf| *pdf)
+        .map_err(|_| failed("Unexpected output stream"))
+}
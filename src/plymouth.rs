@@ -0,0 +1,121 @@
+/* plymouth.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::path::{Path, PathBuf};
+
+use gtk::{gdk, gio, glib};
+
+use crate::gradient::Gradient;
+use crate::png;
+use crate::wallpaper;
+
+/// Name of the theme, its folder and its files.
+const THEME_NAME: &str = "vibrant-gradient";
+
+/// Sizes of the backgrounds, the script picking the closest to the screen.
+const SIZES: [(i32, i32); 3] = [(1366, 768), (1920, 1080), (3840, 2160)];
+
+/// Draws the background that fits the screen best, and a prompt with a
+/// bullet for every typed character when a password is asked for, as for
+/// encrypted disks.
+const SCRIPT: &str = r#"width = Window.GetWidth();
+height = Window.GetHeight();
+
+file = "background-1366x768.png";
+if (width > 1366) file = "background-1920x1080.png";
+if (width > 1920) file = "background-3840x2160.png";
+
+background.image = Image(file).Scale(width, height);
+background.sprite = Sprite(background.image);
+background.sprite.SetZ(-100);
+
+fun display_password_callback(prompt, bullets) {
+    text = prompt + "  ";
+    i = 0;
+    while (i < bullets) {
+        text += "•";
+        i++;
+    }
+    label.image = Image.Text(text, 1, 1, 1);
+    label.sprite = Sprite(label.image);
+    label.sprite.SetPosition(width / 2 - label.image.GetWidth() / 2, height / 2, 1);
+}
+Plymouth.SetDisplayPasswordFunction(display_password_callback);
+
+fun display_normal_callback() {
+    if (label.sprite) label.sprite.SetOpacity(0);
+}
+Plymouth.SetDisplayNormalFunction(display_normal_callback);
+"#;
+
+fn failed(message: impl ToString) -> glib::Error {
+    glib::Error::new(gio::IOErrorEnum::Failed, &message.to_string())
+}
+
+/// Plymouth colors are channels from 0 to 1.
+fn channels(rgba: &gdk::RGBA) -> String {
+    format!("{}, {}, {}", rgba.red(), rgba.green(), rgba.blue())
+}
+
+/// Writes a Plymouth script theme showing the gradient into a new folder
+/// of `parent`, to be copied to `/usr/share/plymouth/themes`. Returns the
+/// folder of the theme.
+pub fn export(gradient: &Gradient, parent: &Path) -> Result<PathBuf, glib::Error> {
+    let dir = parent.join(THEME_NAME);
+    std::fs::create_dir_all(&dir).map_err(failed)?;
+
+    for (width, height) in SIZES {
+        let bounds = gdk::Rectangle::new(0, 0, width, height);
+        let texture = wallpaper::render_layout(gradient, &[bounds], 1, true)
+            .ok_or_else(|| failed("Could not render the background"))?;
+        png::save_srgb(
+            &texture,
+            &dir.join(format!("background-{width}x{height}.png")),
+        )?;
+    }
+
+    //shown while the images load, and by renderers without image support
+    let first = gradient
+        .stops
+        .first()
+        .map(|stop| stop.color)
+        .unwrap_or(gdk::RGBA::BLACK);
+    let last = gradient
+        .stops
+        .last()
+        .map(|stop| stop.color)
+        .unwrap_or(first);
+    let script = format!(
+        "Window.SetBackgroundTopColor({});\nWindow.SetBackgroundBottomColor({});\n\n{SCRIPT}",
+        channels(&first),
+        channels(&last)
+    );
+    std::fs::write(dir.join(format!("{THEME_NAME}.script")), script).map_err(failed)?;
+
+    let installed = Path::new("/usr/share/plymouth/themes").join(THEME_NAME);
+    let theme = format!(
+        "[Plymouth Theme]\nName=Vibrant Gradient\nDescription=A gradient made with Vibrant\nModuleName=script\n\n[script]\nImageDir={}\nScriptFile={}\n",
+        installed.display(),
+        installed.join(format!("{THEME_NAME}.script")).display()
+    );
+    std::fs::write(dir.join(format!("{THEME_NAME}.plymouth")), theme).map_err(failed)?;
+
+    Ok(dir)
+}
@@ -0,0 +1,184 @@
+/* png.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::path::Path;
+
+use gtk::prelude::*;
+use gtk::{gdk, gio, glib};
+
+use crate::icc::ColorSpace;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+/// Length of the IHDR chunk, which always comes first.
+const HEADER_LENGTH: usize = 8 + 4 + 4 + 13 + 4;
+
+/// The CRC-32 used by PNG chunks.
+fn crc(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut chunk = Vec::with_capacity(data.len() + 12);
+    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(kind);
+    chunk.extend_from_slice(data);
+    chunk.extend_from_slice(&crc(&chunk[4..]).to_be_bytes());
+    chunk
+}
+
+fn zlib(data: &[u8]) -> Result<Vec<u8>, glib::Error> {
+    let output = gio::MemoryOutputStream::new_resizable();
+    let compressor = gio::ZlibCompressor::new(gio::ZlibCompressorFormat::Zlib, -1);
+    let stream = gio::ConverterOutputStream::new(&output, &compressor);
+    if let (_, Some(err)) = stream.write_all(data, gio::Cancellable::NONE)? {
+        return Err(err);
+    }
+    stream.close(gio::Cancellable::NONE)?;
+
+    Ok(output.steal_as_bytes().to_vec())
+}
+
+/// The chunks tagging an image with a color space: its ICC profile, with
+/// the gamma and primaries it implies for readers that only know about
+/// those. PNG has readers ignore an `sRGB` chunk next to a profile, so
+/// there is none.
+fn color_chunks(space: ColorSpace) -> Result<Vec<u8>, glib::Error> {
+    let mut profile = space.name().as_bytes().to_vec();
+    //name terminator and deflate compression
+    profile.extend([0, 0]);
+    profile.extend(zlib(&space.profile())?);
+    let mut chunks = chunk(b"iCCP", &profile);
+
+    chunks.extend(chunk(b"gAMA", &45455u32.to_be_bytes()));
+    let data: Vec<u8> = space
+        .chromaticities()
+        .into_iter()
+        .flat_map(|(x, y)| [x, y])
+        .flat_map(|value| ((value * 100_000.0).round() as u32).to_be_bytes())
+        .collect();
+    chunks.extend(chunk(b"cHRM", &data));
+
+    Ok(chunks)
+}
+
+/// Encodes the texture as a PNG tagged with `space`, which its channels
+/// are in, so color managed editors and viewers show the same colors as
+/// the preview.
+pub fn encode(texture: &gdk::Texture, space: ColorSpace) -> Result<Vec<u8>, glib::Error> {
+    let bytes = texture.save_to_png_bytes();
+    if bytes.len() < HEADER_LENGTH || bytes[..8] != SIGNATURE {
+        return Err(glib::Error::new(
+            gio::IOErrorEnum::InvalidData,
+            "Could not encode the image",
+        ));
+    }
+
+    let mut png = Vec::with_capacity(bytes.len() + 64);
+    png.extend_from_slice(&bytes[..HEADER_LENGTH]);
+    png.extend(color_chunks(space)?);
+    png.extend_from_slice(&bytes[HEADER_LENGTH..]);
+
+    Ok(png)
+}
+
+pub fn encode_srgb(texture: &gdk::Texture) -> Result<Vec<u8>, glib::Error> {
+    encode(texture, ColorSpace::Srgb)
+}
+
+pub fn save_srgb(texture: &gdk::Texture, path: &Path) -> Result<(), glib::Error> {
+    let png = encode_srgb(texture)?;
+    std::fs::write(path, png)
+        .map_err(|err| glib::Error::new(gio::IOErrorEnum::Failed, &err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::golden;
+    use crate::wallpaper;
+
+    /// The name and ICC profile of the `iCCP` chunk, right after the header.
+    fn embedded_profile(png: &[u8]) -> (String, Vec<u8>) {
+        let length = u32::from_be_bytes(png[HEADER_LENGTH..HEADER_LENGTH + 4].try_into().unwrap());
+        assert_eq!(&png[HEADER_LENGTH + 4..HEADER_LENGTH + 8], b"iCCP");
+        let data = &png[HEADER_LENGTH + 8..HEADER_LENGTH + 8 + length as usize];
+
+        let name = data.iter().position(|byte| *byte == 0).unwrap();
+        assert_eq!(data[name + 1], 0);
+
+        let output = gio::MemoryOutputStream::new_resizable();
+        let decompressor = gio::ZlibDecompressor::new(gio::ZlibCompressorFormat::Zlib);
+        let stream = gio::ConverterOutputStream::new(&output, &decompressor);
+        stream
+            .write_all(&data[name + 2..], gio::Cancellable::NONE)
+            .unwrap();
+        stream.close(gio::Cancellable::NONE).unwrap();
+        (
+            String::from_utf8(data[..name].to_vec()).unwrap(),
+            output.steal_as_bytes().to_vec(),
+        )
+    }
+
+    #[test]
+    fn exported_ramp() {
+        let bounds = gdk::Rectangle::new(0, 0, 64, 16);
+        let texture = wallpaper::render_layout(&golden::ramp(), &[bounds], 1, true).unwrap();
+
+        let png = encode_srgb(&texture).unwrap();
+        assert_eq!(png[..8], SIGNATURE);
+        assert_eq!(
+            embedded_profile(&png),
+            ("sRGB".to_owned(), ColorSpace::Srgb.profile())
+        );
+
+        let decoded = gdk::Texture::from_bytes(&glib::Bytes::from_owned(png)).unwrap();
+        golden::assert_matches(&decoded, "png-ramp", 2.0 / 255.0);
+    }
+
+    #[test]
+    fn display_p3() {
+        let bounds = gdk::Rectangle::new(0, 0, 8, 8);
+        let gradient = golden::ramp().to_display_p3();
+        let texture = wallpaper::render_layout(&gradient, &[bounds], 1, true).unwrap();
+
+        let png = encode(&texture, ColorSpace::DisplayP3).unwrap();
+        assert_eq!(
+            embedded_profile(&png),
+            ("Display P3".to_owned(), ColorSpace::DisplayP3.profile())
+        );
+
+        //sRGB red is well inside the P3 gamut
+        let red = gradient.stops[0].color;
+        assert!((red.red() - 0.9175).abs() < 0.001);
+        assert!((red.green() - 0.2003).abs() < 0.001);
+        assert!((red.blue() - 0.1386).abs() < 0.001);
+    }
+}
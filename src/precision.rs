@@ -0,0 +1,171 @@
+/* precision.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use gtk::prelude::*;
+use gtk::{gdk, glib};
+
+/// How much slower values change while fine tuning.
+const FINE_FACTOR: f64 = 0.1;
+
+/// Angles snap to this many degrees.
+pub const ANGLE_SNAP: f64 = 15.0;
+/// Stop positions snap to tenths of the gradient.
+pub const POSITION_SNAP: f32 = 0.1;
+
+/// How precisely a drag or nudge changes a value: holding Shift fine tunes
+/// it and holding Ctrl snaps it to round values.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Precision {
+    Normal,
+    Fine,
+    Snap,
+}
+
+impl Precision {
+    pub fn from_state(state: gdk::ModifierType) -> Self {
+        let modifiers = state & gtk::accelerator_get_default_mod_mask();
+        if modifiers.contains(gdk::ModifierType::CONTROL_MASK) {
+            Self::Snap
+        } else if modifiers.contains(gdk::ModifierType::SHIFT_MASK) {
+            Self::Fine
+        } else {
+            Self::Normal
+        }
+    }
+
+    /// Scales a change of a value.
+    pub fn scale(self, delta: f64) -> f64 {
+        if self == Self::Fine {
+            delta * FINE_FACTOR
+        } else {
+            delta
+        }
+    }
+
+    /// The value to show, rounded to a multiple of `step` when snapping.
+    pub fn apply(self, value: f64, step: f64) -> f64 {
+        if self == Self::Snap {
+            (value / step).round() * step
+        } else {
+            value
+        }
+    }
+}
+
+/// A change asked for with the keys ranges use: arrows for a step, Page Up
+/// and Page Down for ten, Home and End for the ends.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Nudge {
+    /// By this many steps, negative going back.
+    Steps(f64),
+    Start,
+    End,
+}
+
+impl Nudge {
+    /// Steps taken by Page Up and Page Down.
+    pub const PAGE: f64 = 10.0;
+
+    pub fn from_key(key: gdk::Key) -> Option<Self> {
+        match key {
+            gdk::Key::Right | gdk::Key::Up | gdk::Key::KP_Right | gdk::Key::KP_Up => {
+                Some(Self::Steps(1.0))
+            }
+            gdk::Key::Left | gdk::Key::Down | gdk::Key::KP_Left | gdk::Key::KP_Down => {
+                Some(Self::Steps(-1.0))
+            }
+            gdk::Key::Page_Up | gdk::Key::KP_Page_Up => Some(Self::Steps(Self::PAGE)),
+            gdk::Key::Page_Down | gdk::Key::KP_Page_Down => Some(Self::Steps(-Self::PAGE)),
+            gdk::Key::Home | gdk::Key::KP_Home => Some(Self::Start),
+            gdk::Key::End | gdk::Key::KP_End => Some(Self::End),
+            _ => None,
+        }
+    }
+
+    /// Applies the nudge to `value`, moving by `step` at a time within
+    /// `lower` and `upper`.
+    pub fn apply(self, value: f64, step: f64, lower: f64, upper: f64) -> f64 {
+        match self {
+            Self::Steps(steps) => (value + steps * step).clamp(lower, upper),
+            Self::Start => lower,
+            Self::End => upper,
+        }
+    }
+}
+
+/// Makes Shift slow down dragging a range and Ctrl snap it to its page
+/// increment, like the handles on the preview.
+pub fn setup_range(range: &impl IsA<gtk::Range>) {
+    let range = range.as_ref();
+
+    let state = Rc::new(Cell::new(gdk::ModifierType::empty()));
+    let dragging = Rc::new(Cell::new(false));
+    //the pointer value last seen and the value it set, while dragging
+    let last = Rc::new(Cell::new(None::<(f64, f64)>));
+
+    let controller = gtk::EventControllerLegacy::new();
+    controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+    controller.connect_event(
+        glib::clone!(@strong state, @strong dragging, @strong last => move |_, event| {
+            state.set(event.modifier_state());
+            match event.event_type() {
+                gdk::EventType::ButtonPress | gdk::EventType::TouchBegin => {
+                    dragging.set(true);
+                    last.set(None);
+                }
+                gdk::EventType::ButtonRelease
+                | gdk::EventType::TouchEnd
+                | gdk::EventType::TouchCancel => dragging.set(false),
+                _ => {}
+            }
+            glib::Propagation::Proceed
+        }),
+    );
+    range.add_controller(controller);
+
+    range.connect_change_value(move |range, scroll, value| {
+        //keys and scrolling keep their own steps
+        if scroll != gtk::ScrollType::Jump || !dragging.get() {
+            return glib::Propagation::Proceed;
+        }
+
+        let precision = Precision::from_state(state.get());
+        let raw = match last.get() {
+            Some((pointer, raw)) if precision == Precision::Fine => {
+                raw + precision.scale(value - pointer)
+            }
+            //start fine tuning from the current value, not the pointer
+            None if precision == Precision::Fine => range.value(),
+            _ => value,
+        };
+        last.set(Some((value, raw)));
+
+        let adjustment = range.adjustment();
+        let value = precision.apply(raw, adjustment.page_increment()).clamp(
+            adjustment.lower(),
+            adjustment.upper() - adjustment.page_size(),
+        );
+        range.set_value(value);
+        glib::Propagation::Stop
+    });
+}
@@ -0,0 +1,335 @@
+/* preferences.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::cell::{Cell, OnceCell, RefCell};
+
+use gettextrs::gettext;
+use glib::clone;
+
+use gtk::prelude::*;
+use gtk::{gdk, gio, glib};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use crate::code_style::{self, CodeStyle};
+use crate::color;
+use crate::gradient::{ColorStop, Gradient, GradientType};
+use crate::swatch::VibrantSwatch;
+
+/// The gradient new windows and tabs start with, as set in the preferences.
+pub fn default_gradient(settings: &gio::Settings) -> Gradient {
+    let mut gradient = Gradient::default();
+
+    let stops = settings
+        .strv("default-colors")
+        .iter()
+        .filter_map(|color| gdk::RGBA::parse(color.to_str()).ok())
+        .map(ColorStop::new)
+        .collect::<Vec<_>>();
+    if !stops.is_empty() {
+        gradient.stops = stops;
+    }
+
+    if let Some(kind) = GradientType::from_name(&settings.string("default-type")) {
+        gradient.kind = kind;
+    }
+    gradient.angle = settings.double("default-angle") as f32;
+
+    gradient
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default, gtk::CompositeTemplate)]
+    #[template(resource = "/com/felipekinoshita/Vibrant/preferences.ui")]
+    pub struct VibrantPreferencesWindow {
+        pub settings: OnceCell<gio::Settings>,
+        /// The gradient of the window the preferences were opened from.
+        pub current: RefCell<Gradient>,
+        /// Blocks widget signals while they are updated from the settings.
+        pub syncing: Cell<bool>,
+
+        // Template widgets
+        #[template_child]
+        pub colors_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub type_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub angle_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub angle_keywords_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub corner_keywords_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub legacy_fallbacks_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub css_color_format_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub css_indent_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub css_semicolons_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub css_prefix_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub javascript_color_format_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub javascript_indent_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub javascript_semicolons_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub javascript_prefix_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub community_author_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub community_credit_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub community_feed_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub community_submit_row: TemplateChild<adw::EntryRow>,
+        #[template_child]
+        pub sync_folder_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub sync_clear_button: TemplateChild<gtk::Button>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for VibrantPreferencesWindow {
+        const NAME: &'static str = "VibrantPreferencesWindow";
+        type Type = super::VibrantPreferencesWindow;
+        type ParentType = adw::PreferencesWindow;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.bind_template();
+            Self::Type::bind_template_callbacks(klass);
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    impl ObjectImpl for VibrantPreferencesWindow {}
+    impl WidgetImpl for VibrantPreferencesWindow {}
+    impl WindowImpl for VibrantPreferencesWindow {}
+    impl AdwWindowImpl for VibrantPreferencesWindow {}
+    impl PreferencesWindowImpl for VibrantPreferencesWindow {}
+}
+
+glib::wrapper! {
+    pub struct VibrantPreferencesWindow(ObjectSubclass<imp::VibrantPreferencesWindow>)
+        @extends gtk::Widget, gtk::Window, adw::Window, adw::PreferencesWindow;
+}
+
+#[gtk::template_callbacks]
+impl VibrantPreferencesWindow {
+    pub fn new<P: IsA<gtk::Window>>(parent: &P, current: Gradient) -> Self {
+        let window: Self = glib::Object::builder()
+            .property("transient-for", parent)
+            .build();
+
+        window.imp().current.replace(current);
+        window.setup_signals();
+        window.sync_widgets();
+
+        window
+    }
+
+    fn settings(&self) -> &gio::Settings {
+        self.imp()
+            .settings
+            .get_or_init(|| gio::Settings::new("com.felipekinoshita.Vibrant"))
+    }
+
+    fn setup_signals(&self) {
+        let imp = self.imp();
+
+        imp.type_row
+            .connect_selected_notify(clone!(@weak self as this => move |row| {
+                if this.imp().syncing.get() {
+                    return;
+                }
+
+                let kind = GradientType::from(row.selected());
+                let _ = this.settings().set_string("default-type", kind.name());
+            }));
+        imp.angle_row
+            .connect_value_notify(clone!(@weak self as this => move |row| {
+                if this.imp().syncing.get() {
+                    return;
+                }
+
+                let _ = this.settings().set_double("default-angle", row.value());
+            }));
+
+        self.settings()
+            .bind("css-angle-keywords", &*imp.angle_keywords_row, "active")
+            .build();
+        self.settings()
+            .bind("css-corner-keywords", &*imp.corner_keywords_row, "active")
+            .build();
+        self.settings()
+            .bind("css-legacy-fallbacks", &*imp.legacy_fallbacks_row, "active")
+            .build();
+        self.settings()
+            .bind("community-author", &*imp.community_author_row, "text")
+            .build();
+        self.settings()
+            .bind("community-credit", &*imp.community_credit_row, "active")
+            .build();
+        self.settings()
+            .bind("community-feed-url", &*imp.community_feed_row, "text")
+            .build();
+        self.settings()
+            .bind("community-submit-url", &*imp.community_submit_row, "text")
+            .build();
+
+        self.settings().connect_changed(
+            None,
+            clone!(@weak self as this => move |_, _| {
+                this.sync_widgets();
+            }),
+        );
+
+        self.bind_code_style(
+            "css",
+            &imp.css_color_format_row,
+            &imp.css_indent_row,
+            &imp.css_semicolons_row,
+            &imp.css_prefix_row,
+        );
+        self.bind_code_style(
+            "javascript",
+            &imp.javascript_color_format_row,
+            &imp.javascript_indent_row,
+            &imp.javascript_semicolons_row,
+            &imp.javascript_prefix_row,
+        );
+    }
+
+    /// Shows and edits the code style stored for `target`.
+    fn bind_code_style(
+        &self,
+        target: &str,
+        color_format_row: &adw::ComboRow,
+        indent_row: &adw::SpinRow,
+        semicolons_row: &adw::SwitchRow,
+        prefix_row: &adw::EntryRow,
+    ) {
+        let settings = code_style::settings(target);
+        let style = CodeStyle::load(target);
+
+        let selected = code_style::COLOR_FORMATS
+            .iter()
+            .position(|format| *format == style.color_format)
+            .unwrap_or(0);
+        color_format_row.set_selected(selected as u32);
+        color_format_row.connect_selected_notify(clone!(@strong settings => move |row| {
+            if let Some(format) = code_style::COLOR_FORMATS.get(row.selected() as usize) {
+                let _ = settings.set_string("color-format", format.name());
+            }
+        }));
+
+        indent_row.set_value(style.indent as f64);
+        indent_row.connect_value_notify(clone!(@strong settings => move |row| {
+            let _ = settings.set_uint("indent", row.value() as u32);
+        }));
+
+        settings
+            .bind("semicolons", semicolons_row, "active")
+            .build();
+        settings.bind("variable-prefix", prefix_row, "text").build();
+    }
+
+    /// Shows the defaults stored in the settings.
+    fn sync_widgets(&self) {
+        let imp = self.imp();
+        let gradient = default_gradient(self.settings());
+
+        imp.syncing.set(true);
+        imp.type_row.set_selected(gradient.kind.into());
+        imp.angle_row.set_value(gradient.angle as f64);
+        imp.syncing.set(false);
+
+        while let Some(child) = imp.colors_box.first_child() {
+            imp.colors_box.remove(&child);
+        }
+        for stop in &gradient.stops {
+            let swatch = VibrantSwatch::new(&stop.color);
+            swatch.set_tooltip_text(Some(&color::to_hex(&stop.color)));
+            imp.colors_box.append(&swatch);
+        }
+
+        let folder = self.settings().string("sync-folder");
+        let subtitle = if folder.is_empty() {
+            gettext("Not syncing")
+        } else {
+            gio::File::for_uri(&folder).parse_name().into()
+        };
+        imp.sync_folder_row.set_subtitle(&subtitle);
+        imp.sync_clear_button.set_visible(!folder.is_empty());
+    }
+
+    #[template_callback]
+    fn use_current_gradient(&self, _row: adw::ActionRow) {
+        let current = self.imp().current.borrow().clone();
+        let colors = current
+            .stops
+            .iter()
+            .map(|stop| color::to_hex(&stop.color))
+            .collect::<Vec<_>>();
+
+        let settings = self.settings();
+        let _ = settings.set_strv("default-colors", colors);
+        let _ = settings.set_string("default-type", current.kind.name());
+        let angle = current.angle.round().rem_euclid(360.0);
+        let _ = settings.set_double("default-angle", angle as f64);
+    }
+
+    #[template_callback]
+    fn reset_defaults(&self, _button: gtk::Button) {
+        let settings = self.settings();
+        for key in ["default-colors", "default-type", "default-angle"] {
+            settings.reset(key);
+        }
+    }
+
+    #[template_callback]
+    fn choose_sync_folder(&self, _button: gtk::Button) {
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Choose a Sync Folder"))
+            .modal(true)
+            .build();
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            if let Ok(folder) = dialog.select_folder_future(Some(&this)).await {
+                let settings = this.settings();
+                let _ = settings.set_int64("sync-time", 0);
+                let _ = settings.set_string("sync-folder", &folder.uri());
+            }
+        }));
+    }
+
+    #[template_callback]
+    fn clear_sync_folder(&self, _button: gtk::Button) {
+        self.settings().reset("sync-folder");
+    }
+}
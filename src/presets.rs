@@ -0,0 +1,347 @@
+/* presets.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gettextrs::gettext;
+use gtk::prelude::*;
+use gtk::{gdk, glib};
+
+use crate::color::{self, Oklch};
+use crate::gradient::{ColorStop, Gradient};
+
+/// Points checked along a gradient generated for text to be readable on.
+const READABLE_SAMPLES: usize = 64;
+
+/// How much the contrast asked of the endpoints grows when the blend
+/// between them falls short.
+const READABLE_STEP: f32 = 0.25;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimeOfDay {
+    Sunrise,
+    Noon,
+    Sunset,
+    Night,
+}
+
+impl TimeOfDay {
+    /// Parses the name used by the `time-of-day` action.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sunrise" => Some(Self::Sunrise),
+            "noon" => Some(Self::Noon),
+            "sunset" => Some(Self::Sunset),
+            "night" => Some(Self::Night),
+            _ => None,
+        }
+    }
+
+    pub fn from_hour(hour: i32) -> Self {
+        match hour {
+            5..=8 => Self::Sunrise,
+            9..=16 => Self::Noon,
+            17..=20 => Self::Sunset,
+            _ => Self::Night,
+        }
+    }
+
+    /// Every time of day, in order from the slideshow's start in the morning.
+    pub const ALL: [Self; 4] = [Self::Sunrise, Self::Noon, Self::Sunset, Self::Night];
+
+    /// How many hours of the day this sky lasts, starting at the hours
+    /// `from_hour` gives.
+    pub fn hours(self) -> u32 {
+        match self {
+            Self::Sunrise | Self::Sunset => 4,
+            Self::Noon | Self::Night => 8,
+        }
+    }
+
+    pub fn now() -> Self {
+        glib::DateTime::now_local()
+            .map(|now| Self::from_hour(now.hour()))
+            .unwrap_or(Self::Noon)
+    }
+
+    /// Sky colors as OKLCH lightness, chroma and hue, from the horizon up.
+    fn sky(self) -> &'static [(f32, f32, f32)] {
+        match self {
+            Self::Sunrise => &[(0.86, 0.11, 70.0), (0.74, 0.12, 10.0), (0.52, 0.11, 290.0)],
+            Self::Noon => &[(0.93, 0.03, 210.0), (0.78, 0.09, 230.0), (0.6, 0.14, 250.0)],
+            Self::Sunset => &[
+                (0.72, 0.17, 50.0),
+                (0.6, 0.19, 15.0),
+                (0.42, 0.14, 320.0),
+                (0.3, 0.1, 280.0),
+            ],
+            Self::Night => &[
+                (0.32, 0.07, 280.0),
+                (0.22, 0.06, 265.0),
+                (0.14, 0.03, 260.0),
+            ],
+        }
+    }
+
+    /// A new sky for this time of day. Hue and lightness vary slightly, so
+    /// generating again gives a different take on it.
+    pub fn colors(self) -> Vec<gdk::RGBA> {
+        let hue_shift = glib::random_double_range(-12.0, 12.0) as f32;
+        let light_shift = glib::random_double_range(-0.04, 0.04) as f32;
+
+        self.sky()
+            .iter()
+            .map(|&(l, c, h)| {
+                Oklch {
+                    l: (l + light_shift).clamp(0.0, 1.0),
+                    c,
+                    h: (h + hue_shift).rem_euclid(360.0),
+                    alpha: 1.0,
+                }
+                .to_rgba()
+            })
+            .collect()
+    }
+}
+
+/// The tones of an image from left to right, averaging `count` vertical
+/// bands of it.
+pub fn from_texture(texture: &gdk::Texture, count: usize) -> Vec<gdk::RGBA> {
+    bands(texture, count, false)
+}
+
+/// Approximates an image with a linear gradient, averaging `count` bands
+/// across the direction its colors change the most. Returns the CSS angle
+/// of the gradient along with its colors.
+pub fn from_region(texture: &gdk::Texture, count: usize) -> (f32, Vec<gdk::RGBA>) {
+    let spread = |colors: &[gdk::RGBA]| -> f32 {
+        colors
+            .windows(2)
+            .map(|pair| color::delta_e(&pair[0], &pair[1]))
+            .sum()
+    };
+
+    let columns = bands(texture, count, false);
+    let rows = bands(texture, count, true);
+    if spread(&rows) > spread(&columns) {
+        (180.0, rows)
+    } else {
+        (90.0, columns)
+    }
+}
+
+/// Averages `count` bands of an image, vertical ones from left to right or
+/// horizontal ones from top to bottom.
+fn bands(texture: &gdk::Texture, count: usize, horizontal: bool) -> Vec<gdk::RGBA> {
+    let mut downloader = gdk::TextureDownloader::new(texture);
+    downloader.set_format(gdk::MemoryFormat::R8g8b8a8);
+    let (data, stride) = downloader.download_bytes();
+
+    let (width, height) = (texture.width() as usize, texture.height() as usize);
+    let length = if horizontal { height } else { width };
+    //a few thousand pixels per band are plenty for an average
+    let step = ((width * height) / (count * 4096)).max(1);
+
+    (0..count)
+        .map(|band| {
+            let start = (band * length / count).min(length.saturating_sub(1));
+            let band = start..((band + 1) * length / count).max(start + 1);
+            let (columns, rows) = if horizontal {
+                (0..width, band)
+            } else {
+                (band, 0..height)
+            };
+            let mut sum = [0.0; 3];
+            let mut samples = 0.0;
+
+            for y in rows.step_by(step) {
+                for x in columns.clone().step_by(step) {
+                    let pixel = &data[y * stride + x * 4..][..4];
+                    for (channel, value) in sum.iter_mut().zip(pixel) {
+                        *channel += *value as f32 / 255.0;
+                    }
+                    samples += 1.0;
+                }
+            }
+
+            let samples = f32::max(samples, 1.0);
+            gdk::RGBA::new(sum[0] / samples, sum[1] / samples, sum[2] / samples, 1.0)
+        })
+        .collect()
+}
+
+/// Analogous colors around an accent, lighter on one side and deeper on the
+/// other, with the accent itself in the middle.
+pub fn around_accent(accent: &gdk::RGBA) -> Vec<gdk::RGBA> {
+    let accent = Oklch::from(*accent);
+
+    [(0.12, 0.8, -30.0), (0.0, 1.0, 0.0), (-0.15, 0.9, 30.0)]
+        .iter()
+        .map(|&(light_shift, chroma_scale, hue_shift)| {
+            Oklch {
+                l: (accent.l + light_shift).clamp(0.0, 1.0),
+                c: accent.c * chroma_scale,
+                h: (accent.h + hue_shift).rem_euclid(360.0),
+                alpha: 1.0,
+            }
+            .to_rgba()
+        })
+        .collect()
+}
+
+/// Endpoints for `gradient` with every point between them, adjustments
+/// included, reaching `ratio` of contrast with `foreground`. They keep the
+/// hues of its first and last colors. Blending can dip below the contrast of
+/// the endpoints, so it is checked along the way and they are pushed further
+/// apart until it holds. Returns nothing when no colors can reach it.
+pub fn readable_endpoints(
+    gradient: &Gradient,
+    foreground: &gdk::RGBA,
+    ratio: f32,
+) -> Option<Vec<gdk::RGBA>> {
+    let seeds = [gradient.stops.first()?.color, gradient.stops.last()?.color];
+
+    let mut target = ratio;
+    while target <= 21.0 {
+        let colors: Vec<gdk::RGBA> = seeds
+            .iter()
+            .map(|seed| color::with_contrast(&seed.with_alpha(1.0), foreground, target))
+            .collect();
+
+        let mut candidate = gradient.clone();
+        candidate.stops = colors.iter().copied().map(ColorStop::new).collect();
+        let lowest = candidate
+            .adjusted()
+            .sample(READABLE_SAMPLES)
+            .iter()
+            .map(|color| color::contrast_ratio(color, foreground))
+            .fold(f32::INFINITY, f32::min);
+        if lowest >= ratio {
+            return Some(colors);
+        }
+
+        target += READABLE_STEP;
+    }
+
+    None
+}
+
+/// Ways to derive a new gradient from the current one, changing every
+/// stop the same way in OKLCH.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Variation {
+    Complementary,
+    Triadic,
+    Analogous,
+    InvertedLightness,
+    Desaturated,
+    Vivid,
+}
+
+impl Variation {
+    pub const ALL: [Self; 6] = [
+        Self::Complementary,
+        Self::Triadic,
+        Self::Analogous,
+        Self::InvertedLightness,
+        Self::Desaturated,
+        Self::Vivid,
+    ];
+
+    pub fn name(self) -> String {
+        match self {
+            Self::Complementary => gettext("Complementary"),
+            Self::Triadic => gettext("Triadic"),
+            Self::Analogous => gettext("Analogous"),
+            Self::InvertedLightness => gettext("Inverted Lightness"),
+            Self::Desaturated => gettext("Desaturated"),
+            Self::Vivid => gettext("Vivid"),
+        }
+    }
+
+    pub fn apply(self, color: &gdk::RGBA) -> gdk::RGBA {
+        let mut color = Oklch::from(*color);
+        match self {
+            Self::Complementary => color.h += 180.0,
+            Self::Triadic => color.h += 120.0,
+            Self::Analogous => color.h += 30.0,
+            Self::InvertedLightness => color.l = 1.0 - color.l,
+            Self::Desaturated => color.c *= 0.35,
+            Self::Vivid => color.c *= 1.5,
+        }
+        color.h = color.h.rem_euclid(360.0);
+
+        color.to_rgba()
+    }
+}
+
+/// How quickly a fade to transparent gives way.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Falloff {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Falloff {
+    pub const ALL: [Self; 4] = [Self::Linear, Self::EaseIn, Self::EaseOut, Self::EaseInOut];
+
+    pub fn name(self) -> String {
+        match self {
+            Self::Linear => gettext("Linear"),
+            Self::EaseIn => gettext("Ease In"),
+            Self::EaseOut => gettext("Ease Out"),
+            Self::EaseInOut => gettext("Ease In and Out"),
+        }
+    }
+
+    /// How far the fade is at `t`, both from 0 to 1.
+    fn ease(self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// Stops sampling a curved fade, enough for it to look smooth.
+const FADE_STOPS: usize = 8;
+
+/// Stops fading `color` to transparent along `falloff`. The color itself
+/// stays, only its alpha drops, so nothing fades through gray the way
+/// blending with CSS `transparent` can.
+pub fn fade(color: &gdk::RGBA, falloff: Falloff) -> Vec<ColorStop> {
+    let count = if falloff == Falloff::Linear {
+        2
+    } else {
+        FADE_STOPS
+    };
+
+    (0..count)
+        .map(|i| {
+            let t = i as f32 / (count - 1) as f32;
+            ColorStop {
+                position: Some(t),
+                ..ColorStop::new(color.with_alpha(color.alpha() * (1.0 - falloff.ease(t))))
+            }
+        })
+        .collect()
+}
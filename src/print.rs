@@ -0,0 +1,83 @@
+/* print.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::gdk;
+
+use crate::color::{self, Oklch};
+
+/// How coated paper printed on an offset press (FOGRA39) looks on screen:
+/// the process inks and their overprints, in order of hue.
+const INKS: [&str; 6] = [
+    //cyan, blue, magenta, red, yellow and green
+    "#009fe3", "#312783", "#e6007e", "#e30613", "#ffed00", "#00963f",
+];
+
+/// OKLab lightness of the paper and of the darkest black the inks reach.
+const PAPER_LIGHTNESS: f32 = 0.98;
+const BLACK_LIGHTNESS: f32 = 0.22;
+
+/// Perceptual difference from which a color visibly changes on paper.
+const VISIBLE_CHANGE: f32 = 0.02;
+
+/// Lightness and chroma of the most colorful printable color of a hue,
+/// interpolated between the two inks around it.
+fn cusp(hue: f32) -> (f32, f32) {
+    let mut inks: Vec<Oklch> = INKS
+        .iter()
+        .filter_map(|hex| gdk::RGBA::parse(*hex).ok())
+        .map(Oklch::from)
+        .collect();
+    inks.sort_by(|a, b| a.h.total_cmp(&b.h));
+
+    for (i, from) in inks.iter().enumerate() {
+        let to = inks[(i + 1) % inks.len()];
+        let span = (to.h - from.h).rem_euclid(360.0);
+        let offset = (hue - from.h).rem_euclid(360.0);
+        if offset <= span {
+            let t = offset / span.max(f32::EPSILON);
+            return (from.l + (to.l - from.l) * t, from.c + (to.c - from.c) * t);
+        }
+    }
+
+    (0.5, 0.0)
+}
+
+/// Brings a color inside the gamut of the press, keeping its hue and as
+/// much of its lightness as the paper and the inks allow. An approximation
+/// from the colors of the inks, not a conversion through an ICC profile.
+pub fn simulate(rgba: &gdk::RGBA) -> gdk::RGBA {
+    let mut lch = Oklch::from(*rgba);
+    lch.l = lch.l.clamp(BLACK_LIGHTNESS, PAPER_LIGHTNESS);
+
+    let (cusp_lightness, cusp_chroma) = cusp(lch.h);
+    let max_chroma = if lch.l >= cusp_lightness {
+        cusp_chroma * (PAPER_LIGHTNESS - lch.l) / (PAPER_LIGHTNESS - cusp_lightness)
+    } else {
+        cusp_chroma * (lch.l - BLACK_LIGHTNESS) / (cusp_lightness - BLACK_LIGHTNESS)
+    };
+    lch.c = lch.c.min(max_chroma.max(0.0));
+
+    lch.to_rgba()
+}
+
+/// Whether a color looks about the same printed.
+pub fn is_printable(rgba: &gdk::RGBA) -> bool {
+    color::delta_e(rgba, &simulate(rgba)) < VISIBLE_CHANGE
+}
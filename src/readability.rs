@@ -0,0 +1,147 @@
+/* readability.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::prelude::*;
+use gtk::{gdk, graphene, gsk};
+
+use crate::color;
+use crate::gradient::Gradient;
+
+/// Height of the status bar band, as a fraction of the image height.
+const BAND: f32 = 0.06;
+
+/// WCAG AA contrast for normal text.
+const MIN_CONTRAST: f32 = 4.5;
+
+/// Columns and rows of the points sampled in the band.
+const SAMPLES: (usize, usize) = (12, 3);
+
+fn samples(gradient: &Gradient, bounds: &graphene::Rect) -> Vec<gdk::RGBA> {
+    let (columns, rows) = SAMPLES;
+    let band = bounds.height() * BAND;
+
+    (0..columns)
+        .flat_map(|column| (0..rows).map(move |row| (column, row)))
+        .map(|(column, row)| {
+            let point = graphene::Point::new(
+                bounds.x() + bounds.width() * (column as f32 + 0.5) / columns as f32,
+                bounds.y() + band * (row as f32 + 0.5) / rows as f32,
+            );
+            gradient.color_at_point(&point, bounds)
+        })
+        .collect()
+}
+
+fn worst_contrast(colors: &[gdk::RGBA], text: &gdk::RGBA) -> f32 {
+    colors
+        .iter()
+        .map(|color| color::contrast_ratio(color, text))
+        .fold(f32::MAX, f32::min)
+}
+
+/// The status bar text color that reads best over the top of the gradient,
+/// with the lowest contrast it gets there.
+fn best_text(colors: &[gdk::RGBA]) -> (gdk::RGBA, f32) {
+    let white = worst_contrast(colors, &gdk::RGBA::WHITE);
+    let black = worst_contrast(colors, &gdk::RGBA::BLACK);
+
+    if white >= black {
+        (gdk::RGBA::WHITE, white)
+    } else {
+        (gdk::RGBA::BLACK, black)
+    }
+}
+
+/// Whether status bar text and icons stay legible over the top of the
+/// gradient without any help.
+pub fn is_readable(gradient: &Gradient, bounds: &graphene::Rect) -> bool {
+    best_text(&samples(gradient, bounds)).1 >= MIN_CONTRAST
+}
+
+fn composite(color: &gdk::RGBA, tint: &gdk::RGBA, alpha: f32) -> gdk::RGBA {
+    let mix = |from: f32, to: f32| from + (to - from) * alpha;
+    gdk::RGBA::new(
+        mix(color.red(), tint.red()),
+        mix(color.green(), tint.green()),
+        mix(color.blue(), tint.blue()),
+        1.0,
+    )
+}
+
+/// The translucent color that darkens the band behind light text, or
+/// lightens it behind dark text, just enough to reach readable contrast.
+pub fn band_overlay(gradient: &Gradient, bounds: &graphene::Rect) -> Option<gdk::RGBA> {
+    let colors = samples(gradient, bounds);
+    let (text, contrast) = best_text(&colors);
+    if contrast >= MIN_CONTRAST {
+        return None;
+    }
+
+    let tint = if text == gdk::RGBA::WHITE {
+        gdk::RGBA::BLACK
+    } else {
+        gdk::RGBA::WHITE
+    };
+
+    let alpha = (1..=18)
+        .map(|step| step as f32 * 0.05)
+        .find(|alpha| {
+            let tinted: Vec<_> = colors
+                .iter()
+                .map(|color| composite(color, &tint, *alpha))
+                .collect();
+            worst_contrast(&tinted, &text) >= MIN_CONTRAST
+        })
+        .unwrap_or(0.9);
+
+    Some(gdk::RGBA::new(tint.red(), tint.green(), tint.blue(), alpha))
+}
+
+/// The band as a CSS layer, solid behind the status bar and fading out
+/// below it.
+pub fn css_layer(overlay: &gdk::RGBA) -> String {
+    format!(
+        "linear-gradient({} {}%, transparent {}%)",
+        color::to_hex(overlay),
+        BAND * 100.0,
+        BAND * 200.0
+    )
+}
+
+/// The band as an SVG gradient with the id `band`, meant to fill the image.
+pub fn svg_gradient(overlay: &gdk::RGBA) -> String {
+    format!(
+        "<linearGradient id=\"band\" x1=\"0\" y1=\"0\" x2=\"0\" y2=\"1\"><stop offset=\"{}\" stop-color=\"{hex}\"/><stop offset=\"{}\" stop-color=\"{hex}\" stop-opacity=\"0\"/></linearGradient>",
+        BAND,
+        BAND * 2.0,
+        hex = color::to_hex(overlay),
+    )
+}
+
+pub fn node(bounds: &graphene::Rect, overlay: &gdk::RGBA) -> gsk::RenderNode {
+    let start = graphene::Point::new(bounds.x(), bounds.y());
+    let end = graphene::Point::new(bounds.x(), bounds.y() + bounds.height());
+    let stops = [
+        gsk::ColorStop::new(BAND, *overlay),
+        gsk::ColorStop::new(BAND * 2.0, gdk::RGBA::TRANSPARENT),
+    ];
+
+    gsk::LinearGradientNode::new(bounds, &start, &end, &stops).upcast()
+}
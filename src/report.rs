@@ -0,0 +1,165 @@
+/* report.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::fmt::Write;
+
+use gettextrs::gettext;
+use gtk::gdk;
+
+use crate::color::{self, ColorBlindness};
+use crate::gradient::Gradient;
+
+/// Points along the gradient where text contrast is checked.
+const CHECKPOINTS: [f32; 5] = [0.0, 0.25, 0.5, 0.75, 1.0];
+
+/// Distance in OKLab under which neighboring colors are hard to tell apart.
+const CONFUSABLE_DELTA: f32 = 0.05;
+
+/// The WCAG level text reaches at a contrast ratio.
+fn level(ratio: f32) -> String {
+    if ratio >= 7.0 {
+        "AAA".to_owned()
+    } else if ratio >= 4.5 {
+        "AA".to_owned()
+    } else if ratio >= 3.0 {
+        gettext("AA Large")
+    } else {
+        gettext("Fail")
+    }
+}
+
+fn contrast(rgba: &gdk::RGBA, text: &gdk::RGBA) -> String {
+    let ratio = color::contrast_ratio(rgba, text);
+    format!("{ratio:.2}:1 ({})", level(ratio))
+}
+
+fn percent(position: f32) -> String {
+    format!("{}%", (position * 100.0).round())
+}
+
+fn deficiency_name(deficiency: ColorBlindness) -> String {
+    match deficiency {
+        ColorBlindness::Protanopia => gettext("Protanopia"),
+        ColorBlindness::Deuteranopia => gettext("Deuteranopia"),
+        ColorBlindness::Tritanopia => gettext("Tritanopia"),
+    }
+}
+
+/// A Markdown report of the gradient for design reviews: its stops, how
+/// readable white and black text are along it, and how it looks with color
+/// vision deficiencies.
+pub fn markdown(gradient: &Gradient) -> String {
+    let white = gdk::RGBA::WHITE;
+    let black = gdk::RGBA::BLACK;
+    let colors: Vec<gdk::RGBA> = gradient.stops.iter().map(|stop| stop.color).collect();
+
+    let mut report = format!(
+        "# {}\n\n```css\nbackground: {};\n```\n\n",
+        gettext("Gradient Accessibility Report"),
+        gradient.to_css()
+    );
+
+    let _ = writeln!(
+        report,
+        "## {}\n\n| | {} | {} | {} | {} |\n|---|---|---|---|---|",
+        gettext("Stops"),
+        gettext("Color"),
+        gettext("Position"),
+        gettext("White Text"),
+        gettext("Black Text")
+    );
+    for (i, (rgba, position)) in colors.iter().zip(gradient.positions()).enumerate() {
+        let _ = writeln!(
+            report,
+            "| {} | `{}` | {} | {} | {} |",
+            i + 1,
+            color::to_hex(rgba),
+            percent(position),
+            contrast(rgba, &white),
+            contrast(rgba, &black)
+        );
+    }
+
+    let _ = writeln!(
+        report,
+        "\n## {}\n\n| {} | {} | {} | {} |\n|---|---|---|---|",
+        gettext("Text Contrast Along the Gradient"),
+        gettext("Position"),
+        gettext("Color"),
+        gettext("White Text"),
+        gettext("Black Text")
+    );
+    let samples = gradient.sample(CHECKPOINTS.len());
+    for (position, rgba) in CHECKPOINTS.iter().zip(&samples) {
+        let _ = writeln!(
+            report,
+            "| {} | `{}` | {} | {} |",
+            percent(*position),
+            color::to_hex(rgba),
+            contrast(rgba, &white),
+            contrast(rgba, &black)
+        );
+    }
+
+    let _ = writeln!(
+        report,
+        "\n## {}\n\n| {} | {} |\n|---|---|",
+        gettext("Color Vision Deficiencies"),
+        gettext("Simulation"),
+        gettext("Stops")
+    );
+    let mut confusable = Vec::new();
+    for deficiency in ColorBlindness::ALL {
+        let simulated: Vec<gdk::RGBA> = colors
+            .iter()
+            .map(|rgba| deficiency.simulate(rgba))
+            .collect();
+        let hexes: Vec<String> = simulated
+            .iter()
+            .map(|rgba| format!("`{}`", color::to_hex(rgba)))
+            .collect();
+        let _ = writeln!(
+            report,
+            "| {} | {} |",
+            deficiency_name(deficiency),
+            hexes.join(" → ")
+        );
+
+        for (i, pair) in simulated.windows(2).enumerate() {
+            if color::delta_e(&pair[0], &pair[1]) < CONFUSABLE_DELTA {
+                confusable.push(
+                    gettext("With {deficiency}, stops {first} and {second} are hard to tell apart")
+                        .replace("{deficiency}", &deficiency_name(deficiency))
+                        .replace("{first}", &(i + 1).to_string())
+                        .replace("{second}", &(i + 2).to_string()),
+                );
+            }
+        }
+    }
+
+    if !confusable.is_empty() {
+        report.push('\n');
+        for warning in confusable {
+            let _ = writeln!(report, "- {warning}");
+        }
+    }
+
+    report
+}
@@ -0,0 +1,136 @@
+/* screenshot.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use gtk::prelude::*;
+use gtk::{gdk, gio, glib};
+
+fn failed(message: &str) -> glib::Error {
+    glib::Error::new(gio::IOErrorEnum::Failed, message)
+}
+
+/// Calls a method of the screenshot portal and waits for the response of
+/// the request it starts, returning its results.
+async fn request(
+    method: &str,
+    mut options: HashMap<&str, glib::Variant>,
+) -> Result<HashMap<String, glib::Variant>, glib::Error> {
+    let connection = gio::bus_get_future(gio::BusType::Session).await?;
+
+    let token = format!("vibrant{}", glib::random_int());
+    let sender = connection
+        .unique_name()
+        .map(|name| name.trim_start_matches(':').replace('.', "_"))
+        .unwrap_or_default();
+    let handle = format!("/org/freedesktop/portal/desktop/request/{sender}/{token}");
+    options.insert("handle_token", token.to_variant());
+
+    let call = connection.call_future(
+        Some("org.freedesktop.portal.Desktop"),
+        "/org/freedesktop/portal/desktop",
+        "org.freedesktop.portal.Screenshot",
+        method,
+        Some(&("", options).to_variant()),
+        Some(glib::VariantTy::new("(o)").unwrap()),
+        gio::DBusCallFlags::NONE,
+        -1,
+    );
+
+    let subscription = Rc::new(RefCell::new(None));
+    let response = gio::GioFuture::new(&connection, {
+        let subscription = subscription.clone();
+        move |connection, _, send| {
+            let send = Rc::new(RefCell::new(Some(send)));
+
+            //subscribing first, so a quick response can't be missed
+            let id = connection.signal_subscribe(
+                Some("org.freedesktop.portal.Desktop"),
+                Some("org.freedesktop.portal.Request"),
+                Some("Response"),
+                Some(&handle),
+                None,
+                gio::DBusSignalFlags::NONE,
+                {
+                    let send = send.clone();
+                    move |_, _, _, _, _, parameters| {
+                        if let Some(send) = send.borrow_mut().take() {
+                            send.resolve(Ok(parameters.clone()));
+                        }
+                    }
+                },
+            );
+            subscription.replace(Some(id));
+
+            glib::MainContext::default().spawn_local(async move {
+                if let Err(err) = call.await {
+                    if let Some(send) = send.borrow_mut().take() {
+                        send.resolve(Err(err));
+                    }
+                }
+            });
+        }
+    })
+    .await;
+
+    if let Some(id) = subscription.take() {
+        connection.signal_unsubscribe(id);
+    }
+
+    let (code, results) = response?
+        .get::<(u32, HashMap<String, glib::Variant>)>()
+        .ok_or_else(|| failed("Unexpected response from the screenshot portal"))?;
+    match code {
+        0 => Ok(results),
+        1 => Err(glib::Error::new(
+            gio::IOErrorEnum::Cancelled,
+            "The request was cancelled",
+        )),
+        _ => Err(failed(
+            "The screenshot portal could not complete the request",
+        )),
+    }
+}
+
+/// Lets the user pick a color anywhere on the screen.
+pub async fn pick_color() -> Result<gdk::RGBA, glib::Error> {
+    let results = request("PickColor", HashMap::new()).await?;
+    let (red, green, blue) = results
+        .get("color")
+        .and_then(|color| color.get::<(f64, f64, f64)>())
+        .ok_or_else(|| failed("No color was picked"))?;
+
+    Ok(gdk::RGBA::new(red as f32, green as f32, blue as f32, 1.0))
+}
+
+/// Takes a screenshot, letting the user choose the area to capture.
+pub async fn capture_region() -> Result<gdk::Texture, glib::Error> {
+    let options = HashMap::from([("interactive", true.to_variant())]);
+    let results = request("Screenshot", options).await?;
+    let uri = results
+        .get("uri")
+        .and_then(|uri| uri.get::<String>())
+        .ok_or_else(|| failed("No screenshot was taken"))?;
+
+    let (bytes, _) = gio::File::for_uri(&uri).load_bytes_future().await?;
+    gdk::Texture::from_bytes(&bytes)
+}
@@ -0,0 +1,894 @@
+/* snippet.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::fmt::Write;
+
+use gtk::gdk;
+
+use crate::code_style::CodeStyle;
+use crate::color::{self, Oklch};
+use crate::gradient::{Gradient, GradientType};
+use crate::json::Value;
+use crate::terminal::Scheme;
+use crate::wallpaper::Shading;
+
+/// Code recreating the gradient in other tools, copied from the export menu.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Snippet {
+    /// A `GradientTexture2D` resource for Godot 4.
+    Godot,
+    /// C# building a `UnityEngine.Gradient`.
+    Unity,
+    /// Python adding a material with a Color Ramp node in Blender.
+    Blender,
+    /// A `gtk.css` giving libadwaita apps an accent from the gradient.
+    Adwaita,
+    /// A JavaScript object with the stops and direction.
+    JavaScript,
+    /// A GLSL fragment shader, as used by a Three.js `ShaderMaterial`.
+    Glsl,
+    /// JavaScript filling a `<canvas>` through its 2D context.
+    Canvas,
+    /// A Jetpack Compose `Brush`.
+    Compose,
+    /// An Expo `LinearGradient` element for React Native.
+    Expo,
+    /// A Figma plugin API `GradientPaint`, as JSON.
+    Figma,
+    /// A Penpot plugin API fill, as JSON.
+    Penpot,
+    /// A matplotlib `LinearSegmentedColormap`.
+    Matplotlib,
+    /// A d3 `scaleLinear` usable as an interpolator, in JavaScript.
+    D3,
+    /// `gsettings` commands for the GNOME background colors.
+    GnomeBackground,
+    /// Terminal color schemes in the mood of the gradient.
+    Alacritty,
+    Kitty,
+    Foot,
+}
+
+/// Rounds to four decimals, dropping trailing zeros.
+fn number(value: f32) -> String {
+    let value = (value * 10_000.0).round() / 10_000.0;
+    //avoid printing -0
+    format!("{}", value + 0.0)
+}
+
+/// The stops as offsets and colors. Hard stops keep the offset where each
+/// color starts, for tools that switch colors in steps.
+fn keys(gradient: &Gradient) -> Vec<(f32, gdk::RGBA)> {
+    let offsets = if gradient.hard_stops {
+        gradient
+            .segments()
+            .iter()
+            .map(|(start, _)| *start)
+            .collect()
+    } else {
+        gradient.positions()
+    };
+
+    offsets
+        .into_iter()
+        .zip(gradient.stops.iter().map(|stop| stop.color))
+        .collect()
+}
+
+impl Snippet {
+    /// Parses the target of the `copy-snippet` action.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "godot" => Some(Self::Godot),
+            "unity" => Some(Self::Unity),
+            "blender" => Some(Self::Blender),
+            "adwaita" => Some(Self::Adwaita),
+            "javascript" => Some(Self::JavaScript),
+            "glsl" => Some(Self::Glsl),
+            "canvas" => Some(Self::Canvas),
+            "compose" => Some(Self::Compose),
+            "expo" => Some(Self::Expo),
+            "figma" => Some(Self::Figma),
+            "penpot" => Some(Self::Penpot),
+            "matplotlib" => Some(Self::Matplotlib),
+            "d3" => Some(Self::D3),
+            "gnome-background" => Some(Self::GnomeBackground),
+            "alacritty" => Some(Self::Alacritty),
+            "kitty" => Some(Self::Kitty),
+            "foot" => Some(Self::Foot),
+            _ => None,
+        }
+    }
+
+    pub fn generate(self, gradient: &Gradient) -> String {
+        match self {
+            Self::Godot => godot(gradient),
+            Self::Unity => unity(gradient),
+            Self::Blender => blender(gradient),
+            Self::Adwaita => adwaita(gradient),
+            Self::JavaScript => javascript(gradient, &CodeStyle::load("javascript")),
+            Self::Glsl => glsl(gradient),
+            Self::Canvas => canvas(gradient, &CodeStyle::load("javascript")),
+            Self::Compose => compose(gradient),
+            Self::Expo => expo(gradient),
+            Self::Figma => figma(gradient),
+            Self::Penpot => penpot(gradient),
+            Self::Matplotlib => matplotlib(gradient),
+            Self::D3 => d3(gradient, &CodeStyle::load("javascript")),
+            Self::GnomeBackground => Shading::new(gradient).to_commands(),
+            Self::Alacritty => Scheme::new(gradient).to_alacritty(),
+            Self::Kitty => Scheme::new(gradient).to_kitty(),
+            Self::Foot => Scheme::new(gradient).to_foot(),
+        }
+    }
+}
+
+/// Godot has no conic fill, so conic gradients keep only their colors in a
+/// linear texture.
+fn godot(gradient: &Gradient) -> String {
+    let keys = keys(gradient);
+    let offsets: Vec<String> = keys.iter().map(|(offset, _)| number(*offset)).collect();
+    let colors: Vec<String> = keys
+        .iter()
+        .map(|(_, color)| {
+            format!(
+                "{}, {}, {}, {}",
+                number(color.red()),
+                number(color.green()),
+                number(color.blue()),
+                number(color.alpha())
+            )
+        })
+        .collect();
+
+    let mut tres =
+        String::from("[gd_resource type=\"GradientTexture2D\" load_steps=2 format=3]\n\n");
+    tres.push_str("[sub_resource type=\"Gradient\" id=\"Gradient_vibrant\"]\n");
+    if gradient.hard_stops {
+        //constant
+        tres.push_str("interpolation_mode = 1\n");
+    }
+    let _ = writeln!(tres, "offsets = PackedFloat32Array({})", offsets.join(", "));
+    let _ = writeln!(tres, "colors = PackedColorArray({})\n", colors.join(", "));
+
+    tres.push_str("[resource]\ngradient = SubResource(\"Gradient_vibrant\")\n");
+    match gradient.kind {
+        GradientType::Radial => {
+            //reaching the corners, like CSS
+            tres.push_str("fill = 1\nfill_from = Vector2(0.5, 0.5)\n");
+            let _ = writeln!(
+                tres,
+                "fill_to = Vector2({}, 0.5)",
+                number(0.5 + std::f32::consts::FRAC_1_SQRT_2)
+            );
+        }
+        GradientType::Linear | GradientType::Conic => {
+            //the CSS gradient line of a square, in texture coordinates
+            let angle = if gradient.kind == GradientType::Linear {
+                gradient.angle
+            } else {
+                180.0
+            };
+            let (sin, cos) = angle.to_radians().sin_cos();
+            let half = (sin.abs() + cos.abs()) / 2.0;
+            let _ = writeln!(
+                tres,
+                "fill_from = Vector2({}, {})\nfill_to = Vector2({}, {})",
+                number(0.5 - sin * half),
+                number(0.5 + cos * half),
+                number(0.5 + sin * half),
+                number(0.5 - cos * half)
+            );
+        }
+    }
+
+    tres
+}
+
+/// Unity gradients only have a direction once applied, so only the keys
+/// carry over. Unity takes at most eight keys of each kind.
+fn unity(gradient: &Gradient) -> String {
+    const MAX_KEYS: usize = 8;
+
+    let keys = if gradient.hard_stops {
+        //fixed gradients show each key's color up to its time
+        gradient
+            .segments()
+            .iter()
+            .map(|(_, end)| *end)
+            .zip(gradient.stops.iter().map(|stop| stop.color))
+            .collect()
+    } else {
+        keys(gradient)
+    };
+
+    let mut code = String::new();
+    if keys.len() > MAX_KEYS {
+        let _ = writeln!(
+            code,
+            "// Unity keeps only the first {MAX_KEYS} of these {} keys",
+            keys.len()
+        );
+    }
+    code.push_str("var gradient = new Gradient();\n");
+    if gradient.hard_stops {
+        code.push_str("gradient.mode = GradientMode.Fixed;\n");
+    }
+
+    code.push_str("gradient.SetKeys(\n    new[] {\n");
+    for (time, color) in &keys {
+        let _ = writeln!(
+            code,
+            "        new GradientColorKey(new Color({}f, {}f, {}f), {}f),",
+            number(color.red()),
+            number(color.green()),
+            number(color.blue()),
+            number(*time)
+        );
+    }
+    code.push_str("    },\n    new[] {\n");
+    for (time, color) in &keys {
+        let _ = writeln!(
+            code,
+            "        new GradientAlphaKey({}f, {}f),",
+            number(color.alpha()),
+            number(*time)
+        );
+    }
+    code.push_str("    });\n");
+
+    code
+}
+
+/// Blender ramps only map values to colors, so the shape of the gradient is
+/// left to whatever drives the node. Their colors are in linear light.
+fn blender(gradient: &Gradient) -> String {
+    let keys: Vec<String> = keys(gradient)
+        .iter()
+        .map(|(position, rgba)| {
+            format!(
+                "    ({}, ({}, {}, {}, {})),",
+                number(*position),
+                number(color::to_linear(rgba.red())),
+                number(color::to_linear(rgba.green())),
+                number(color::to_linear(rgba.blue())),
+                number(rgba.alpha())
+            )
+        })
+        .collect();
+    let interpolation = if gradient.hard_stops {
+        "CONSTANT"
+    } else {
+        "LINEAR"
+    };
+
+    format!(
+        r#"import bpy
+
+keys = [
+{}
+]
+
+material = bpy.data.materials.new("Vibrant Gradient")
+material.use_nodes = True
+ramp = material.node_tree.nodes.new("ShaderNodeValToRGB")
+ramp.color_ramp.interpolation = "{interpolation}"
+
+# new ramps start with two elements
+elements = ramp.color_ramp.elements
+elements[0].position, elements[0].color = keys[0]
+elements[1].position, elements[1].color = keys[-1]
+for position, color in keys[1:-1]:
+    elements.new(position).color = color
+"#,
+        keys.join("\n")
+    )
+}
+
+/// 8 bit RGB, since LEDs have no alpha.
+fn rgb(rgba: &gdk::RGBA) -> [u8; 3] {
+    [rgba.red(), rgba.green(), rgba.blue()]
+        .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+/// Colors for individual LEDs as an array of RGB triplets.
+pub fn led_array(colors: &[gdk::RGBA]) -> String {
+    let colors: Vec<String> = colors
+        .iter()
+        .map(|rgba| {
+            let [r, g, b] = rgb(rgba);
+            format!("[{r}, {g}, {b}]")
+        })
+        .collect();
+
+    format!("[{}]\n", colors.join(", "))
+}
+
+/// A WLED JSON API state lighting each LED of the main segment, which can be
+/// sent to `/json/state` or saved as a preset.
+pub fn wled_state(colors: &[gdk::RGBA]) -> String {
+    let colors: Vec<String> = colors
+        .iter()
+        .map(|rgba| {
+            let [r, g, b] = rgb(rgba);
+            format!("\"{r:02X}{g:02X}{b:02X}\"")
+        })
+        .collect();
+
+    format!(
+        "{{\"on\": true, \"seg\": {{\"i\": [{}]}}}}\n",
+        colors.join(", ")
+    )
+}
+
+/// Contrast of the accent background with its white text, as Adwaita's own
+/// accents have.
+const ACCENT_BG_CONTRAST: f32 = 3.0;
+/// Contrast of accent colored text with the light window background.
+const ACCENT_CONTRAST: f32 = 4.5;
+
+/// Accent colors from the most saturated stop, darkened until they stay
+/// readable the way libadwaita uses them.
+fn adwaita(gradient: &Gradient) -> String {
+    let key = gradient
+        .stops
+        .iter()
+        .map(|stop| stop.color)
+        .max_by(|a, b| Oklch::from(*a).c.total_cmp(&Oklch::from(*b).c))
+        .unwrap_or(gdk::RGBA::BLACK);
+    let key = gdk::RGBA::new(key.red(), key.green(), key.blue(), 1.0);
+
+    let window = gdk::RGBA::parse("#fafafa").unwrap();
+    let accent_bg = color::with_contrast(&key, &gdk::RGBA::WHITE, ACCENT_BG_CONTRAST);
+    let accent = color::with_contrast(&key, &window, ACCENT_CONTRAST);
+
+    format!(
+        "/* Accent colors from Vibrant, for ~/.config/gtk-4.0/gtk.css */\n@define-color accent_color {};\n@define-color accent_bg_color {};\n@define-color accent_fg_color #ffffff;\n",
+        color::to_hex(&accent),
+        color::to_hex(&accent_bg)
+    )
+}
+
+/// A variable name with the prefix of the style, in camel case unless the
+/// prefix ends with a separator.
+fn variable(style: &CodeStyle, name: &str) -> String {
+    let prefix = &style.variable_prefix;
+    match prefix.chars().last() {
+        Some(last) if last.is_alphanumeric() => {
+            let mut chars = name.chars();
+            let first = chars.next().map(|c| c.to_uppercase().collect::<String>());
+            format!("{prefix}{}{}", first.unwrap_or_default(), chars.as_str())
+        }
+        _ => format!("{prefix}{name}"),
+    }
+}
+
+/// Positions are resolved, so every stop has one even where CSS would
+/// spread them out.
+fn javascript(gradient: &Gradient, style: &CodeStyle) -> String {
+    let indent = style.indentation();
+    let semicolon = if style.semicolons { ";" } else { "" };
+
+    let mut code = format!("const {} = {{\n", variable(style, "gradient"));
+    let _ = writeln!(code, "{indent}type: \"{}\",", gradient.kind.name());
+    if gradient.kind != GradientType::Radial {
+        let _ = writeln!(code, "{indent}angle: {},", number(gradient.angle));
+    }
+    if gradient.hard_stops {
+        let _ = writeln!(code, "{indent}hardStops: true,");
+    }
+
+    let _ = writeln!(code, "{indent}stops: [");
+    for (stop, position) in gradient.stops.iter().zip(gradient.positions()) {
+        let _ = writeln!(
+            code,
+            "{indent}{indent}{{ color: \"{}\", position: {} }},",
+            style.format_color(&stop.color),
+            number(position)
+        );
+    }
+    let _ = writeln!(code, "{indent}],\n}}{semicolon}");
+
+    code
+}
+
+/// Sizes the gradient to the canvas the way CSS sizes it to a box, and
+/// shares the JavaScript code style.
+fn canvas(gradient: &Gradient, style: &CodeStyle) -> String {
+    let semicolon = if style.semicolons { ";" } else { "" };
+    let name = variable(style, "gradient");
+
+    let mut code = format!(
+        "const canvas = document.querySelector(\"canvas\"){semicolon}\nconst context = canvas.getContext(\"2d\"){semicolon}\nconst {{ width, height }} = canvas{semicolon}\n\n"
+    );
+
+    match gradient.kind {
+        GradientType::Linear => {
+            //the gradient line reaches the corners, like in CSS
+            let _ = writeln!(
+                code,
+                "const angle = {} * Math.PI / 180{semicolon}\nconst length = Math.abs(width * Math.sin(angle)) + Math.abs(height * Math.cos(angle)){semicolon}\nconst dx = Math.sin(angle) * length / 2{semicolon}\nconst dy = -Math.cos(angle) * length / 2{semicolon}\nconst {name} = context.createLinearGradient(width / 2 - dx, height / 2 - dy, width / 2 + dx, height / 2 + dy){semicolon}",
+                number(gradient.angle)
+            );
+        }
+        GradientType::Radial => {
+            //a unit circle stretched into the ellipse reaching the corners
+            let _ = writeln!(
+                code,
+                "const {name} = context.createRadialGradient(0, 0, 0, 0, 0, 1){semicolon}"
+            );
+        }
+        GradientType::Conic => {
+            //canvas angles start at the right instead of the top
+            let _ = writeln!(
+                code,
+                "const {name} = context.createConicGradient(({} - 90) * Math.PI / 180, width / 2, height / 2){semicolon}",
+                number(gradient.angle)
+            );
+        }
+    }
+
+    for (position, color) in blended_stops(gradient) {
+        let _ = writeln!(
+            code,
+            "{name}.addColorStop({}, \"{}\"){semicolon}",
+            number(position),
+            style.format_color(&color)
+        );
+    }
+
+    let _ = write!(code, "\ncontext.fillStyle = {name}{semicolon}\n");
+    if gradient.kind == GradientType::Radial {
+        let _ = write!(
+            code,
+            "context.translate(width / 2, height / 2){semicolon}\ncontext.scale(width * Math.SQRT1_2, height * Math.SQRT1_2){semicolon}\ncontext.fillRect(-1, -1, 2, 2){semicolon}\n"
+        );
+    } else {
+        let _ = writeln!(code, "context.fillRect(0, 0, width, height){semicolon}");
+    }
+
+    code
+}
+
+/// Stops as positions and colors, with hard stops repeating each color at
+/// both ends of its segment, for APIs that only blend.
+fn blended_stops(gradient: &Gradient) -> Vec<(f32, gdk::RGBA)> {
+    if gradient.hard_stops {
+        gradient
+            .stops
+            .iter()
+            .zip(gradient.segments())
+            .flat_map(|(stop, (start, end))| [(start, stop.color), (end, stop.color)])
+            .collect()
+    } else {
+        gradient
+            .positions()
+            .into_iter()
+            .zip(gradient.stops.iter().map(|stop| stop.color))
+            .collect()
+    }
+}
+
+/// Stops for a colormap, which maps values from 0 to 1 and so needs colors
+/// at both ends. The direction of the gradient doesn't matter there.
+pub fn colormap_stops(gradient: &Gradient) -> Vec<(f32, gdk::RGBA)> {
+    let mut stops = blended_stops(gradient);
+    if let Some(&(_, color)) = stops.first().filter(|(position, _)| *position > 0.0) {
+        stops.insert(0, (0.0, color));
+    }
+    if let Some(&(_, color)) = stops.last().filter(|(position, _)| *position < 1.0) {
+        stops.push((1.0, color));
+    }
+    stops
+}
+
+fn matplotlib(gradient: &Gradient) -> String {
+    let stops: Vec<String> = colormap_stops(gradient)
+        .iter()
+        .map(|(position, rgba)| {
+            format!("    ({}, \"{}\"),", number(*position), color::to_hex(rgba))
+        })
+        .collect();
+
+    format!(
+        r#"from matplotlib.colors import LinearSegmentedColormap
+
+cmap = LinearSegmentedColormap.from_list("vibrant", [
+{}
+])
+"#,
+        stops.join("\n")
+    )
+}
+
+/// The scale blends in sRGB like the gradient, and maps values from 0 to 1
+/// so it can be passed wherever d3 expects an interpolator. Colors are
+/// always hex codes, the only format besides `rgb()` d3 parses everywhere.
+fn d3(gradient: &Gradient, style: &CodeStyle) -> String {
+    let semicolon = if style.semicolons { ";" } else { "" };
+    let stops = colormap_stops(gradient);
+    let domain: Vec<String> = stops
+        .iter()
+        .map(|(position, _)| number(*position))
+        .collect();
+    let range: Vec<String> = stops
+        .iter()
+        .map(|(_, rgba)| format!("\"{}\"", color::to_hex(rgba)))
+        .collect();
+    let name = variable(style, "scale");
+
+    format!(
+        "const {name} = d3.scaleLinear()\n{indent}.domain([{}])\n{indent}.range([{}])\n{indent}.interpolate(d3.interpolateRgb){semicolon}\n\nconst {} = (t) => {name}(t){semicolon}\n",
+        domain.join(", "),
+        range.join(", "),
+        variable(style, "interpolator"),
+        indent = style.indentation()
+    )
+}
+
+/// Side and corner angles map to `Brush.linearGradient`, whose infinite
+/// coordinates stand for the edges of the view, matching CSS on square
+/// views. Other angles need the size the brush fills, so they get a
+/// `ShaderBrush`. Radial gradients are circles reaching the corners, as
+/// Compose has no elliptical shader.
+fn compose(gradient: &Gradient) -> String {
+    let stops = blended_stops(gradient);
+    let colors: Vec<String> = stops
+        .iter()
+        .map(|(_, rgba)| {
+            let [r, g, b] = rgb(rgba);
+            let a = (rgba.alpha().clamp(0.0, 1.0) * 255.0).round() as u8;
+            format!("Color(0x{a:02X}{r:02X}{g:02X}{b:02X})")
+        })
+        .collect();
+    let positions: Vec<String> = stops
+        .iter()
+        .map(|(position, _)| format!("{}f", number(*position)))
+        .collect();
+
+    let angle = gradient.angle.rem_euclid(360.0);
+    if gradient.kind == GradientType::Linear && angle % 45.0 == 0.0 {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        //where the line starts and ends along one axis, the far edge being infinite
+        let ends = |direction: f32| match direction.round() as i32 {
+            1 => ("0f", "Float.POSITIVE_INFINITY"),
+            -1 => ("Float.POSITIVE_INFINITY", "0f"),
+            _ => ("0f", "0f"),
+        };
+        let ((start_x, end_x), (start_y, end_y)) = (ends(sin * 2.0), ends(-cos * 2.0));
+        let color_stops: Vec<String> = positions
+            .iter()
+            .zip(&colors)
+            .map(|(position, color)| format!("    {position} to {color},\n"))
+            .collect();
+
+        return format!(
+            "import androidx.compose.ui.geometry.Offset
+import androidx.compose.ui.graphics.Brush
+import androidx.compose.ui.graphics.Color
+
+val gradient = Brush.linearGradient(
+{}    start = Offset({start_x}, {start_y}),
+    end = Offset({end_x}, {end_y}),
+)
+",
+            color_stops.concat()
+        );
+    }
+
+    let shader = match gradient.kind {
+        GradientType::Linear => format!(
+            "        //the gradient line reaches the corners, like in CSS
+        val angle = Math.toRadians({})
+        val length = abs(size.width * sin(angle)) + abs(size.height * cos(angle))
+        val delta = Offset((sin(angle) * length / 2).toFloat(), (-cos(angle) * length / 2).toFloat())
+        return LinearGradientShader(size.center - delta, size.center + delta, colors, stops)",
+            float(gradient.angle)
+        ),
+        GradientType::Radial => "        return RadialGradientShader(size.center, hypot(size.width, size.height) / 2, colors, stops)".to_owned(),
+        GradientType::Conic => format!(
+            "        //sweeps start at the right instead of the top
+        return SweepGradientShader(size.center, colors, stops).apply {{
+            setLocalMatrix(android.graphics.Matrix().apply {{ setRotate({}f - 90f, size.center.x, size.center.y) }})
+        }}",
+            number(gradient.angle)
+        ),
+    };
+
+    format!(
+        "import androidx.compose.ui.geometry.Offset
+import androidx.compose.ui.geometry.Size
+import androidx.compose.ui.geometry.center
+import androidx.compose.ui.graphics.Color
+import androidx.compose.ui.graphics.LinearGradientShader
+import androidx.compose.ui.graphics.RadialGradientShader
+import androidx.compose.ui.graphics.Shader
+import androidx.compose.ui.graphics.ShaderBrush
+import androidx.compose.ui.graphics.SweepGradientShader
+import kotlin.math.abs
+import kotlin.math.cos
+import kotlin.math.hypot
+import kotlin.math.sin
+
+val gradient = object : ShaderBrush() {{
+    private val colors = listOf({})
+    private val stops = listOf({})
+
+    override fun createShader(size: Size): Shader {{
+{shader}
+    }}
+}}
+",
+        colors.join(", "),
+        positions.join(", ")
+    )
+}
+
+/// Start and end points are fractions of the view, matching CSS on square
+/// views. The component only draws linear gradients, so others keep their
+/// colors going down.
+fn expo(gradient: &Gradient) -> String {
+    let stops = blended_stops(gradient);
+    let colors: Vec<String> = stops
+        .iter()
+        .map(|(_, rgba)| format!("\"{}\"", color::to_hex(rgba)))
+        .collect();
+    let locations: Vec<String> = stops
+        .iter()
+        .map(|(position, _)| number(*position))
+        .collect();
+
+    let angle = if gradient.kind == GradientType::Linear {
+        gradient.angle
+    } else {
+        180.0
+    };
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let half = (sin.abs() + cos.abs()) / 2.0;
+
+    let mut code = String::new();
+    if gradient.kind != GradientType::Linear {
+        code.push_str("{/* expo-linear-gradient only draws linear gradients */}\n");
+    }
+    let _ = write!(
+        code,
+        "<LinearGradient\n  colors={{[{}]}}\n  locations={{[{}]}}\n  start={{{{ x: {}, y: {} }}}}\n  end={{{{ x: {}, y: {} }}}}\n/>\n",
+        colors.join(", "),
+        locations.join(", "),
+        number(0.5 - sin * half),
+        number(0.5 + cos * half),
+        number(0.5 + sin * half),
+        number(0.5 - cos * half)
+    );
+
+    code
+}
+
+/// The paint a plugin can assign to `node.fills`. Figma has no clipboard
+/// format open to other apps, so this is as close as pasting gets.
+///
+/// The gradient transform maps the unit square of the node into gradient
+/// space, where linear gradients run from (0, 0.5) to (1, 0.5) and the
+/// others are centered with a radius of 0.5, starting at the right.
+fn figma(gradient: &Gradient) -> String {
+    let (sin, cos) = gradient.angle.to_radians().sin_cos();
+
+    let (kind, transform) = match gradient.kind {
+        GradientType::Linear => {
+            //the CSS gradient line of a square, in node coordinates
+            let half = (sin.abs() + cos.abs()) / 2.0;
+            let (x, y) = (0.5 - sin * half, 0.5 + cos * half);
+            let (dx, dy) = (2.0 * sin * half, -2.0 * cos * half);
+            let length = (dx * dx + dy * dy).max(f32::EPSILON);
+            (
+                "GRADIENT_LINEAR",
+                [
+                    [dx / length, dy / length, -(dx * x + dy * y) / length],
+                    [-dy / length, dx / length, 0.5 - (dx * y - dy * x) / length],
+                ],
+            )
+        }
+        GradientType::Radial => {
+            //the ellipse reaching the corners down to a radius of 0.5
+            let scale = std::f32::consts::FRAC_1_SQRT_2;
+            let offset = 0.5 - 0.5 * scale;
+            (
+                "GRADIENT_RADIAL",
+                [[scale, 0.0, offset], [0.0, scale, offset]],
+            )
+        }
+        GradientType::Conic => {
+            //turn the starting direction of CSS to the right
+            (
+                "GRADIENT_ANGULAR",
+                [
+                    [sin, -cos, 0.5 - 0.5 * (sin - cos)],
+                    [cos, sin, 0.5 - 0.5 * (cos + sin)],
+                ],
+            )
+        }
+    };
+
+    let stops: Vec<Value> = blended_stops(gradient)
+        .into_iter()
+        .map(|(position, rgba)| {
+            Value::object([
+                (
+                    "color",
+                    Value::object([
+                        ("r", rgba.red().into()),
+                        ("g", rgba.green().into()),
+                        ("b", rgba.blue().into()),
+                        ("a", rgba.alpha().into()),
+                    ]),
+                ),
+                ("position", position.into()),
+            ])
+        })
+        .collect();
+    let transform: Vec<Value> = transform
+        .iter()
+        .map(|row| Value::from(row.to_vec()))
+        .collect();
+
+    Value::object([
+        ("type", kind.into()),
+        ("gradientTransform", transform.into()),
+        ("gradientStops", stops.into()),
+    ])
+    .to_json()
+}
+
+/// A fill a Penpot plugin can assign to `shape.fills`. Points are fractions
+/// of the shape, so radial gradients reach the corners on any shape like in
+/// CSS. Penpot has no conic gradients, so those keep their colors going
+/// down.
+fn penpot(gradient: &Gradient) -> String {
+    let (kind, start, end) = match gradient.kind {
+        GradientType::Linear => {
+            let (sin, cos) = gradient.angle.to_radians().sin_cos();
+            let half = (sin.abs() + cos.abs()) / 2.0;
+            (
+                "linear",
+                (0.5 - sin * half, 0.5 + cos * half),
+                (0.5 + sin * half, 0.5 - cos * half),
+            )
+        }
+        GradientType::Radial => (
+            "radial",
+            (0.5, 0.5),
+            (0.5, 0.5 + std::f32::consts::FRAC_1_SQRT_2),
+        ),
+        GradientType::Conic => ("linear", (0.5, 0.0), (0.5, 1.0)),
+    };
+
+    let stops: Vec<Value> = blended_stops(gradient)
+        .into_iter()
+        .map(|(offset, rgba)| {
+            let opaque = gdk::RGBA::new(rgba.red(), rgba.green(), rgba.blue(), 1.0);
+            Value::object([
+                ("color", color::to_hex(&opaque).into()),
+                ("opacity", rgba.alpha().into()),
+                ("offset", offset.into()),
+            ])
+        })
+        .collect();
+
+    Value::object([(
+        "fillColorGradient",
+        Value::object([
+            ("type", kind.into()),
+            ("startX", start.0.into()),
+            ("startY", start.1.into()),
+            ("endX", end.0.into()),
+            ("endY", end.1.into()),
+            ("width", 1.0f32.into()),
+            ("stops", stops.into()),
+        ]),
+    )])
+    .to_json()
+}
+
+/// Follows the CSS geometry on a square, with `vUv` going up from the
+/// bottom left corner. Colors stay in sRGB and blend there, like CSS.
+fn glsl(gradient: &Gradient) -> String {
+    let keys = keys(gradient);
+    let count = keys.len().max(1);
+
+    let colors: Vec<String> = keys
+        .iter()
+        .map(|(_, color)| {
+            format!(
+                "    vec4({}, {}, {}, {})",
+                float(color.red()),
+                float(color.green()),
+                float(color.blue()),
+                float(color.alpha())
+            )
+        })
+        .collect();
+    let positions: Vec<String> = keys
+        .iter()
+        .map(|(position, _)| format!("    {}", float(*position)))
+        .collect();
+
+    let offset = match gradient.kind {
+        GradientType::Linear => {
+            //the gradient line reaches the corners
+            let (sin, cos) = gradient.angle.to_radians().sin_cos();
+            format!(
+                "vec2 direction = vec2({}, {});\n    float t = dot(uv - 0.5, direction) / {} + 0.5;",
+                float(sin),
+                float(cos),
+                float(sin.abs() + cos.abs())
+            )
+        }
+        GradientType::Radial => "float t = length(uv - 0.5) * sqrt(2.0);".to_owned(),
+        GradientType::Conic => format!(
+            "vec2 p = uv - 0.5;\n    float t = fract((degrees(atan(p.x, p.y)) - {}) / 360.0);",
+            float(gradient.angle)
+        ),
+    };
+    let blend = if gradient.hard_stops {
+        "step(POSITIONS[i], t)"
+    } else {
+        "clamp((t - POSITIONS[i - 1]) / max(POSITIONS[i] - POSITIONS[i - 1], 1e-5), 0.0, 1.0)"
+    };
+
+    format!(
+        r#"// Gradient from Vibrant
+const int STOP_COUNT = {count};
+const vec4 COLORS[STOP_COUNT] = vec4[](
+{}
+);
+const float POSITIONS[STOP_COUNT] = float[](
+{}
+);
+
+vec4 gradient(vec2 uv) {{
+    {offset}
+
+    vec4 color = COLORS[0];
+    for (int i = 1; i < STOP_COUNT; i++) {{
+        color = mix(color, COLORS[i], {blend});
+    }}
+    return color;
+}}
+
+varying vec2 vUv;
+
+void main() {{
+    gl_FragColor = gradient(vUv);
+}}
+"#,
+        colors.join(",\n"),
+        positions.join(",\n")
+    )
+}
+
+/// GLSL needs a decimal point to read a number as a float.
+fn float(value: f32) -> String {
+    let number = number(value);
+    if number.contains('.') {
+        number
+    } else {
+        format!("{number}.0")
+    }
+}
@@ -0,0 +1,234 @@
+/* stats.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::gdk;
+
+use crate::color::{self, Oklab, Oklch};
+use crate::gradient::{Gradient, GradientType};
+use crate::print;
+
+/// Points sampled along the gradient for the figures measured along it.
+const LUMINANCE_SAMPLES: usize = 64;
+
+/// Chroma under which a color reads as gray, so it can't turn muddy.
+const MUDDY_MIN_CHROMA: f32 = 0.05;
+
+/// How much of the chroma of the duller end the midpoint must keep.
+const MUDDY_RATIO: f32 = 0.5;
+
+/// Change of OKLab lightness below which a turn doesn't count, so tiny
+/// wobbles don't flag a gradient.
+const LIGHTNESS_TOLERANCE: f32 = 0.02;
+
+/// Size of a 4K display, where gradients spread over the most pixels.
+const UHD_WIDTH: f32 = 3840.0;
+const UHD_HEIGHT: f32 = 2160.0;
+
+/// Width in pixels from which a step between 8-bit levels shows as a band.
+const BANDING_WIDTH: f32 = 12.0;
+
+/// Grain that hides the steps, enough to dither without looking noisy.
+pub const DITHER_GRAIN: f32 = 0.1;
+
+/// Figures computed from a gradient to help judge it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    /// Average WCAG relative luminance along the gradient line, from 0 to 1.
+    pub average_luminance: f32,
+    /// WCAG contrast ratio between the first and last colors.
+    pub end_contrast: f32,
+    /// Perceptual difference between each pair of neighboring stops.
+    pub differences: Vec<f32>,
+    /// Indices of the stops whose blend with the next one turns muddy.
+    pub muddy: Vec<usize>,
+    /// The colors sampled along the gradient line.
+    pub samples: Vec<gdk::RGBA>,
+    /// How many times the lightness turns from rising to falling or back,
+    /// which makes a colormap hard to read. Zero when it's monotonic.
+    pub lightness_turns: usize,
+    /// How evenly the perceptual difference spreads along the gradient,
+    /// from 0 to 1 when every step looks as large as the others.
+    pub uniformity: f32,
+    /// Indices of the stops that visibly change when printed.
+    pub unprintable: Vec<usize>,
+    /// Width in pixels of the widest band of a single 8-bit level on a 4K
+    /// display, or 0 when the grain already hides them.
+    pub widest_band: f32,
+}
+
+impl Stats {
+    pub fn new(gradient: &Gradient) -> Self {
+        let samples = gradient.sample(LUMINANCE_SAMPLES);
+        let average_luminance =
+            samples.iter().map(color::luminance).sum::<f32>() / samples.len().max(1) as f32;
+
+        let colors: Vec<gdk::RGBA> = gradient.stops.iter().map(|stop| stop.color).collect();
+        let end_contrast = match (colors.first(), colors.last()) {
+            (Some(first), Some(last)) => color::contrast_ratio(first, last),
+            _ => 1.0,
+        };
+
+        let differences = colors
+            .windows(2)
+            .map(|pair| color::delta_e(&pair[0], &pair[1]))
+            .collect();
+
+        //hard stops don't blend, so there is no midpoint to turn muddy
+        let muddy = if gradient.hard_stops {
+            Vec::new()
+        } else {
+            colors
+                .windows(2)
+                .enumerate()
+                .filter(|(_, pair)| is_muddy(&pair[0], &pair[1]))
+                .map(|(i, _)| i)
+                .collect()
+        };
+
+        let widest_band = if gradient.grain >= DITHER_GRAIN {
+            0.0
+        } else {
+            widest_band(gradient)
+        };
+
+        let lightness: Vec<f32> = samples.iter().map(|rgba| Oklab::from(*rgba).l).collect();
+        let steps: Vec<f32> = samples
+            .windows(2)
+            .map(|pair| color::delta_e(&pair[0], &pair[1]))
+            .collect();
+
+        Self {
+            average_luminance,
+            end_contrast,
+            differences,
+            muddy,
+            lightness_turns: turns(&lightness),
+            uniformity: uniformity(&steps),
+            samples,
+            unprintable: (0..colors.len())
+                .filter(|&i| !print::is_printable(&colors[i]))
+                .collect(),
+            widest_band,
+        }
+    }
+
+    /// Whether the gradient will show bands on 8-bit displays at 4K.
+    pub fn bands(&self) -> bool {
+        self.widest_band >= BANDING_WIDTH
+    }
+}
+
+/// Counts the turns of a series, ignoring changes under the tolerance.
+fn turns(values: &[f32]) -> usize {
+    let Some((&first, rest)) = values.split_first() else {
+        return 0;
+    };
+
+    //the furthest value reached since the last turn
+    let mut extreme = first;
+    let mut direction = 0.0;
+    let mut turns = 0;
+    for &value in rest {
+        let change = value - extreme;
+        if change * direction > 0.0 {
+            extreme = value;
+        } else if change.abs() > LIGHTNESS_TOLERANCE {
+            if direction != 0.0 {
+                turns += 1;
+            }
+            direction = change.signum();
+            extreme = value;
+        }
+    }
+
+    turns
+}
+
+/// One minus the coefficient of variation of the steps.
+fn uniformity(steps: &[f32]) -> f32 {
+    let mean = steps.iter().sum::<f32>() / steps.len().max(1) as f32;
+    if mean <= f32::EPSILON {
+        return 1.0;
+    }
+
+    let variance = steps.iter().map(|step| (step - mean).powi(2)).sum::<f32>() / steps.len() as f32;
+    (1.0 - variance.sqrt() / mean).clamp(0.0, 1.0)
+}
+
+/// Length of the gradient line on a 4K display.
+fn line_length(gradient: &Gradient) -> f32 {
+    match gradient.kind {
+        GradientType::Linear => {
+            let (sin, cos) = gradient.angle.to_radians().sin_cos();
+            (UHD_WIDTH * sin).abs() + (UHD_HEIGHT * cos).abs()
+        }
+        //out to the farthest corner
+        GradientType::Radial => (UHD_WIDTH / 2.0).hypot(UHD_HEIGHT / 2.0),
+        //around the edge of the largest circle that fits
+        GradientType::Conic => std::f32::consts::PI * UHD_HEIGHT,
+    }
+}
+
+/// Spreads each pair of neighboring stops over the 8-bit levels between
+/// them, and finds the widest run of pixels sharing one level.
+fn widest_band(gradient: &Gradient) -> f32 {
+    //hard stops don't blend, so there are no steps between levels
+    if gradient.hard_stops {
+        return 0.0;
+    }
+
+    let length = line_length(gradient);
+    let positions = gradient.positions();
+    gradient
+        .stops
+        .windows(2)
+        .zip(positions.windows(2))
+        .filter_map(|(stops, positions)| {
+            let (from, to) = (stops[0].color, stops[1].color);
+            let levels = [
+                to.red() - from.red(),
+                to.green() - from.green(),
+                to.blue() - from.blue(),
+            ]
+            .map(|channel| (channel.abs() * 255.0).round())
+            .into_iter()
+            .fold(0.0, f32::max);
+
+            (levels > 0.0).then(|| (positions[1] - positions[0]) * length / levels)
+        })
+        .fold(0.0, f32::max)
+}
+
+/// Whether blending two colors in sRGB passes through gray or brown, which
+/// happens when their midpoint loses most of the chroma both ends have.
+pub fn is_muddy(from: &gdk::RGBA, to: &gdk::RGBA) -> bool {
+    let end_chroma = Oklch::from(*from).c.min(Oklch::from(*to).c);
+    if end_chroma < MUDDY_MIN_CHROMA {
+        return false;
+    }
+
+    let midpoint = gdk::RGBA::new(
+        (from.red() + to.red()) / 2.0,
+        (from.green() + to.green()) / 2.0,
+        (from.blue() + to.blue()) / 2.0,
+        1.0,
+    );
+    Oklch::from(midpoint).c < end_chroma * MUDDY_RATIO
+}
@@ -0,0 +1,82 @@
+/* swatch.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::cell::Cell;
+
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use gtk::{gdk, glib, graphene, gsk};
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct VibrantSwatch {
+        pub rgba: Cell<Option<gdk::RGBA>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for VibrantSwatch {
+        const NAME: &'static str = "VibrantSwatch";
+        type Type = super::VibrantSwatch;
+        type ParentType = gtk::Widget;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_css_name("swatch");
+        }
+    }
+
+    impl ObjectImpl for VibrantSwatch {}
+
+    impl WidgetImpl for VibrantSwatch {
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            let Some(rgba) = self.rgba.get() else {
+                return;
+            };
+
+            let widget = self.obj();
+            let bounds =
+                graphene::Rect::new(0.0, 0.0, widget.width() as f32, widget.height() as f32);
+            let radius = bounds.width().min(bounds.height()) / 4.0;
+
+            snapshot.push_rounded_clip(&gsk::RoundedRect::from_rect(bounds, radius));
+            snapshot.append_color(&rgba, &bounds);
+            snapshot.pop();
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct VibrantSwatch(ObjectSubclass<imp::VibrantSwatch>)
+        @extends gtk::Widget;
+}
+
+impl VibrantSwatch {
+    pub fn new(rgba: &gdk::RGBA) -> Self {
+        let swatch: Self = glib::Object::new();
+        swatch.set_rgba(rgba);
+        swatch
+    }
+
+    pub fn set_rgba(&self, rgba: &gdk::RGBA) {
+        self.imp().rgba.set(Some(*rgba));
+        self.queue_draw();
+    }
+}
@@ -0,0 +1,95 @@
+/* sync.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::prelude::*;
+use gtk::{gio, glib};
+
+use crate::palette;
+
+/// Files of the library kept in the sync folder, relative to both.
+const FILES: &[&str] = &[palette::PALETTES_FILE];
+
+/// What a sync changed.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Outcome {
+    /// Whether newer files were copied from the sync folder.
+    pub downloaded: bool,
+    /// Whether both copies of a file changed since the last sync, and the
+    /// older changes were replaced.
+    pub conflict: bool,
+}
+
+async fn modified(file: &gio::File) -> Option<i64> {
+    file.query_info_future(
+        gio::FILE_ATTRIBUTE_TIME_MODIFIED,
+        gio::FileQueryInfoFlags::NONE,
+        glib::Priority::DEFAULT,
+    )
+    .await
+    .ok()?
+    .modification_date_time()
+    .map(|time| time.to_unix())
+}
+
+/// Copies each library file between the library and `folder`, any folder
+/// GVfs can write to, so the newest copy wins. `last_sync` is the Unix time
+/// of the previous sync, telling apart files changed on both sides.
+pub async fn sync(folder: &gio::File, last_sync: i64) -> Result<Outcome, glib::Error> {
+    let mut outcome = Outcome::default();
+
+    for name in FILES {
+        let local = gio::File::for_path(palette::library_dir().join(name));
+        let remote = folder.child(name);
+        let local_time = modified(&local).await;
+        let remote_time = modified(&remote).await;
+
+        let (source, destination, overwritten) = match (local_time, remote_time) {
+            (_, Some(remote_time)) if local_time.is_none_or(|time| remote_time > time) => {
+                outcome.downloaded = true;
+                (&remote, &local, local_time)
+            }
+            (Some(local_time), _) if remote_time.is_none_or(|time| local_time > time) => {
+                (&local, &remote, remote_time)
+            }
+            _ => continue,
+        };
+
+        if let Some(parent) = destination.parent() {
+            if let Err(err) = parent.make_directory_with_parents(gio::Cancellable::NONE) {
+                if !err.matches(gio::IOErrorEnum::Exists) {
+                    return Err(err);
+                }
+            }
+        }
+        //keeping the modification time, so the other side sees it unchanged
+        source
+            .copy_future(
+                destination,
+                gio::FileCopyFlags::OVERWRITE | gio::FileCopyFlags::ALL_METADATA,
+                glib::Priority::DEFAULT,
+            )
+            .0
+            .await?;
+
+        outcome.conflict |= overwritten.is_some_and(|time| time > last_sync);
+    }
+
+    Ok(outcome)
+}
@@ -0,0 +1,106 @@
+/* tab.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::cell::{Ref, RefCell};
+
+use gtk::{gio, glib};
+
+use adw::subclass::prelude::*;
+
+use crate::document::{Collection, Metadata};
+use crate::gradient::Gradient;
+
+/// Everything belonging to one open document.
+#[derive(Debug, Default)]
+pub struct Session {
+    pub gradient: Gradient,
+    /// The gradient as last opened or saved, telling unsaved changes apart.
+    pub saved_gradient: Gradient,
+    /// The open document, unset until it is first saved.
+    pub file: Option<gio::File>,
+    pub metadata: Metadata,
+    /// The other gradients of the document, with `gradient` as the active
+    /// one, and the collection as last opened or saved.
+    pub collection: Collection,
+    pub saved_collection: Collection,
+    pub undo_stack: Vec<Gradient>,
+    pub redo_stack: Vec<Gradient>,
+    pub selected_stop: usize,
+    /// Whether the document left the start page.
+    pub started: bool,
+}
+
+impl Session {
+    pub fn is_modified(&self) -> bool {
+        self.gradient != self.saved_gradient
+            || self
+                .collection
+                .is_modified(&self.gradient, &self.saved_collection)
+    }
+}
+
+mod imp {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct VibrantTab {
+        pub session: RefCell<Session>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for VibrantTab {
+        const NAME: &'static str = "VibrantTab";
+        type Type = super::VibrantTab;
+        type ParentType = adw::Bin;
+    }
+
+    impl ObjectImpl for VibrantTab {}
+    impl WidgetImpl for VibrantTab {}
+    impl BinImpl for VibrantTab {}
+}
+
+glib::wrapper! {
+    /// The child of a tab page, holding its document while another tab is
+    /// selected. The window edits the selected document in place and moves
+    /// it back here when switching away, so a tab dragged to another window
+    /// takes its document and history along.
+    pub struct VibrantTab(ObjectSubclass<imp::VibrantTab>)
+        @extends gtk::Widget, adw::Bin;
+}
+
+impl VibrantTab {
+    pub fn new(session: Session) -> Self {
+        let tab: Self = glib::Object::new();
+        tab.imp().session.replace(session);
+        tab
+    }
+
+    pub fn session(&self) -> Ref<'_, Session> {
+        self.imp().session.borrow()
+    }
+
+    pub fn replace_session(&self, session: Session) {
+        self.imp().session.replace(session);
+    }
+
+    pub fn take_session(&self) -> Session {
+        self.imp().session.take()
+    }
+}
@@ -0,0 +1,173 @@
+/* terminal.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::fmt::Write;
+
+use gtk::gdk;
+
+use crate::color::{self, GamutMapping, Oklab, Oklch};
+use crate::gradient::Gradient;
+
+/// Names of the eight ANSI colors, in order.
+const NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+/// OKLCH hues of red, green, yellow, blue, magenta and cyan. The gradient
+/// only tints them, so programs using them for meaning still make sense.
+const HUES: [f32; 6] = [29.0, 142.0, 100.0, 264.0, 328.0, 195.0];
+/// Gradient hues closer than this pull the ANSI ones toward them.
+const HUE_REACH: f32 = 45.0;
+/// Contrast every color keeps against the background, the WCAG AA minimum.
+const MIN_CONTRAST: f32 = 4.5;
+const SAMPLES: usize = 32;
+
+/// A 16 color terminal palette in the mood of a gradient.
+#[derive(Debug, Clone)]
+pub struct Scheme {
+    pub background: gdk::RGBA,
+    pub foreground: gdk::RGBA,
+    pub normal: [gdk::RGBA; 8],
+    pub bright: [gdk::RGBA; 8],
+}
+
+fn oklch(l: f32, c: f32, h: f32) -> Oklch {
+    Oklch {
+        l,
+        c,
+        h,
+        alpha: 1.0,
+    }
+}
+
+/// Signed difference between two hues, from -180 to 180 degrees.
+fn hue_difference(from: f32, to: f32) -> f32 {
+    (to - from + 540.0).rem_euclid(360.0) - 180.0
+}
+
+/// Lightens the color until it reads on the background, keeping its hue.
+fn readable(lch: Oklch, background: &gdk::RGBA) -> gdk::RGBA {
+    let rgba = Oklab::from(lch).to_rgba_mapped(GamutMapping::ReduceChroma);
+    color::with_contrast(&rgba, background, MIN_CONTRAST)
+}
+
+impl Scheme {
+    /// Takes the background from the darkest color of the gradient and
+    /// tints the ANSI colors with its hues and saturation.
+    pub fn new(gradient: &Gradient) -> Self {
+        let samples: Vec<Oklch> = gradient
+            .sample(SAMPLES)
+            .into_iter()
+            .map(Oklch::from)
+            .collect();
+        let darkest = samples
+            .iter()
+            .copied()
+            .min_by(|a, b| a.l.total_cmp(&b.l))
+            .unwrap_or(oklch(0.0, 0.0, 0.0));
+        //grays have no hue worth following
+        let colorful: Vec<Oklch> = samples.iter().copied().filter(|lch| lch.c > 0.03).collect();
+        let chroma = if colorful.is_empty() {
+            0.12
+        } else {
+            (colorful.iter().map(|lch| lch.c).sum::<f32>() / colorful.len() as f32).clamp(0.08, 0.2)
+        };
+
+        let background = oklch(0.2, darkest.c.min(0.04), darkest.h).to_rgba();
+        let foreground = oklch(0.92, darkest.c.min(0.02), darkest.h).to_rgba();
+
+        let mut normal = [gdk::RGBA::BLACK; 8];
+        let mut bright = [gdk::RGBA::BLACK; 8];
+        normal[0] = oklch(0.3, darkest.c.min(0.04), darkest.h).to_rgba();
+        bright[0] = readable(oklch(0.45, darkest.c.min(0.04), darkest.h), &background);
+        normal[7] = readable(oklch(0.8, darkest.c.min(0.02), darkest.h), &background);
+        bright[7] = oklch(0.97, darkest.c.min(0.02), darkest.h).to_rgba();
+
+        for (i, hue) in HUES.into_iter().enumerate() {
+            let nearest = colorful
+                .iter()
+                .map(|lch| hue_difference(hue, lch.h))
+                .min_by(|a, b| a.abs().total_cmp(&b.abs()))
+                .filter(|difference| difference.abs() < HUE_REACH)
+                .unwrap_or(0.0);
+            let hue = (hue + nearest / 2.0).rem_euclid(360.0);
+
+            normal[i + 1] = readable(oklch(0.65, chroma, hue), &background);
+            bright[i + 1] = readable(oklch(0.75, chroma * 1.1, hue), &background);
+        }
+
+        Self {
+            background,
+            foreground,
+            normal,
+            bright,
+        }
+    }
+
+    /// A theme for Alacritty's TOML configuration.
+    pub fn to_alacritty(&self) -> String {
+        let mut toml = format!(
+            "[colors.primary]\nbackground = \"{}\"\nforeground = \"{}\"\n",
+            color::to_hex(&self.background),
+            color::to_hex(&self.foreground)
+        );
+
+        for (section, colors) in [("normal", &self.normal), ("bright", &self.bright)] {
+            let _ = writeln!(toml, "\n[colors.{section}]");
+            for (name, rgba) in NAMES.iter().zip(colors) {
+                let _ = writeln!(toml, "{name} = \"{}\"", color::to_hex(rgba));
+            }
+        }
+
+        toml
+    }
+
+    /// A kitty theme, which can be included from `kitty.conf`.
+    pub fn to_kitty(&self) -> String {
+        let mut conf = format!(
+            "background {}\nforeground {}\n",
+            color::to_hex(&self.background),
+            color::to_hex(&self.foreground)
+        );
+
+        for (i, rgba) in self.normal.iter().chain(&self.bright).enumerate() {
+            let _ = writeln!(conf, "color{i} {}", color::to_hex(rgba));
+        }
+
+        conf
+    }
+
+    /// The `[colors]` section of `foot.ini`, which writes colors without `#`.
+    pub fn to_foot(&self) -> String {
+        let hex = |rgba: &gdk::RGBA| color::to_hex(rgba)[1..].to_owned();
+        let mut ini = format!(
+            "[colors]\nbackground={}\nforeground={}\n",
+            hex(&self.background),
+            hex(&self.foreground)
+        );
+
+        for (prefix, colors) in [("regular", &self.normal), ("bright", &self.bright)] {
+            for (i, rgba) in colors.iter().enumerate() {
+                let _ = writeln!(ini, "{prefix}{i}={}", hex(rgba));
+            }
+        }
+
+        ini
+    }
+}
@@ -0,0 +1,137 @@
+/* thumbnail.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::path::{Path, PathBuf};
+
+use gtk::{gdk, gio, glib};
+
+use crate::document::Document;
+use crate::gradient::Gradient;
+use crate::png;
+use crate::wallpaper;
+
+/// Size of thumbnails, in logical pixels.
+pub const SIZE: i32 = 32;
+
+/// Option that runs Vibrant as the thumbnailer of saved gradients, as
+/// called from `com.felipekinoshita.Vibrant.thumbnailer`.
+pub const OPTION: &str = "--thumbnail";
+
+fn cache_dir() -> PathBuf {
+    glib::user_cache_dir().join("vibrant").join("thumbnails")
+}
+
+/// Names the thumbnail after a hash of the gradient as it is saved, so
+/// edited documents get a new one and identical gradients share theirs.
+fn path(gradient: &Gradient, scale: i32) -> PathBuf {
+    let document = Document {
+        gradient: gradient.clone(),
+        ..Default::default()
+    };
+    let hash =
+        glib::compute_checksum_for_data(glib::ChecksumType::Sha256, document.to_json().as_bytes())
+            .unwrap_or_default();
+
+    cache_dir().join(format!("{hash}-{SIZE}@{scale}.png"))
+}
+
+/// A square thumbnail of the gradient with its adjustments applied, read
+/// from the disk cache when it was rendered before.
+pub fn load(gradient: &Gradient, scale: i32) -> Option<gdk::Texture> {
+    let path = path(gradient, scale);
+    if let Ok(texture) = gdk::Texture::from_filename(&path) {
+        return Some(texture);
+    }
+
+    let bounds = gdk::Rectangle::new(0, 0, SIZE, SIZE);
+    let texture = wallpaper::render_layout(&gradient.adjusted(), &[bounds], scale, true)?;
+
+    //a missing cache only makes the next load slower
+    let saved = std::fs::create_dir_all(cache_dir())
+        .map_err(|err| err.to_string())
+        .and_then(|_| png::save_srgb(&texture, &path).map_err(|err| err.to_string()));
+    if let Err(err) = saved {
+        glib::g_warning!("vibrant", "Could not cache thumbnail: {}", err);
+    }
+
+    Some(texture)
+}
+
+/// Writes a `size` pixels wide PNG preview of the gradient saved at
+/// `input`, for file managers.
+pub fn write(input: &Path, output: &Path, size: i32) -> Result<(), glib::Error> {
+    let text = std::fs::read_to_string(input)
+        .map_err(|err| glib::Error::new(gio::IOErrorEnum::Failed, &err.to_string()))?;
+    let document = Document::from_json(&text)?;
+
+    let bounds = gdk::Rectangle::new(0, 0, size.max(1), size.max(1));
+    let texture = wallpaper::render_layout(&document.gradient.adjusted(), &[bounds], 1, true)
+        .ok_or_else(|| glib::Error::new(gio::IOErrorEnum::Failed, "Nothing to draw"))?;
+    png::save_srgb(&texture, output)
+}
+
+/// Runs the thumbnailer with the input path, output path and size it was
+/// given.
+pub fn run(args: &[String]) -> glib::ExitCode {
+    let [input, output, size] = args else {
+        eprintln!("Usage: vibrant {OPTION} INPUT OUTPUT SIZE");
+        return glib::ExitCode::FAILURE;
+    };
+    let Ok(size) = size.parse() else {
+        eprintln!("Invalid thumbnail size: {size}");
+        return glib::ExitCode::FAILURE;
+    };
+
+    match write(Path::new(input), Path::new(output), size) {
+        Ok(()) => glib::ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Could not write thumbnail: {err}");
+            glib::ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gtk::prelude::*;
+
+    use super::*;
+    use crate::golden;
+
+    #[test]
+    fn file_manager_thumbnail() {
+        let dir = std::env::temp_dir().join(format!("vibrant-thumbnail-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (input, output) = (dir.join("ramp.gradient"), dir.join("ramp.png"));
+
+        let document = Document {
+            gradient: golden::ramp(),
+            ..Default::default()
+        };
+        std::fs::write(&input, document.to_json()).unwrap();
+        write(&input, &output, 48).unwrap();
+
+        let texture = gdk::Texture::from_filename(&output).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!((texture.width(), texture.height()), (48, 48));
+
+        assert!(write(&dir.join("missing.gradient"), &output, 48).is_err());
+    }
+}
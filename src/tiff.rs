@@ -0,0 +1,161 @@
+/* tiff.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gtk::{glib, graphene};
+
+use crate::gradient::Gradient;
+use crate::icc::ColorSpace;
+use crate::job::Progress;
+
+/// Bytes per pixel of 16 bit RGBA.
+const PIXEL_SIZE: usize = 8;
+
+/// Types of TIFF fields.
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+const UNDEFINED: u16 = 7;
+
+/// The gradient alone, without its layers, sampled at 16 bits per channel
+/// so ramps and mattes don't band in compositing software. Slow enough to
+/// run off the main thread, as a job.
+pub fn samples(
+    gradient: &Gradient,
+    width: u32,
+    height: u32,
+    progress: &Progress,
+) -> Result<Vec<u8>, glib::Error> {
+    let bounds = graphene::Rect::new(0.0, 0.0, width as f32, height as f32);
+    let mut samples = Vec::with_capacity(width as usize * height as usize * PIXEL_SIZE);
+
+    for y in 0..height {
+        progress.check()?;
+        progress.set(y as f64 / height as f64);
+
+        for x in 0..width {
+            //sample the middle of each pixel
+            let point = graphene::Point::new(x as f32 + 0.5, y as f32 + 0.5);
+            let color = gradient.color_at_point(&point, &bounds);
+            for channel in [color.red(), color.green(), color.blue(), color.alpha()] {
+                let value = (channel.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16;
+                samples.extend_from_slice(&value.to_ne_bytes());
+            }
+        }
+    }
+
+    Ok(samples)
+}
+
+/// Encodes what `samples` returned as an uncompressed 16 bit TIFF, tagged
+/// with the sRGB profile. Written by hand, as GDK can't embed a profile.
+/// Samples stay in the byte order of the machine, which the header names.
+pub fn encode(samples: Vec<u8>, width: u32, height: u32) -> glib::Bytes {
+    let profile = ColorSpace::Srgb.profile();
+    let bits_per_sample: Vec<u8> = [16u16; 4]
+        .iter()
+        .flat_map(|bits| bits.to_ne_bytes())
+        .collect();
+
+    //the directory comes first, then the values too long to fit in it
+    let entries = 12;
+    let directory_size = 2 + entries * 12 + 4;
+    let bits_offset = 8 + directory_size;
+    let profile_offset = bits_offset + bits_per_sample.len();
+    let samples_offset = (profile_offset + profile.len()).next_multiple_of(2);
+
+    let fields: [(u16, u16, u32, u32); 12] = [
+        (256, LONG, 1, width),
+        (257, LONG, 1, height),
+        (258, SHORT, 4, bits_offset as u32),
+        //no compression
+        (259, SHORT, 1, 1),
+        //RGB
+        (262, SHORT, 1, 2),
+        (273, LONG, 1, samples_offset as u32),
+        (277, SHORT, 1, 4),
+        (278, LONG, 1, height),
+        (279, LONG, 1, samples.len() as u32),
+        //channels interleaved
+        (284, SHORT, 1, 1),
+        //unassociated alpha
+        (338, SHORT, 1, 2),
+        (
+            34675,
+            UNDEFINED,
+            profile.len() as u32,
+            profile_offset as u32,
+        ),
+    ];
+
+    let mut tiff = Vec::with_capacity(samples_offset + samples.len());
+    tiff.extend(if cfg!(target_endian = "little") {
+        b"II"
+    } else {
+        b"MM"
+    });
+    tiff.extend(42u16.to_ne_bytes());
+    tiff.extend(8u32.to_ne_bytes());
+
+    tiff.extend((entries as u16).to_ne_bytes());
+    for (tag, kind, count, value) in fields {
+        tiff.extend(tag.to_ne_bytes());
+        tiff.extend(kind.to_ne_bytes());
+        tiff.extend(count.to_ne_bytes());
+        //single short values sit in the first half of the field
+        if kind == SHORT && count == 1 {
+            tiff.extend((value as u16).to_ne_bytes());
+            tiff.extend([0; 2]);
+        } else {
+            tiff.extend(value.to_ne_bytes());
+        }
+    }
+    //no further directories
+    tiff.extend(0u32.to_ne_bytes());
+
+    tiff.extend(bits_per_sample);
+    tiff.extend(profile);
+    tiff.resize(samples_offset, 0);
+    tiff.extend(samples);
+
+    glib::Bytes::from_owned(tiff)
+}
+
+#[cfg(test)]
+mod tests {
+    use gtk::gdk;
+
+    use super::*;
+    use crate::golden;
+
+    #[test]
+    fn exported_ramp() {
+        let samples = samples(&golden::ramp(), 64, 16, &Progress::default()).unwrap();
+        let tiff = encode(samples, 64, 16);
+
+        //the profile is the last field of the only directory
+        let profile = ColorSpace::Srgb.profile();
+        let field = 8 + 2 + 11 * 12;
+        assert_eq!(tiff[field..field + 2], 34675u16.to_ne_bytes());
+        let offset = u32::from_ne_bytes(tiff[field + 8..field + 12].try_into().unwrap()) as usize;
+        assert_eq!(tiff[offset..offset + profile.len()], profile);
+
+        let decoded = gdk::Texture::from_bytes(&tiff).unwrap();
+        golden::assert_matches(&decoded, "tiff-ramp", 2.0 / u16::MAX as f32);
+    }
+}
@@ -0,0 +1,87 @@
+/* tokens.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use crate::color;
+use crate::gradient::Gradient;
+use crate::json::Value;
+use crate::snippet;
+
+/// Key of the extension holding what design tokens can't describe.
+const EXTENSION: &str = "com.felipekinoshita.Vibrant";
+
+/// Writes named gradients, with their adjustments applied, as design tokens
+/// in the W3C Design Tokens format. The CSS of each gradient goes along in
+/// an extension, as tokens only describe a list of stops.
+pub fn design_tokens(gradients: &[(String, Gradient)]) -> String {
+    let mut names: Vec<String> = Vec::new();
+    let tokens = gradients
+        .iter()
+        .map(|(name, gradient)| {
+            let stops = snippet::colormap_stops(gradient)
+                .iter()
+                .map(|(position, rgba)| {
+                    Value::object([
+                        ("color", color::to_hex(rgba).into()),
+                        ("position", (*position).into()),
+                    ])
+                })
+                .collect::<Vec<_>>();
+
+            let token = Value::object([
+                ("$type", "gradient".into()),
+                ("$value", stops.into()),
+                (
+                    "$extensions",
+                    Value::object([(
+                        EXTENSION,
+                        Value::object([("css", gradient.to_css().into())]),
+                    )]),
+                ),
+            ]);
+            //names must be unique within the group
+            let base = token_name(name);
+            let mut name = base.clone();
+            let mut count = 1;
+            while names.contains(&name) {
+                count += 1;
+                name = format!("{base}-{count}");
+            }
+            names.push(name.clone());
+
+            (name, token)
+        })
+        .collect();
+
+    Value::object([("gradient", Value::Object(tokens))]).to_json()
+}
+
+/// Token names can't contain braces or periods, which refer to other
+/// tokens, nor start with `$`.
+fn token_name(name: &str) -> String {
+    let name = name
+        .trim()
+        .trim_start_matches('$')
+        .replace(['{', '}', '.'], "-");
+    if name.is_empty() {
+        "gradient".to_owned()
+    } else {
+        name
+    }
+}
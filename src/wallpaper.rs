@@ -0,0 +1,400 @@
+/* wallpaper.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use gtk::prelude::*;
+use gtk::{gdk, gio, glib, graphene, gsk};
+
+use crate::color;
+use crate::gradient::{ColorStop, Gradient, GradientType};
+use crate::job::Progress;
+use crate::png;
+
+const BACKGROUND_SCHEMA: &str = "org.gnome.desktop.background";
+
+/// Geometry of every connected monitor in the display layout, in logical
+/// pixels, along with the highest scale factor among them.
+pub fn monitor_layout(display: &gdk::Display) -> (Vec<gdk::Rectangle>, i32) {
+    let monitors = display.monitors();
+    let mut geometries = Vec::new();
+    let mut scale = 1;
+
+    for i in 0..monitors.n_items() {
+        if let Some(monitor) = monitors.item(i).and_downcast::<gdk::Monitor>() {
+            geometries.push(monitor.geometry());
+            scale = scale.max(monitor.scale_factor());
+        }
+    }
+
+    (geometries, scale)
+}
+
+/// Renders one image covering the whole monitor layout. When `span` is set
+/// the gradient continues across screens, otherwise every monitor gets its
+/// own copy of the gradient.
+pub fn render_layout(
+    gradient: &Gradient,
+    monitors: &[gdk::Rectangle],
+    scale: i32,
+    span: bool,
+) -> Option<gdk::Texture> {
+    let left = monitors.iter().map(|m| m.x()).min()?;
+    let top = monitors.iter().map(|m| m.y()).min()?;
+    let right = monitors.iter().map(|m| m.x() + m.width()).max()?;
+    let bottom = monitors.iter().map(|m| m.y() + m.height()).max()?;
+
+    let nodes: Vec<_> = if span {
+        let bounds = graphene::Rect::new(0.0, 0.0, (right - left) as f32, (bottom - top) as f32);
+        gradient.node(&bounds).into_iter().collect()
+    } else {
+        monitors
+            .iter()
+            .filter_map(|monitor| {
+                gradient.node(&graphene::Rect::new(
+                    (monitor.x() - left) as f32,
+                    (monitor.y() - top) as f32,
+                    monitor.width() as f32,
+                    monitor.height() as f32,
+                ))
+            })
+            .collect()
+    };
+
+    //built as nodes rather than with a snapshot, so this also runs on worker threads
+    let transform = gsk::Transform::new().scale(scale as f32, scale as f32);
+    let node = gsk::TransformNode::new(gsk::ContainerNode::new(&nodes), &transform);
+    let viewport = graphene::Rect::new(
+        0.0,
+        0.0,
+        ((right - left) * scale) as f32,
+        ((bottom - top) * scale) as f32,
+    );
+
+    let renderer = gsk::CairoRenderer::new();
+    renderer.realize(None::<&gdk::Surface>).ok()?;
+    let texture = renderer.render_texture(&node, Some(&viewport));
+    renderer.unrealize();
+
+    Some(texture)
+}
+
+fn wallpaper_dir() -> PathBuf {
+    glib::user_data_dir().join("vibrant").join("wallpapers")
+}
+
+fn failed(message: impl ToString) -> glib::Error {
+    glib::Error::new(gio::IOErrorEnum::Failed, &message.to_string())
+}
+
+/// Saves the texture under a new name, so the desktop notices the change,
+/// and removes wallpapers written previously.
+fn save(texture: &gdk::Texture) -> Result<PathBuf, glib::Error> {
+    let dir = wallpaper_dir();
+    std::fs::create_dir_all(&dir).map_err(failed)?;
+
+    if let Ok(entries) = std::fs::read_dir(&dir) {
+        for entry in entries.flatten() {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+
+    let path = dir.join(format!("wallpaper-{}.png", glib::real_time()));
+    png::save_srgb(texture, &path)?;
+
+    Ok(path)
+}
+
+fn background_settings() -> Option<gio::Settings> {
+    //inside the sandbox the schema may exist but writes never reach the host
+    if std::path::Path::new("/.flatpak-info").exists() {
+        return None;
+    }
+
+    gio::SettingsSchemaSource::default()?.lookup(BACKGROUND_SCHEMA, true)?;
+    Some(gio::Settings::new(BACKGROUND_SCHEMA))
+}
+
+/// The closest the GNOME background colors get to a gradient: its end
+/// colors blended along the nearest axis.
+pub struct Shading {
+    pub primary: gdk::RGBA,
+    pub secondary: gdk::RGBA,
+    /// The value of `color-shading-type`.
+    pub kind: &'static str,
+}
+
+impl Shading {
+    pub fn new(gradient: &Gradient) -> Self {
+        //the background has no alpha
+        let opaque = |stop: &ColorStop| {
+            gdk::RGBA::new(stop.color.red(), stop.color.green(), stop.color.blue(), 1.0)
+        };
+        let first = gradient
+            .stops
+            .first()
+            .map(opaque)
+            .unwrap_or(gdk::RGBA::BLACK);
+        let last = gradient.stops.last().map(opaque).unwrap_or(first);
+
+        if first == last {
+            return Self {
+                primary: first,
+                secondary: last,
+                kind: "solid",
+            };
+        }
+
+        //the primary color is at the top or on the left
+        let quarter = if gradient.kind == GradientType::Linear {
+            (gradient.angle.rem_euclid(360.0) / 90.0).round() as u32 % 4
+        } else {
+            2
+        };
+        let (primary, secondary, kind) = match quarter {
+            0 => (last, first, "vertical"),
+            1 => (first, last, "horizontal"),
+            3 => (last, first, "horizontal"),
+            _ => (first, last, "vertical"),
+        };
+
+        Self {
+            primary,
+            secondary,
+            kind,
+        }
+    }
+
+    /// `gsettings` commands setting the colors as the desktop background.
+    pub fn to_commands(&self) -> String {
+        format!(
+            "gsettings set {BACKGROUND_SCHEMA} picture-options 'none'\n\
+             gsettings set {BACKGROUND_SCHEMA} primary-color '{}'\n\
+             gsettings set {BACKGROUND_SCHEMA} secondary-color '{}'\n\
+             gsettings set {BACKGROUND_SCHEMA} color-shading-type '{}'\n",
+            color::to_hex(&self.primary),
+            color::to_hex(&self.secondary),
+            self.kind
+        )
+    }
+}
+
+/// Sets the end colors of the gradient as the desktop background, without
+/// a picture. Only works outside the sandbox, as there is no portal for it.
+pub fn set_background_colors(gradient: &Gradient) -> Result<(), glib::Error> {
+    let settings =
+        background_settings().ok_or_else(|| failed("The background settings are not available"))?;
+    let shading = Shading::new(gradient);
+
+    settings
+        .set_string("picture-options", "none")
+        .map_err(failed)?;
+    settings
+        .set_string("primary-color", &color::to_hex(&shading.primary))
+        .map_err(failed)?;
+    settings
+        .set_string("secondary-color", &color::to_hex(&shading.secondary))
+        .map_err(failed)?;
+    settings
+        .set_string("color-shading-type", shading.kind)
+        .map_err(failed)?;
+
+    Ok(())
+}
+
+/// Renders the gradient for the current monitor layout and sets it as the
+/// desktop background, through GSettings when running on the host or the
+/// wallpaper portal otherwise.
+pub async fn set_wallpaper(
+    display: &gdk::Display,
+    gradient: &Gradient,
+    span: bool,
+) -> Result<(), glib::Error> {
+    let (monitors, scale) = monitor_layout(display);
+    let texture = render_layout(gradient, &monitors, scale, span)
+        .ok_or_else(|| failed("Could not render the wallpaper"))?;
+
+    //encoding large layouts takes a while
+    let path = gio::spawn_blocking(move || save(&texture))
+        .await
+        .map_err(|_| failed("Could not save the wallpaper"))??;
+    let uri = gio::File::for_path(&path).uri();
+
+    if let Some(settings) = background_settings() {
+        let options = if monitors.len() > 1 {
+            "spanned"
+        } else {
+            "zoom"
+        };
+        settings
+            .set_string("picture-options", options)
+            .map_err(failed)?;
+        settings.set_string("picture-uri", &uri).map_err(failed)?;
+        if settings
+            .settings_schema()
+            .is_some_and(|schema| schema.has_key("picture-uri-dark"))
+        {
+            settings
+                .set_string("picture-uri-dark", &uri)
+                .map_err(failed)?;
+        }
+
+        return Ok(());
+    }
+
+    let options: HashMap<&str, glib::Variant> = HashMap::from([
+        ("show-preview", false.to_variant()),
+        ("set-on", "both".to_variant()),
+    ]);
+
+    let connection = gio::bus_get_future(gio::BusType::Session).await?;
+    connection
+        .call_future(
+            Some("org.freedesktop.portal.Desktop"),
+            "/org/freedesktop/portal/desktop",
+            "org.freedesktop.portal.Wallpaper",
+            "SetWallpaperURI",
+            Some(&("", uri.as_str(), options).to_variant()),
+            None,
+            gio::DBusCallFlags::NONE,
+            -1,
+        )
+        .await?;
+
+    Ok(())
+}
+
+/// Data directory of the host, which GNOME Settings reads backgrounds from
+/// even when running sandboxed.
+fn host_data_dir() -> PathBuf {
+    if std::path::Path::new("/.flatpak-info").exists() {
+        glib::home_dir().join(".local").join("share")
+    } else {
+        glib::user_data_dir()
+    }
+}
+
+/// Hour of the day the slideshow starts at, with its first slide.
+const SLIDESHOW_START_HOUR: u32 = 5;
+
+/// How long each slide fades into the next one, in seconds.
+const SLIDESHOW_TRANSITION: u32 = 3600;
+
+/// Seconds in the day a slideshow loops over.
+const DAY: u32 = 24 * 3600;
+
+/// Seconds each slide stays still before fading into the next one, from
+/// the hours they last. Every slide lasts at least as long as its fade and
+/// together they make up a day, or the slideshow would drift.
+fn still_durations(hours: &[u32]) -> Result<Vec<u32>, glib::Error> {
+    let mut total = 0u32;
+    let mut durations = Vec::with_capacity(hours.len());
+    for hours in hours {
+        let seconds = hours
+            .checked_mul(3600)
+            .filter(|seconds| *seconds >= SLIDESHOW_TRANSITION)
+            .ok_or_else(|| failed("Slides must last between one hour and a day"))?;
+        total = total.saturating_add(seconds);
+        durations.push(seconds - SLIDESHOW_TRANSITION);
+    }
+
+    if total != DAY {
+        return Err(failed("Slides must add up to a day"));
+    }
+    Ok(durations)
+}
+
+/// Renders every slide and installs them as a GNOME background slideshow,
+/// listed in Settings. Each slide is shown for its number of hours, the last
+/// of them fading into the next slide, and the hours must add up to a day.
+/// Runs as a job, rendering a slide at a time.
+pub fn install_slideshow(
+    monitors: &[gdk::Rectangle],
+    scale: i32,
+    slides: &[(Gradient, u32)],
+    span: bool,
+    progress: &Progress,
+) -> Result<PathBuf, glib::Error> {
+    let hours: Vec<u32> = slides.iter().map(|(_, hours)| *hours).collect();
+    let durations = still_durations(&hours)?;
+
+    let dir = host_data_dir().join("backgrounds").join("vibrant");
+    std::fs::create_dir_all(&dir).map_err(failed)?;
+
+    let mut files = Vec::new();
+    for (i, (gradient, _)) in slides.iter().enumerate() {
+        progress.check()?;
+        progress.set(i as f64 / slides.len() as f64);
+
+        let texture = render_layout(gradient, monitors, scale, span)
+            .ok_or_else(|| failed("Could not render the slideshow"))?;
+        let path = dir.join(format!("day-{}.png", i + 1));
+        png::save_srgb(&texture, &path)?;
+        files.push(path);
+    }
+
+    let mut xml = format!(
+        "<background>\n  <starttime>\n    <year>2023</year>\n    <month>1</month>\n    <day>1</day>\n    <hour>{SLIDESHOW_START_HOUR}</hour>\n    <minute>0</minute>\n    <second>0</second>\n  </starttime>\n"
+    );
+    for (i, shown) in durations.iter().enumerate() {
+        let file = glib::markup_escape_text(&files[i].to_string_lossy());
+        let next = glib::markup_escape_text(&files[(i + 1) % files.len()].to_string_lossy());
+
+        xml.push_str(&format!(
+            "  <static>\n    <duration>{shown}.0</duration>\n    <file>{file}</file>\n  </static>\n  <transition type=\"overlay\">\n    <duration>{SLIDESHOW_TRANSITION}.0</duration>\n    <from>{file}</from>\n    <to>{next}</to>\n  </transition>\n"
+        ));
+    }
+    xml.push_str("</background>\n");
+
+    let slideshow = dir.join("day.xml");
+    std::fs::write(&slideshow, xml).map_err(failed)?;
+
+    let properties_dir = host_data_dir().join("gnome-background-properties");
+    std::fs::create_dir_all(&properties_dir).map_err(failed)?;
+    let properties = format!(
+        "<?xml version=\"1.0\"?>\n<!DOCTYPE wallpapers SYSTEM \"gnome-wp-list.dtd\">\n<wallpapers>\n  <wallpaper deleted=\"false\">\n    <name>Vibrant Day</name>\n    <filename>{}</filename>\n    <options>{}</options>\n  </wallpaper>\n</wallpapers>\n",
+        glib::markup_escape_text(&slideshow.to_string_lossy()),
+        if monitors.len() > 1 && span { "spanned" } else { "zoom" },
+    );
+    std::fs::write(properties_dir.join("vibrant-day.xml"), properties).map_err(failed)?;
+
+    Ok(slideshow)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slide_durations() {
+        assert_eq!(
+            still_durations(&[4, 8, 4, 8]).unwrap(),
+            [3 * 3600, 7 * 3600, 3 * 3600, 7 * 3600]
+        );
+        assert_eq!(still_durations(&[24]).unwrap(), [23 * 3600]);
+
+        assert!(still_durations(&[]).is_err());
+        assert!(still_durations(&[4, 8, 4]).is_err());
+        assert!(still_durations(&[0, 24]).is_err());
+        assert!(still_durations(&[u32::MAX, 1]).is_err());
+        assert!(still_durations(&[1_193_047, 1_193_047, 24]).is_err());
+    }
+}
@@ -18,33 +18,133 @@
  * SPDX-License-Identifier: GPL-3.0-or-later
  */
 
-use gettextrs::gettext;
+use std::cell::{Cell, OnceCell, Ref, RefCell};
+
+use gettextrs::{gettext, ngettext, pgettext};
 use glib::clone;
 
 use gtk::prelude::*;
-use gtk::{gio, glib};
+use gtk::{gdk, gio, glib, graphene};
 
 use adw::prelude::*;
 use adw::subclass::prelude::*;
 
+use crate::accent;
+use crate::autosave;
+use crate::code_style::CodeStyle;
+use crate::color::{self, GamutMapping};
+use crate::color_format::{self, ColorFormat};
+use crate::community;
+use crate::compass::VibrantCompass;
 use crate::config::PROFILE;
+use crate::document::{self, Collection, Document, Member, Metadata};
+use crate::gradient::{self, Adjustments, ColorStop, CssOptions, Gradient, GradientType, Vignette};
+use crate::grain_layer::VibrantGrainLayer;
+use crate::handle_layer::VibrantHandleLayer;
+use crate::handles::{Handle, Zoom};
+use crate::icc::ColorSpace;
+use crate::job;
+use crate::lightness_plot::VibrantLightnessPlot;
+use crate::palette::{self, Palette};
+use crate::pattern::PatternKind;
+use crate::png;
+use crate::precision::{self, Nudge, Precision};
+use crate::preferences::{self, VibrantPreferencesWindow};
+use crate::presets::{self, Falloff, TimeOfDay, Variation};
+use crate::print;
+use crate::readability;
+use crate::screenshot;
+use crate::snippet::{self, Snippet};
+use crate::stats;
+use crate::swatch::VibrantSwatch;
+use crate::sync;
+use crate::tab::{Session, VibrantTab};
+use crate::thumbnail;
+use crate::wallpaper;
 
-#[derive(Debug, Copy, Clone)]
-enum GradientType {
-    Linear,
-    Radial,
-    Conic,
-}
+mod export;
+mod gestures;
+mod saved;
+mod statistics;
 
-impl From<u32> for GradientType {
-    fn from(value: u32) -> Self {
-        match value {
-            1 => GradientType::Radial,
-            2 => GradientType::Conic,
-            //default to Linear, including 0
-            _ => GradientType::Linear,
+const MAX_RECENT_COLORS: usize = 12;
+const MAX_COLLECTED_COLORS: usize = 24;
+/// Longest side of the image embedded by Copy as Data URI, in pixels.
+const DATA_URI_SIZE: f64 = 64.0;
+/// How far Alt+arrows move a stop, ten times as far with Alt+Page Up and
+/// Alt+Page Down.
+const NUDGE_STEP: f32 = 0.01;
+/// Size of the previews of suggested variations, in logical pixels.
+const VARIATION_WIDTH: i32 = 96;
+const VARIATION_HEIGHT: i32 = 64;
+/// Number of stops approximating a screenshot.
+const SCREENSHOT_STOPS: usize = 5;
+
+/// Puts a gradient into words for screen readers: its type and direction,
+/// then each color by its closest CSS name and its position.
+fn describe_gradient(gradient: &Gradient) -> String {
+    let angle = format!("{}", gradient.angle.round());
+    let kind = match gradient.kind {
+        GradientType::Linear => gettext("Linear gradient at {} degrees").replace("{}", &angle),
+        GradientType::Radial => gettext("Radial gradient"),
+        GradientType::Conic => {
+            gettext("Conic gradient starting at {} degrees").replace("{}", &angle)
         }
-    }
+    };
+
+    let colors = gradient
+        .stops
+        .iter()
+        .zip(gradient.positions())
+        .map(|(stop, position)| {
+            let name = color::nearest_name(&stop.color);
+            let name = if stop.color.alpha() < 1.0 {
+                gettext("translucent {}").replace("{}", name)
+            } else {
+                name.to_owned()
+            };
+            gettext("{color} at {position}")
+                .replace("{color}", &name)
+                .replace("{position}", &gradient.format_position(position))
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let description = if gradient.hard_stops {
+        gettext("{kind} in hard bands of {colors}")
+    } else {
+        gettext("{kind} of {colors}")
+    };
+    description
+        .replace("{kind}", &kind)
+        .replace("{colors}", &colors)
+}
+
+/// Pops up a context menu at a point of `widget`, removing it once closed.
+fn popup_menu(widget: &impl IsA<gtk::Widget>, menu: &gio::Menu, x: f64, y: f64) {
+    let popover = gtk::PopoverMenu::from_model(Some(menu));
+    popover.set_parent(widget);
+    popover.set_has_arrow(false);
+    popover.set_halign(gtk::Align::Start);
+    popover.set_pointing_to(Some(&gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+
+    //the chosen item activates its action before the menu closes
+    popover.connect_closed(|popover| {
+        glib::idle_add_local_once(clone!(@weak popover => move || popover.unparent()));
+    });
+    popover.popup();
+}
+
+/// Continuous edits, where consecutive changes share one undo step.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Edit {
+    Angle,
+    StopColor(usize),
+    StopPosition(usize),
+    Adjustments,
+    Pattern,
+    Vignette,
+    Grain,
 }
 
 mod imp {
@@ -53,26 +153,208 @@ mod imp {
     #[derive(Debug, Default, gtk::CompositeTemplate)]
     #[template(resource = "/com/felipekinoshita/Vibrant/window.ui")]
     pub struct VibrantWindow {
+        pub settings: OnceCell<gio::Settings>,
+        pub gradient: RefCell<Gradient>,
+        pub selected_stop: Cell<usize>,
+        /// Whether the controls edit the dark variant.
+        pub editing_dark: Cell<bool>,
+        pub print_preview: Cell<bool>,
+        pub stop_rows: RefCell<Vec<adw::EntryRow>>,
+        pub stop_position_entries: RefCell<Vec<gtk::Entry>>,
+        /// Blocks widget signals while they are updated from the model.
+        pub syncing: Cell<bool>,
+
+        /// The open document, unset until it is first saved.
+        pub file: RefCell<Option<gio::File>>,
+        pub metadata: RefCell<Metadata>,
+        /// The gradient as last opened or saved, telling unsaved changes apart.
+        pub saved_gradient: RefCell<Gradient>,
+        /// The other gradients of the document, and the collection as last
+        /// opened or saved. The member edited in `gradient` is stale here.
+        pub collection: RefCell<Collection>,
+        pub saved_collection: RefCell<Collection>,
+        pub collection_rows: RefCell<Vec<adw::EntryRow>>,
+        /// Set once unsaved changes were dealt with, to let the window close.
+        pub closing: Cell<bool>,
+        /// The tab whose document the fields above hold.
+        pub current_tab: RefCell<Option<VibrantTab>>,
+        /// Whether the document left the start page.
+        pub started: Cell<bool>,
+
+        /// Styles both previews, reloaded at most once per frame.
+        pub preview_provider: gtk::CssProvider,
+        pub preview_css: RefCell<String>,
+        pub preview_queued: Cell<bool>,
+
+        pub undo_stack: RefCell<Vec<Gradient>>,
+        pub redo_stack: RefCell<Vec<Gradient>>,
+        pub last_edit: Cell<Option<Edit>>,
+
+        pub palettes: RefCell<Vec<Palette>>,
+        pub palette_rows: RefCell<Vec<adw::ExpanderRow>>,
+        /// Shown while colors are being snapped to the brand palette, so a
+        /// drag doesn't pile up toasts.
+        pub brand_toast: RefCell<Option<adw::Toast>>,
+        pub recent_document_rows: RefCell<Vec<adw::ActionRow>>,
+        /// The check buttons of the saved gradients while selecting them.
+        pub saved_checks: RefCell<Vec<(gio::File, gtk::CheckButton)>>,
+        #[template_child]
+        pub community_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub community_row: TemplateChild<adw::SwitchRow>,
+        pub community_rows: RefCell<Vec<adw::ActionRow>>,
+
         // Template widgets
         #[template_child]
         pub toast_overlay: TemplateChild<adw::ToastOverlay>,
         #[template_child]
         pub navigation_view: TemplateChild<adw::OverlaySplitView>,
+        #[template_child]
+        pub tab_view: TemplateChild<adw::TabView>,
+        #[template_child]
+        pub content_stack: TemplateChild<gtk::Stack>,
+        #[template_child]
+        pub recent_documents_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub selection_bar: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub selection_label: TemplateChild<gtk::Label>,
+
+        /// The angle of the gesture turning the preview when last seen, and
+        /// the unrounded angle it set.
+        pub drag_angle: Cell<(f64, f64)>,
+        /// Whether the drag on the preview went past the threshold and moves
+        /// its handle.
+        pub drag_turning: Cell<bool>,
+        /// The handle of the preview being dragged.
+        pub dragged_handle: Cell<Option<Handle>>,
+        pub preview_zoom: Cell<Zoom>,
+        /// The zoom when the pinch on the preview began, and the point of
+        /// the preview it began on.
+        pub pinch: Cell<(f64, f64, f64)>,
 
         #[template_child]
         pub gradient_box: TemplateChild<gtk::Box>,
         #[template_child]
         pub gradient_overlay: TemplateChild<gtk::Overlay>,
+        #[template_child]
+        pub handle_layer: TemplateChild<VibrantHandleLayer>,
+        #[template_child]
+        pub chrome_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub paired_gradient_box: TemplateChild<gtk::Box>,
+        #[template_child]
+        pub grain_layer: TemplateChild<VibrantGrainLayer>,
+        #[template_child]
+        pub paired_grain_layer: TemplateChild<VibrantGrainLayer>,
 
         #[template_child]
-        pub direction_combo: TemplateChild<adw::ComboRow>,
+        pub angle_row: TemplateChild<adw::SpinRow>,
+        #[template_child]
+        pub angle_compass: TemplateChild<VibrantCompass>,
         #[template_child]
         pub gradient_combo: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub hard_stops_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub symmetric_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub tint_transparent_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub dark_variant_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub dark_variant_button: TemplateChild<gtk::ToggleButton>,
+        #[template_child]
+        pub light_variant_button: TemplateChild<gtk::ToggleButton>,
+
+        #[template_child]
+        pub colors_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub color_search_popover: TemplateChild<gtk::Popover>,
+        #[template_child]
+        pub color_search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
+        pub color_search_list: TemplateChild<gtk::ListBox>,
+
+        #[template_child]
+        pub lightness_scale: TemplateChild<gtk::Scale>,
+        #[template_child]
+        pub chroma_scale: TemplateChild<gtk::Scale>,
+        #[template_child]
+        pub hue_scale: TemplateChild<gtk::Scale>,
+        #[template_child]
+        pub temperature_scale: TemplateChild<gtk::Scale>,
+        #[template_child]
+        pub gamut_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub gamut_warning: TemplateChild<gtk::Image>,
+
+        #[template_child]
+        pub pattern_combo: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub pattern_color_combo: TemplateChild<adw::ComboRow>,
+        #[template_child]
+        pub pattern_opacity_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub pattern_opacity_scale: TemplateChild<gtk::Scale>,
+        #[template_child]
+        pub pattern_scale_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub pattern_scale_scale: TemplateChild<gtk::Scale>,
+        #[template_child]
+        pub grain_scale: TemplateChild<gtk::Scale>,
+
+        #[template_child]
+        pub vignette_strength_scale: TemplateChild<gtk::Scale>,
+        #[template_child]
+        pub vignette_size_scale: TemplateChild<gtk::Scale>,
+        #[template_child]
+        pub readable_top_row: TemplateChild<adw::SwitchRow>,
+        #[template_child]
+        pub readability_warning: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub average_luminance_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub end_contrast_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub stop_differences_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub lightness_plot: TemplateChild<VibrantLightnessPlot>,
+        #[template_child]
+        pub uniformity_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub lightness_turns_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub muddy_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub banding_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub unprintable_row: TemplateChild<adw::ActionRow>,
+
+        #[template_child]
+        pub collected_colors_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub collected_colors_box: TemplateChild<gtk::FlowBox>,
+        /// Hex codes picked up from the clipboard, oldest first.
+        pub collected_colors: RefCell<Vec<String>>,
+        pub clipboard_handler: RefCell<Option<glib::SignalHandlerId>>,
+
+        #[template_child]
+        pub gradient_colors_box: TemplateChild<gtk::FlowBox>,
+        /// The colors shown in `gradient_colors_box`.
+        pub gradient_colors: RefCell<Vec<String>>,
+        #[template_child]
+        pub recent_colors_group: TemplateChild<adw::PreferencesGroup>,
+        #[template_child]
+        pub recent_colors_box: TemplateChild<gtk::FlowBox>,
+
+        #[template_child]
+        pub gnome_palette_grid: TemplateChild<gtk::Grid>,
 
         #[template_child]
-        pub color_one_entry: TemplateChild<adw::EntryRow>,
+        pub palettes_group: TemplateChild<adw::PreferencesGroup>,
         #[template_child]
-        pub color_two_entry: TemplateChild<adw::EntryRow>,
+        pub collection_group: TemplateChild<adw::PreferencesGroup>,
     }
 
     #[glib::object_subclass]
@@ -82,6 +364,11 @@ mod imp {
         type ParentType = adw::ApplicationWindow;
 
         fn class_init(klass: &mut Self::Class) {
+            VibrantCompass::ensure_type();
+            VibrantLightnessPlot::ensure_type();
+            VibrantGrainLayer::ensure_type();
+            VibrantHandleLayer::ensure_type();
+
             klass.bind_template();
             Self::Type::bind_template_callbacks(klass);
         }
@@ -93,7 +380,22 @@ mod imp {
 
     impl ObjectImpl for VibrantWindow {}
     impl WidgetImpl for VibrantWindow {}
-    impl WindowImpl for VibrantWindow {}
+    impl WindowImpl for VibrantWindow {
+        fn close_request(&self) -> glib::Propagation {
+            let obj = self.obj();
+            if self.closing.get() || !obj.has_unsaved_tabs() {
+                return self.parent_close_request();
+            }
+
+            glib::MainContext::default().spawn_local(clone!(@weak obj => async move {
+                if obj.confirm_close_tabs().await {
+                    obj.imp().closing.set(true);
+                    obj.close();
+                }
+            }));
+            glib::Propagation::Stop
+        }
+    }
     impl ApplicationWindowImpl for VibrantWindow {}
     impl AdwApplicationWindowImpl for VibrantWindow {}
 }
@@ -106,12 +408,22 @@ glib::wrapper! {
 #[gtk::template_callbacks]
 impl VibrantWindow {
     pub fn new<P: glib::IsA<gtk::Application>>(application: &P) -> Self {
+        let win = Self::without_tabs(application);
+        win.add_tab();
+        win
+    }
+
+    /// A window waiting for a tab to be dropped into it.
+    fn without_tabs<P: glib::IsA<gtk::Application>>(application: &P) -> Self {
         let win: VibrantWindow = glib::Object::builder()
             .property("application", application)
             .build();
 
+        win.setup_actions();
         win.init();
         win.setup_signals();
+        win.setup_preview_gestures();
+        win.setup_tabs();
 
         win
     }
@@ -123,9 +435,414 @@ impl VibrantWindow {
             self.add_css_class("devel");
         }
 
-        imp.color_one_entry.set_text("blue");
-        imp.color_two_entry.set_text("pink");
+        imp.editing_dark.set(adw::StyleManager::default().is_dark());
+
+        for preview in [imp.gradient_box.get(), imp.paired_gradient_box.get()] {
+            preview
+                .style_context()
+                .add_provider(&imp.preview_provider, 1000);
+        }
+
+        self.sync_widgets();
+        self.update_compass();
         self.update_gradient();
+        self.update_recent_colors();
+        self.update_collected_colors();
+        self.setup_color_search();
+        self.setup_gnome_palette();
+
+        imp.palettes.replace(palette::load());
+        self.update_palettes();
+        self.update_recent_documents();
+        self.update_community();
+        self.sync_library();
+    }
+
+    fn settings(&self) -> &gio::Settings {
+        self.imp()
+            .settings
+            .get_or_init(|| gio::Settings::new("com.felipekinoshita.Vibrant"))
+    }
+
+    fn setup_actions(&self) {
+        let open_action = gio::ActionEntry::builder("open")
+            .activate(move |win: &Self, _, _| win.open())
+            .build();
+        let new_tab_action = gio::ActionEntry::builder("new-tab")
+            .activate(move |win: &Self, _, _| win.add_tab())
+            .build();
+        let close_tab_action = gio::ActionEntry::builder("close-tab")
+            .activate(move |win: &Self, _, _| {
+                let tab_view = &win.imp().tab_view;
+                if let Some(page) = tab_view.selected_page() {
+                    tab_view.close_page(&page);
+                }
+            })
+            .build();
+        let start_blank_action = gio::ActionEntry::builder("start-blank")
+            .activate(move |win: &Self, _, _| {
+                win.imp().started.set(true);
+                win.update_start_page();
+            })
+            .build();
+        let start_from_image_action = gio::ActionEntry::builder("start-from-image")
+            .activate(move |win: &Self, _, _| win.start_from_image())
+            .build();
+        let paste_colors_action = gio::ActionEntry::builder("paste-colors")
+            .activate(move |win: &Self, _, _| win.paste_colors())
+            .build();
+        let start_from_screenshot_action = gio::ActionEntry::builder("start-from-screenshot")
+            .activate(move |win: &Self, _, _| win.start_from_screenshot())
+            .build();
+        let pick_endpoints_action = gio::ActionEntry::builder("pick-endpoints")
+            .activate(move |win: &Self, _, _| win.pick_endpoints())
+            .build();
+        let save_action = gio::ActionEntry::builder("save")
+            .activate(move |win: &Self, _, _| {
+                glib::MainContext::default().spawn_local(clone!(@weak win => async move {
+                    win.save().await;
+                }));
+            })
+            .build();
+        let save_as_action = gio::ActionEntry::builder("save-as")
+            .activate(move |win: &Self, _, _| {
+                glib::MainContext::default().spawn_local(clone!(@weak win => async move {
+                    win.save_as().await;
+                }));
+            })
+            .build();
+        let set_wallpaper_action = gio::ActionEntry::builder("set-wallpaper")
+            .activate(move |win: &Self, _, _| win.set_wallpaper())
+            .build();
+        let set_background_colors_action = gio::ActionEntry::builder("set-background-colors")
+            .activate(move |win: &Self, _, _| win.set_background_colors())
+            .build();
+        let export_plymouth_action = gio::ActionEntry::builder("export-plymouth")
+            .activate(move |win: &Self, _, _| win.export_plymouth())
+            .build();
+        let copy_color_action = gio::ActionEntry::builder("copy-color")
+            .parameter_type(Some(glib::VariantTy::STRING))
+            .activate(move |win: &Self, _, parameter| {
+                if let Some(text) = parameter.and_then(|parameter| parameter.str()) {
+                    win.copy_color(text);
+                }
+            })
+            .build();
+        let export_palette_action = gio::ActionEntry::builder("export-palette")
+            .activate(move |win: &Self, _, _| win.export_palette())
+            .build();
+        let insert_stop_action = gio::ActionEntry::builder("insert-stop")
+            .parameter_type(Some(glib::VariantTy::DOUBLE))
+            .activate(move |win: &Self, _, parameter| {
+                if let Some(offset) = parameter.and_then(|parameter| parameter.get::<f64>()) {
+                    win.insert_stop(offset as f32);
+                }
+            })
+            .build();
+        let remove_stop_action = gio::ActionEntry::builder("remove-stop")
+            .parameter_type(Some(glib::VariantTy::UINT32))
+            .activate(move |win: &Self, _, parameter| {
+                if let Some(index) = parameter.and_then(|parameter| parameter.get::<u32>()) {
+                    win.remove_stop(index as usize);
+                }
+            })
+            .build();
+        let edit_stop_position_action = gio::ActionEntry::builder("edit-stop-position")
+            .parameter_type(Some(glib::VariantTy::UINT32))
+            .activate(move |win: &Self, _, parameter| {
+                if let Some(index) = parameter.and_then(|parameter| parameter.get::<u32>()) {
+                    win.focus_stop_position(index as usize);
+                }
+            })
+            .build();
+        let undo_action = gio::ActionEntry::builder("undo")
+            .activate(move |win: &Self, _, _| win.undo())
+            .build();
+        let redo_action = gio::ActionEntry::builder("redo")
+            .activate(move |win: &Self, _, _| win.redo())
+            .build();
+        let add_stop_action = gio::ActionEntry::builder("add-stop")
+            .activate(move |win: &Self, _, _| win.add_stop())
+            .build();
+        let swap_stops_action = gio::ActionEntry::builder("swap-stops")
+            .activate(move |win: &Self, _, _| win.modify_gradient(Gradient::swap_ends))
+            .build();
+        let shuffle_stops_action = gio::ActionEntry::builder("shuffle-stops")
+            .activate(move |win: &Self, _, _| win.modify_gradient(Gradient::shuffle))
+            .build();
+        let smooth_stops_action = gio::ActionEntry::builder("smooth-stops")
+            .activate(move |win: &Self, _, _| win.modify_gradient(Gradient::smooth))
+            .build();
+        let fix_muddy_midpoints_action = gio::ActionEntry::builder("fix-muddy-midpoints")
+            .activate(move |win: &Self, _, _| win.modify_gradient(Gradient::fix_muddy_midpoints))
+            .build();
+        let add_dither_grain_action = gio::ActionEntry::builder("add-dither-grain")
+            .activate(move |win: &Self, _, _| {
+                win.modify_gradient(|gradient| {
+                    gradient.grain = gradient.grain.max(stats::DITHER_GRAIN)
+                })
+            })
+            .build();
+        let copy_stop_color_action = gio::ActionEntry::builder("copy-stop-color")
+            .parameter_type(Some(glib::VariantTy::new("(us)").unwrap()))
+            .activate(move |win: &Self, _, parameter| {
+                let Some((index, name)) =
+                    parameter.and_then(|parameter| parameter.get::<(u32, String)>())
+                else {
+                    return;
+                };
+                if let Some(format) = ColorFormat::from_name(&name) {
+                    win.copy_stop_color(index as usize, format);
+                }
+            })
+            .build();
+        let distribute_stops_action = gio::ActionEntry::builder("distribute-stops")
+            .activate(move |win: &Self, _, _| win.modify_gradient(Gradient::distribute))
+            .build();
+        let snap_stops_action = gio::ActionEntry::builder("snap-stops")
+            .activate(move |win: &Self, _, _| win.modify_gradient(Gradient::snap))
+            .build();
+        let mirror_stops_action = gio::ActionEntry::builder("mirror-stops")
+            .activate(move |win: &Self, _, _| win.modify_gradient(Gradient::mirror))
+            .build();
+        let fade_out_action = gio::ActionEntry::builder("fade-out")
+            .activate(move |win: &Self, _, _| win.show_fade_out())
+            .build();
+        let reset_adjustments_action = gio::ActionEntry::builder("reset-adjustments")
+            .activate(move |win: &Self, _, _| {
+                win.modify_gradient(|gradient| gradient.adjustments = Adjustments::default())
+            })
+            .build();
+        let copy_data_uri_action = gio::ActionEntry::builder("copy-data-uri")
+            .activate(move |win: &Self, _, _| win.copy_data_uri())
+            .build();
+        let copy_outlook_html_action = gio::ActionEntry::builder("copy-outlook-html")
+            .activate(move |win: &Self, _, _| win.copy_outlook_html())
+            .build();
+        let export_pdf_action = gio::ActionEntry::builder("export-pdf")
+            .activate(move |win: &Self, _, _| win.export_pdf())
+            .build();
+        let export_tiff_action = gio::ActionEntry::builder("export-tiff")
+            .activate(move |win: &Self, _, _| win.export_tiff())
+            .build();
+        let export_png_action = gio::ActionEntry::builder("export-png")
+            .activate(move |win: &Self, _, _| win.export_png(ColorSpace::Srgb))
+            .build();
+        let export_png_p3_action = gio::ActionEntry::builder("export-png-p3")
+            .activate(move |win: &Self, _, _| win.export_png(ColorSpace::DisplayP3))
+            .build();
+        let set_angle_action = gio::ActionEntry::builder("set-angle")
+            .parameter_type(Some(glib::VariantTy::DOUBLE))
+            .activate(move |win: &Self, _, parameter| {
+                if let Some(angle) = parameter.and_then(|parameter| parameter.get::<f64>()) {
+                    win.modify_gradient(|gradient| gradient.angle = angle as f32);
+                }
+            })
+            .build();
+        let copy_snippet_action = gio::ActionEntry::builder("copy-snippet")
+            .parameter_type(Some(glib::VariantTy::STRING))
+            .activate(move |win: &Self, _, parameter| {
+                let snippet = parameter
+                    .and_then(|parameter| parameter.str())
+                    .and_then(Snippet::from_name);
+                if let Some(snippet) = snippet {
+                    win.copy_snippet(snippet);
+                }
+            })
+            .build();
+        let copy_led_colors_action = gio::ActionEntry::builder("copy-led-colors")
+            .activate(move |win: &Self, _, _| win.copy_led_colors())
+            .build();
+        let export_material_theme_action = gio::ActionEntry::builder("export-material-theme")
+            .activate(move |win: &Self, _, _| win.export_material_theme())
+            .build();
+        let export_design_tokens_action = gio::ActionEntry::builder("export-design-tokens")
+            .activate(move |win: &Self, _, _| win.export_design_tokens())
+            .build();
+        let add_collection_gradient_action = gio::ActionEntry::builder("add-collection-gradient")
+            .activate(move |win: &Self, _, _| win.add_collection_gradient())
+            .build();
+        let export_report_action = gio::ActionEntry::builder("export-report")
+            .activate(move |win: &Self, _, _| win.export_report())
+            .build();
+        let export_svg_action = gio::ActionEntry::builder("export-svg")
+            .activate(move |win: &Self, _, _| win.export_svg())
+            .build();
+        let time_of_day_action = gio::ActionEntry::builder("time-of-day")
+            .parameter_type(Some(glib::VariantTy::STRING))
+            .activate(move |win: &Self, _, parameter| {
+                let name = parameter
+                    .and_then(|parameter| parameter.str())
+                    .unwrap_or_default();
+                let time = TimeOfDay::from_name(name).unwrap_or_else(TimeOfDay::now);
+                win.apply_time_of_day(time);
+            })
+            .build();
+        let install_slideshow_action = gio::ActionEntry::builder("install-slideshow")
+            .activate(move |win: &Self, _, _| win.install_slideshow())
+            .build();
+        let select_saved_action = gio::ActionEntry::builder("select-saved")
+            .state(false.to_variant())
+            .activate(move |win: &Self, action, _| {
+                let selecting = !action
+                    .state()
+                    .and_then(|state| state.get::<bool>())
+                    .unwrap_or_default();
+                win.set_selecting_saved(selecting);
+            })
+            .build();
+        let tag_selected_action = gio::ActionEntry::builder("tag-selected")
+            .activate(move |win: &Self, _, _| win.tag_selected())
+            .build();
+        let export_selected_action = gio::ActionEntry::builder("export-selected")
+            .activate(move |win: &Self, _, _| win.export_selected())
+            .build();
+        let trash_selected_action = gio::ActionEntry::builder("trash-selected")
+            .activate(move |win: &Self, _, _| {
+                win.trash_documents(&win.selected_saved());
+                win.set_selecting_saved(false);
+            })
+            .build();
+        let show_trash_action = gio::ActionEntry::builder("show-trash")
+            .activate(move |win: &Self, _, _| win.show_trash())
+            .build();
+        let remove_duplicates_action = gio::ActionEntry::builder("remove-duplicates")
+            .activate(move |win: &Self, _, _| win.remove_duplicates())
+            .build();
+        let readable_background_action = gio::ActionEntry::builder("readable-background")
+            .activate(move |win: &Self, _, _| win.readable_background())
+            .build();
+        let match_desktop_action = gio::ActionEntry::builder("match-desktop")
+            .activate(move |win: &Self, _, _| win.match_desktop())
+            .build();
+        let preview_chrome_action = gio::ActionEntry::builder("preview-chrome")
+            .parameter_type(Some(glib::VariantTy::STRING))
+            .state("none".to_variant())
+            .activate(move |win: &Self, action, parameter| {
+                let Some(style) = parameter.and_then(|parameter| parameter.str()) else {
+                    return;
+                };
+                action.set_state(&style.to_variant());
+                win.update_chrome(style);
+            })
+            .build();
+        let print_preview_action = gio::ActionEntry::builder("print-preview")
+            .state(false.to_variant())
+            .activate(move |win: &Self, action, _| {
+                let preview = !action
+                    .state()
+                    .and_then(|state| state.get())
+                    .unwrap_or(false);
+                action.set_state(&preview.to_variant());
+                win.imp().print_preview.set(preview);
+                win.queue_preview_update();
+            })
+            .build();
+        let preferences_action = gio::ActionEntry::builder("preferences")
+            .activate(move |win: &Self, _, _| {
+                VibrantPreferencesWindow::new(win, win.imp().gradient.borrow().clone()).present();
+            })
+            .build();
+        let watch_clipboard_action = gio::ActionEntry::builder("watch-clipboard")
+            .state(false.to_variant())
+            .activate(move |win: &Self, action, _| {
+                let watch = !action
+                    .state()
+                    .and_then(|state| state.get())
+                    .unwrap_or(false);
+                action.set_state(&watch.to_variant());
+                win.watch_clipboard(watch);
+            })
+            .build();
+        let use_collected_colors_action = gio::ActionEntry::builder("use-collected-colors")
+            .activate(move |win: &Self, _, _| win.use_collected_colors())
+            .build();
+        let clear_collected_colors_action = gio::ActionEntry::builder("clear-collected-colors")
+            .activate(move |win: &Self, _, _| {
+                win.imp().collected_colors.borrow_mut().clear();
+                win.update_collected_colors();
+            })
+            .build();
+        let suggest_variations_action = gio::ActionEntry::builder("suggest-variations")
+            .activate(move |win: &Self, _, _| win.show_variations())
+            .build();
+        let share_to_gallery_action = gio::ActionEntry::builder("share-to-gallery")
+            .activate(move |win: &Self, _, _| win.share_to_gallery())
+            .build();
+        let pie_chart_action = gio::ActionEntry::builder("pie-chart")
+            .activate(move |win: &Self, _, _| win.show_pie_chart())
+            .build();
+        self.add_action_entries([
+            open_action,
+            new_tab_action,
+            close_tab_action,
+            start_blank_action,
+            start_from_image_action,
+            paste_colors_action,
+            pick_endpoints_action,
+            start_from_screenshot_action,
+            save_action,
+            save_as_action,
+            set_wallpaper_action,
+            set_background_colors_action,
+            export_plymouth_action,
+            copy_color_action,
+            export_palette_action,
+            insert_stop_action,
+            remove_stop_action,
+            edit_stop_position_action,
+            undo_action,
+            redo_action,
+            add_stop_action,
+            swap_stops_action,
+            shuffle_stops_action,
+            smooth_stops_action,
+            fix_muddy_midpoints_action,
+            add_dither_grain_action,
+            copy_stop_color_action,
+            distribute_stops_action,
+            snap_stops_action,
+            mirror_stops_action,
+            fade_out_action,
+            reset_adjustments_action,
+            copy_data_uri_action,
+            copy_outlook_html_action,
+            set_angle_action,
+            copy_snippet_action,
+            copy_led_colors_action,
+            export_svg_action,
+            export_material_theme_action,
+            export_design_tokens_action,
+            add_collection_gradient_action,
+            export_report_action,
+            export_pdf_action,
+            export_tiff_action,
+            export_png_action,
+            export_png_p3_action,
+            time_of_day_action,
+            match_desktop_action,
+            readable_background_action,
+            remove_duplicates_action,
+            show_trash_action,
+            select_saved_action,
+            tag_selected_action,
+            export_selected_action,
+            trash_selected_action,
+            install_slideshow_action,
+            preview_chrome_action,
+            print_preview_action,
+            watch_clipboard_action,
+            use_collected_colors_action,
+            clear_collected_colors_action,
+            suggest_variations_action,
+            share_to_gallery_action,
+            pie_chart_action,
+            preferences_action,
+        ]);
+
+        self.add_action(&self.settings().create_action("wallpaper-span-monitors"));
+        self.add_action(&self.settings().create_action("wide-gamut-css"));
+        self.add_action(&self.settings().create_action("library-sort"));
     }
 
     fn setup_signals(&self) {
@@ -133,30 +850,218 @@ impl VibrantWindow {
 
         imp.gradient_combo.connect_selected_item_notify(
             clone!(@strong self as this => move |combo| {
-                //disable when gradient is radial, as it does effect anything
-                this.imp().direction_combo.set_sensitive(combo.selected() != 1);
-                this.update_gradient();
+                if this.imp().syncing.get() {
+                    return;
+                }
+
+                let kind = GradientType::from(combo.selected());
+                this.modify_gradient(|gradient| gradient.kind = kind);
             }),
         );
 
-        imp.direction_combo.connect_notify_local(
-            Some("selected"),
-            clone!(@strong self as this => move |_combo, _| {
-                this.update_gradient();
+        //follows drags on the preview too, which go through the row
+        imp.angle_row
+            .connect_value_notify(clone!(@weak self as this => move |_| {
+                this.update_compass();
+            }));
+        imp.angle_row
+            .connect_value_notify(clone!(@weak self as this => move |row| {
+                if this.imp().syncing.get() {
+                    return;
+                }
+
+                let angle = row.value() as f32;
+                this.edit_gradient(Edit::Angle, |gradient| gradient.angle = angle);
+            }));
+
+        imp.hard_stops_row
+            .connect_active_notify(clone!(@weak self as this => move |row| {
+                if this.imp().syncing.get() {
+                    return;
+                }
+
+                let hard_stops = row.is_active();
+                this.modify_gradient(|gradient| gradient.hard_stops = hard_stops);
+            }));
+
+        imp.symmetric_row
+            .connect_active_notify(clone!(@weak self as this => move |row| {
+                if this.imp().syncing.get() {
+                    return;
+                }
+
+                let symmetric = row.is_active();
+                this.modify_gradient(|gradient| gradient.symmetric = symmetric);
+            }));
+
+        imp.tint_transparent_row
+            .connect_active_notify(clone!(@weak self as this => move |row| {
+                if this.imp().syncing.get() {
+                    return;
+                }
+
+                let tint = row.is_active();
+                this.modify_gradient(|gradient| gradient.tint_transparent = tint);
+            }));
+
+        for scale in [
+            imp.lightness_scale.get(),
+            imp.chroma_scale.get(),
+            imp.hue_scale.get(),
+            imp.temperature_scale.get(),
+        ] {
+            scale.connect_value_changed(clone!(@weak self as this => move |_| {
+                this.update_from_scales();
+            }));
+        }
+
+        imp.gamut_row
+            .connect_selected_notify(clone!(@weak self as this => move |row| {
+                if this.imp().syncing.get() {
+                    return;
+                }
+
+                let gamut_mapping = GamutMapping::from(row.selected());
+                this.modify_gradient(|gradient| {
+                    gradient.adjustments.gamut_mapping = gamut_mapping;
+                });
+            }));
+
+        for scale in [
+            imp.lightness_scale.get(),
+            imp.chroma_scale.get(),
+            imp.hue_scale.get(),
+            imp.temperature_scale.get(),
+            imp.pattern_opacity_scale.get(),
+            imp.pattern_scale_scale.get(),
+            imp.grain_scale.get(),
+            imp.vignette_strength_scale.get(),
+            imp.vignette_size_scale.get(),
+        ] {
+            precision::setup_range(&scale);
+        }
+
+        for combo in [imp.pattern_combo.get(), imp.pattern_color_combo.get()] {
+            combo.connect_selected_notify(clone!(@weak self as this => move |_| {
+                this.update_pattern(false);
+            }));
+        }
+
+        for scale in [
+            imp.pattern_opacity_scale.get(),
+            imp.pattern_scale_scale.get(),
+        ] {
+            scale.connect_value_changed(clone!(@weak self as this => move |_| {
+                this.update_pattern(true);
+            }));
+        }
+
+        for scale in [
+            imp.vignette_strength_scale.get(),
+            imp.vignette_size_scale.get(),
+        ] {
+            scale.connect_value_changed(clone!(@weak self as this => move |_| {
+                let imp = this.imp();
+                if imp.syncing.get() {
+                    return;
+                }
+
+                let vignette = Vignette {
+                    strength: imp.vignette_strength_scale.value() as f32,
+                    size: imp.vignette_size_scale.value() as f32,
+                };
+                this.edit_gradient(Edit::Vignette, |gradient| gradient.vignette = vignette);
+            }));
+        }
+
+        imp.dark_variant_row
+            .connect_active_notify(clone!(@weak self as this => move |row| {
+                if this.imp().syncing.get() {
+                    return;
+                }
+
+                let enabled = row.is_active();
+                this.imp().editing_dark.set(enabled);
+                this.modify_variants(|gradient| {
+                    gradient.dark = enabled.then(|| {
+                        Box::new(Gradient {
+                            dark: None,
+                            ..gradient.clone()
+                        })
+                    });
+                });
+            }));
+
+        imp.dark_variant_button
+            .connect_toggled(clone!(@weak self as this => move |button| {
+                if this.imp().syncing.get() {
+                    return;
+                }
+
+                this.set_editing_dark(button.is_active());
+            }));
+
+        //edit the variant the desktop is showing
+        adw::StyleManager::default().connect_dark_notify(
+            clone!(@weak self as this => move |manager| {
+                this.set_editing_dark(manager.is_dark());
             }),
         );
 
-        imp.color_one_entry.connect_notify_local(
-            Some("text"),
-            clone!(@strong self as this => move |_entry, _| {
-                this.update_gradient();
+        imp.readable_top_row
+            .connect_active_notify(clone!(@weak self as this => move |row| {
+                if this.imp().syncing.get() {
+                    return;
+                }
+
+                let readable_top = row.is_active();
+                this.modify_gradient(|gradient| gradient.readable_top = readable_top);
+            }));
+
+        imp.grain_scale
+            .connect_value_changed(clone!(@weak self as this => move |scale| {
+                if this.imp().syncing.get() {
+                    return;
+                }
+
+                let grain = scale.value() as f32;
+                this.edit_gradient(Edit::Grain, |gradient| gradient.grain = grain);
+            }));
+
+        self.settings()
+            .bind("community-feed", &*imp.community_row, "active")
+            .build();
+        self.settings().connect_changed(
+            Some("sync-folder"),
+            clone!(@weak self as this => move |_, _| {
+                this.sync_library();
+            }),
+        );
+        self.settings().connect_changed(
+            Some("library-sort"),
+            clone!(@weak self as this => move |_, _| {
+                this.update_recent_documents();
+            }),
+        );
+        self.settings().connect_changed(
+            Some("brand-palette"),
+            clone!(@weak self as this => move |_, _| {
+                this.update_palettes();
+                //bring the colors already there on brand
+                this.modify_variants(|_| {});
+            }),
+        );
+        self.settings().connect_changed(
+            Some("community-feed"),
+            clone!(@weak self as this => move |_, _| {
+                this.update_community();
             }),
         );
 
-        imp.color_two_entry.connect_notify_local(
-            Some("text"),
-            clone!(@strong self as this => move |_entry, _| {
-                this.update_gradient();
+        self.settings().connect_changed(
+            Some("recent-colors"),
+            clone!(@weak self as this => move |_, _| {
+                this.update_recent_colors();
             }),
         );
     }
@@ -165,43 +1070,2674 @@ impl VibrantWindow {
     pub fn copy_css(&self, _button: gtk::Button) {
         let clipboard = self.clipboard();
         clipboard.set_text(&self.generate_css());
+        self.remember_colors();
 
         self.imp()
             .toast_overlay
             .add_toast(adw::Toast::new(&gettext("Copied CSS to clipboard")))
     }
 
-    fn generate_css(&self) -> String {
+    fn open(&self) {
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Open Gradient"))
+            .modal(true)
+            .filters(&document::file_filters())
+            .build();
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let Ok(file) = dialog.open_future(Some(&this)).await else {
+                return;
+            };
+
+            if this.select_file(&file) {
+                return;
+            }
+            if !this.is_pristine() {
+                this.add_tab();
+            }
+            this.load_file(&file).await;
+        }));
+    }
+
+    /// Opens a saved gradient in this window, in the background.
+    pub fn open_file(&self, file: &gio::File) {
+        glib::MainContext::default().spawn_local(
+            clone!(@weak self as this, @strong file => async move {
+                this.load_file(&file).await;
+            }),
+        );
+    }
+
+    async fn load_file(&self, file: &gio::File) {
+        if !self.confirm_discard().await {
+            return;
+        }
+
+        match document::load(file).await {
+            Ok(document) => {
+                self.set_document(document, Some(file.clone()));
+                self.remember_in_library(file);
+            }
+            Err(err) => {
+                glib::g_warning!("vibrant", "Could not open gradient: {}", err);
+                self.imp().toast_overlay.add_toast(adw::Toast::new(
+                    &gettext("Could not open “{}”").replace("{}", &document::display_name(file)),
+                ));
+            }
+        }
+    }
+
+    /// Shows where the angle points and its closest CSS keyword, to help
+    /// learn how they map.
+    fn update_compass(&self) {
+        let imp = self.imp();
+        let angle = imp.angle_row.value() as f32;
+        let (keyword, exact) = gradient::nearest_direction(angle);
+
+        let direction = if exact {
+            keyword.to_owned()
+        } else {
+            gettext("Close to “{}”").replace("{}", keyword)
+        };
+        imp.angle_compass.set_angle(angle);
+        imp.angle_compass
+            .update_property(&[gtk::accessible::Property::Description(&direction)]);
+        imp.angle_row.set_subtitle(&direction);
+    }
+
+    fn setup_tabs(&self) {
+        let tab_view = self.imp().tab_view.get();
+
+        tab_view.connect_selected_page_notify(clone!(@weak self as this => move |_| {
+            this.switch_tab();
+        }));
+
+        tab_view.connect_page_detached(clone!(@weak self as this => move |view, page, _| {
+            //a tab dragged away takes its document along
+            let imp = this.imp();
+            if page.child().downcast_ref::<VibrantTab>() == imp.current_tab.borrow().as_ref() {
+                this.stash_session();
+            }
+
+            if view.n_pages() == 0 {
+                imp.closing.set(true);
+                this.close();
+            }
+        }));
+
+        tab_view.connect_close_page(
+            clone!(@weak self as this => @default-return false, move |view, page| {
+                glib::MainContext::default().spawn_local(
+                    clone!(@weak this, @weak view, @strong page => async move {
+                        view.set_selected_page(&page);
+                        let confirmed = this.confirm_discard().await;
+                        view.close_page_finish(&page, confirmed);
+                    }),
+                );
+                true
+            }),
+        );
+
+        tab_view.connect_create_window(
+            clone!(@weak self as this => @default-return None, move |_| {
+                let application = this.application()?;
+                let window = Self::without_tabs(&application);
+                window.present();
+                Some(window.imp().tab_view.get())
+            }),
+        );
+    }
+
+    /// Opens a new tab with the gradient set in the preferences and selects
+    /// it.
+    fn add_tab(&self) {
+        let tab_view = &self.imp().tab_view;
+
+        let gradient = preferences::default_gradient(self.settings());
+        let session = Session {
+            gradient: gradient.clone(),
+            saved_gradient: gradient,
+            ..Session::default()
+        };
+
+        let page = tab_view.append(&VibrantTab::new(session));
+        page.set_title(&gettext("Untitled Gradient"));
+        tab_view.set_selected_page(&page);
+        self.update_recent_documents();
+    }
+
+    fn tabs(&self) -> Vec<VibrantTab> {
+        let tab_view = &self.imp().tab_view;
+
+        (0..tab_view.n_pages())
+            .filter_map(|i| tab_view.nth_page(i).child().downcast::<VibrantTab>().ok())
+            .collect()
+    }
+
+    /// Edits the document of the selected tab, putting the previous one back
+    /// into its tab.
+    fn switch_tab(&self) {
         let imp = self.imp();
 
-        let gradient_type = GradientType::from(imp.gradient_combo.selected());
-        let degree = imp.direction_combo.selected() as u16 * 90;
+        let selected = imp
+            .tab_view
+            .selected_page()
+            .and_then(|page| page.child().downcast::<VibrantTab>().ok());
+        if selected == *imp.current_tab.borrow() {
+            return;
+        }
+
+        self.stash_session();
+        if let Some(tab) = selected {
+            self.load_session(tab);
+        }
+    }
 
-        let gradient = match gradient_type {
-            GradientType::Linear => format!("linear-gradient({}deg,", degree),
-            GradientType::Radial => "radial-gradient(".to_owned(),
-            GradientType::Conic => format!(
-                "conic-gradient(from {}deg,",
-                //adjust degree to only switch bottom and top direction
-                degree + (degree % 180 == 0) as u16 * 180
-            ),
+    /// Moves the edited document back into its tab.
+    fn stash_session(&self) {
+        let imp = self.imp();
+        let Some(tab) = imp.current_tab.take() else {
+            return;
         };
 
-        format!(
-            ".gradient-box {{background: {} {}, {});}}",
-            gradient,
-            imp.color_one_entry.text(),
-            imp.color_two_entry.text()
-        )
+        tab.replace_session(Session {
+            gradient: imp.gradient.take(),
+            saved_gradient: imp.saved_gradient.take(),
+            file: imp.file.take(),
+            metadata: imp.metadata.take(),
+            collection: imp.collection.take(),
+            saved_collection: imp.saved_collection.take(),
+            undo_stack: imp.undo_stack.take(),
+            redo_stack: imp.redo_stack.take(),
+            selected_stop: imp.selected_stop.get(),
+            started: imp.started.get(),
+        });
     }
 
-    fn update_gradient(&self) {
-        let provider = gtk::CssProvider::new();
-        provider.load_from_data(&self.generate_css());
+    fn load_session(&self, tab: VibrantTab) {
+        let imp = self.imp();
+        let session = tab.take_session();
 
-        self.imp()
-            .gradient_box
-            .style_context()
-            .add_provider(&provider, 1000);
+        imp.gradient.replace(session.gradient);
+        imp.saved_gradient.replace(session.saved_gradient);
+        imp.file.replace(session.file);
+        imp.metadata.replace(session.metadata);
+        imp.collection.replace(session.collection);
+        imp.saved_collection.replace(session.saved_collection);
+        imp.undo_stack.replace(session.undo_stack);
+        imp.redo_stack.replace(session.redo_stack);
+        imp.selected_stop.set(session.selected_stop);
+        imp.started.set(session.started);
+        imp.last_edit.set(None);
+        imp.current_tab.replace(Some(tab));
+
+        self.sync_widgets();
+        self.update_collection();
+        self.update_gradient();
+    }
+
+    /// Selects the tab showing `file`, returning whether there is one.
+    pub fn select_file(&self, file: &gio::File) -> bool {
+        let imp = self.imp();
+
+        let Some(tab) = self.tab_for_file(file) else {
+            return false;
+        };
+        imp.tab_view.set_selected_page(&imp.tab_view.page(&tab));
+        true
+    }
+
+    /// The tab showing `file`, if it is open.
+    fn tab_for_file(&self, file: &gio::File) -> Option<VibrantTab> {
+        let imp = self.imp();
+        let current = imp.current_tab.borrow().clone();
+
+        self.tabs().into_iter().find(|tab| {
+            let open = if Some(tab) == current.as_ref() {
+                imp.file.borrow().clone()
+            } else {
+                tab.session().file.clone()
+            };
+            open.is_some_and(|open| open.equal(file))
+        })
+    }
+
+    fn has_unsaved_tabs(&self) -> bool {
+        let current = self.imp().current_tab.borrow().clone();
+
+        self.is_modified()
+            || self
+                .tabs()
+                .iter()
+                .any(|tab| Some(tab) != current.as_ref() && tab.session().is_modified())
+    }
+
+    /// Goes through the tabs with unsaved changes, asking about each of
+    /// them. Returns whether the window can close.
+    async fn confirm_close_tabs(&self) -> bool {
+        let tab_view = self.imp().tab_view.get();
+
+        for tab in self.tabs() {
+            tab_view.set_selected_page(&tab_view.page(&tab));
+            if !self.confirm_discard().await {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// The documents of every tab with unsaved changes, for autosaving.
+    pub fn unsaved_documents(&self) -> Vec<autosave::Unsaved> {
+        let imp = self.imp();
+        let current = imp.current_tab.borrow().clone();
+
+        self.tabs()
+            .iter()
+            .filter_map(|tab| {
+                if Some(tab) == current.as_ref() {
+                    self.is_modified().then(|| {
+                        let document = Document {
+                            gradient: imp.gradient.borrow().clone(),
+                            metadata: imp.metadata.borrow().clone(),
+                            collection: self.current_collection(),
+                        };
+                        (document, imp.file.borrow().clone())
+                    })
+                } else {
+                    let session = tab.session();
+                    session.is_modified().then(|| {
+                        let document = Document {
+                            gradient: session.gradient.clone(),
+                            metadata: session.metadata.clone(),
+                            collection: session.collection.with_active(&session.gradient),
+                        };
+                        (document, session.file.clone())
+                    })
+                }
+            })
+            .collect()
+    }
+
+    /// Opens an autosaved document in a tab, still marked as modified
+    /// against its file, or against a new gradient when it was never saved.
+    pub async fn restore_document(&self, document: Document, file: Option<gio::File>) {
+        let saved = match &file {
+            Some(file) => document::load(file).await.unwrap_or_default(),
+            None => Document::default(),
+        };
+
+        if !self.is_pristine() {
+            self.add_tab();
+        }
+        self.set_document(document, file);
+        self.imp().saved_gradient.replace(saved.gradient);
+        self.imp().saved_collection.replace(saved.collection);
+        self.update_title();
+    }
+
+    pub fn add_toast(&self, toast: adw::Toast) {
+        self.imp().toast_overlay.add_toast(toast);
+    }
+
+    /// Replaces the gradient with a document, starting a new history.
+    fn set_document(&self, document: Document, file: Option<gio::File>) {
+        let imp = self.imp();
+
+        imp.gradient.replace(document.gradient.clone());
+        imp.saved_gradient.replace(document.gradient);
+        imp.metadata.replace(document.metadata);
+        imp.collection.replace(document.collection.clone());
+        imp.saved_collection.replace(document.collection);
+        imp.file.replace(file);
+
+        imp.undo_stack.borrow_mut().clear();
+        imp.redo_stack.borrow_mut().clear();
+        imp.last_edit.set(None);
+        imp.selected_stop.set(0);
+
+        self.sync_widgets();
+        self.update_collection();
+        self.update_gradient();
+    }
+
+    /// The collection with the edited gradient in place of its stale copy.
+    fn current_collection(&self) -> Collection {
+        let imp = self.imp();
+        imp.collection.borrow().with_active(&imp.gradient.borrow())
+    }
+
+    /// Adds a copy of the edited gradient to the collection and switches to
+    /// it, turning a single gradient into a collection the first time.
+    fn add_collection_gradient(&self) {
+        let imp = self.imp();
+
+        let mut collection = self.current_collection();
+        let gradient = imp.gradient.borrow().clone();
+        if collection.members.is_empty() {
+            collection.members.push(Member {
+                name: self.document_name(),
+                gradient: gradient.clone(),
+            });
+        }
+        let count = collection.members.len() + 1;
+        collection.members.push(Member {
+            name: gettext("Gradient {}").replace("{}", &count.to_string()),
+            gradient,
+        });
+        let index = collection.members.len() - 1;
+        imp.collection.replace(collection);
+
+        self.switch_collection_gradient(index);
+    }
+
+    /// Edits another gradient of the collection. Each one starts a new
+    /// history, as undoing can't reach across gradients.
+    fn switch_collection_gradient(&self, index: usize) {
+        let imp = self.imp();
+
+        let mut collection = self.current_collection();
+        let Some(member) = collection.members.get(index) else {
+            return;
+        };
+        let gradient = member.gradient.clone();
+        let saved_gradient = imp
+            .saved_collection
+            .borrow()
+            .members
+            .get(index)
+            .map_or_else(|| gradient.clone(), |saved| saved.gradient.clone());
+        collection.active = index;
+
+        imp.collection.replace(collection);
+        imp.gradient.replace(gradient);
+        imp.saved_gradient.replace(saved_gradient);
+        imp.undo_stack.borrow_mut().clear();
+        imp.redo_stack.borrow_mut().clear();
+        imp.last_edit.set(None);
+        imp.selected_stop.set(0);
+
+        self.sync_widgets();
+        self.update_collection();
+        self.update_gradient();
+    }
+
+    fn remove_collection_gradient(&self, index: usize) {
+        let imp = self.imp();
+
+        let mut collection = self.current_collection();
+        if index >= collection.members.len() {
+            return;
+        }
+        collection.members.remove(index);
+
+        if index == collection.active {
+            //edit the neighbor of the removed gradient, without saving it back
+            let active = index.min(collection.members.len().saturating_sub(1));
+            collection.active = usize::MAX;
+            imp.collection.replace(collection);
+            self.switch_collection_gradient(active);
+        } else {
+            if index < collection.active {
+                collection.active -= 1;
+            }
+            imp.collection.replace(collection);
+            self.update_collection();
+            self.update_title();
+        }
+    }
+
+    /// Lists the gradients of the collection, each with its name editable.
+    fn update_collection(&self) {
+        let imp = self.imp();
+
+        for row in imp.collection_rows.take() {
+            imp.collection_group.remove(&row);
+        }
+
+        let collection = imp.collection.borrow().clone();
+        imp.collection_group.set_description(
+            (collection.members.is_empty())
+                .then(|| {
+                    gettext(
+                        "Keep related gradients in one document, like those of a brand or an app",
+                    )
+                })
+                .as_deref(),
+        );
+
+        let mut rows = Vec::new();
+        let mut group: Option<gtk::CheckButton> = None;
+        for (index, member) in collection.members.iter().enumerate() {
+            let row = adw::EntryRow::builder()
+                .title(gettext("Name"))
+                .text(&member.name)
+                .build();
+            row.connect_changed(clone!(@weak self as this => move |row| {
+                if let Some(member) = this.imp().collection.borrow_mut().members.get_mut(index) {
+                    member.name = row.text().into();
+                }
+                this.update_title();
+            }));
+
+            let check = gtk::CheckButton::builder()
+                .active(index == collection.active)
+                .tooltip_text(gettext("Edit Gradient"))
+                .valign(gtk::Align::Center)
+                .build();
+            check.set_group(group.as_ref());
+            check.connect_toggled(clone!(@weak self as this => move |check| {
+                if check.is_active() {
+                    this.switch_collection_gradient(index);
+                }
+            }));
+            group.get_or_insert(check.clone());
+            row.add_prefix(&check);
+
+            let remove_button = gtk::Button::builder()
+                .icon_name("user-trash-symbolic")
+                .tooltip_text(gettext("Remove Gradient"))
+                .valign(gtk::Align::Center)
+                .sensitive(collection.members.len() > 1)
+                .css_classes(["flat"])
+                .build();
+            remove_button.connect_clicked(clone!(@weak self as this => move |_| {
+                this.remove_collection_gradient(index);
+            }));
+            row.add_suffix(&remove_button);
+
+            imp.collection_group.add(&row);
+            rows.push(row);
+        }
+
+        imp.collection_rows.replace(rows);
+    }
+
+    /// Saves to the open file, asking for one the first time. Returns
+    /// whether the document was saved.
+    async fn save(&self) -> bool {
+        let file = self.imp().file.borrow().clone();
+        match file {
+            Some(file) => self.save_to(&file).await,
+            None => self.save_as().await,
+        }
+    }
+
+    async fn save_as(&self) -> bool {
+        //offer to update a saved gradient looking the same, instead of
+        //saving another copy of it
+        if let Some(entry) = self.similar_saved_gradient().await {
+            match self.confirm_update_similar(&entry.file).await.as_str() {
+                "update" => {
+                    //the saved gradient keeps its own name and creation date
+                    self.imp().metadata.replace(entry.document.metadata);
+                    return self.save_to(&entry.file).await;
+                }
+                "copy" => (),
+                _ => return false,
+            }
+        }
+
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Save Gradient"))
+            .modal(true)
+            .filters(&document::file_filters())
+            .initial_name(format!("{}.{}", self.document_name(), document::SUFFIX))
+            .build();
+
+        match dialog.save_future(Some(self)).await {
+            Ok(file) => self.save_to(&file).await,
+            Err(_) => false,
+        }
+    }
+
+    async fn save_to(&self, file: &gio::File) -> bool {
+        let imp = self.imp();
+
+        let gradient = imp.gradient.borrow().clone();
+        let collection = self.current_collection();
+        let mut document = Document {
+            gradient: gradient.clone(),
+            metadata: imp.metadata.borrow().clone(),
+            collection: collection.clone(),
+        };
+
+        match document::save(file, &mut document).await {
+            Ok(()) => {
+                imp.file.replace(Some(file.clone()));
+                imp.metadata.replace(document.metadata);
+                imp.saved_gradient.replace(gradient);
+                imp.saved_collection.replace(collection);
+                self.update_title();
+                self.remember_in_library(file);
+                true
+            }
+            Err(err) => {
+                glib::g_warning!("vibrant", "Could not save gradient: {}", err);
+                imp.toast_overlay
+                    .add_toast(adw::Toast::new(&gettext("Could not save gradient")));
+                false
+            }
+        }
+    }
+
+    /// Asks what to do with unsaved changes, saving them when requested.
+    /// Returns whether the document can be replaced or closed.
+    async fn confirm_discard(&self) -> bool {
+        if !self.is_modified() {
+            return true;
+        }
+
+        let dialog = adw::MessageDialog::builder()
+            .transient_for(self)
+            .modal(true)
+            .heading(gettext("Save Changes?"))
+            .body(
+                gettext("“{}” contains unsaved changes. Changes which are not saved will be permanently lost.")
+                    .replace("{}", &self.document_name()),
+            )
+            .default_response("save")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[
+            ("cancel", &gettext("_Cancel")),
+            ("discard", &gettext("_Discard")),
+            ("save", &gettext("_Save")),
+        ]);
+        dialog.set_response_appearance("discard", adw::ResponseAppearance::Destructive);
+        dialog.set_response_appearance("save", adw::ResponseAppearance::Suggested);
+
+        match dialog.choose_future().await.as_str() {
+            "discard" => true,
+            "save" => self.save().await,
+            _ => false,
+        }
+    }
+
+    /// Whether the window still shows the gradient it started with, so a
+    /// document can be opened in it without losing anything.
+    pub fn is_pristine(&self) -> bool {
+        let imp = self.imp();
+        imp.file.borrow().is_none() && imp.undo_stack.borrow().is_empty() && !self.is_modified()
+    }
+
+    fn is_modified(&self) -> bool {
+        let imp = self.imp();
+        *imp.gradient.borrow() != *imp.saved_gradient.borrow()
+            || imp
+                .collection
+                .borrow()
+                .is_modified(&imp.gradient.borrow(), &imp.saved_collection.borrow())
+    }
+
+    fn document_name(&self) -> String {
+        self.imp()
+            .file
+            .borrow()
+            .as_ref()
+            .map(document::display_name)
+            .unwrap_or_else(|| gettext("Untitled Gradient"))
+    }
+
+    /// Shows the document name as the title of the window and its tab,
+    /// marked while it has unsaved changes.
+    fn update_title(&self) {
+        let name = self.document_name();
+        let title = if self.is_modified() {
+            format!("• {name}")
+        } else {
+            name
+        };
+
+        self.set_title(Some(&title));
+        if let Some(page) = self.imp().tab_view.selected_page() {
+            page.set_title(&title);
+        }
+    }
+
+    /// Replaces the colors with a vertical sky for the time of day.
+    fn apply_time_of_day(&self, time: TimeOfDay) {
+        self.apply_colors(time.colors());
+    }
+
+    /// Replaces the stops with generated colors as a vertical linear
+    /// gradient, keeping the textures and adjustments.
+    fn apply_colors(&self, colors: Vec<gdk::RGBA>) {
+        self.apply_colors_at(0.0, colors);
+    }
+
+    /// Replaces the stops with generated colors as a linear gradient at
+    /// `angle`, keeping the textures and adjustments.
+    fn apply_colors_at(&self, angle: f32, colors: Vec<gdk::RGBA>) {
+        self.modify_gradient(|gradient| {
+            gradient.kind = GradientType::Linear;
+            gradient.angle = angle;
+            gradient.hard_stops = false;
+            gradient.symmetric = false;
+            gradient.stops = colors.into_iter().map(ColorStop::new).collect();
+        });
+        self.imp().selected_stop.set(0);
+    }
+
+    /// Takes the colors of an image, from left to right.
+    fn start_from_image(&self) {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(&gettext("Images")));
+        filter.add_pixbuf_formats();
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        filters.append(&filter);
+
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Choose an Image"))
+            .modal(true)
+            .filters(&filters)
+            .build();
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let Ok(file) = dialog.open_future(Some(&this)).await else {
+                return;
+            };
+
+            let texture = file
+                .load_bytes_future()
+                .await
+                .and_then(|(bytes, _)| gdk::Texture::from_bytes(&bytes));
+            match texture {
+                Ok(texture) => this.apply_colors(presets::from_texture(&texture, 3)),
+                Err(err) => {
+                    glib::g_warning!("vibrant", "Could not load image: {}", err);
+                    this.imp()
+                        .toast_overlay
+                        .add_toast(adw::Toast::new(&gettext("Could not load image")));
+                }
+            }
+        }));
+    }
+
+    /// Takes the colors written in the copied text, or builds a gradient
+    /// around a single one.
+    fn paste_colors(&self) {
+        let clipboard = self.clipboard();
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let text = clipboard.read_text_future().await.ok().flatten();
+            let colors = text.map(|text| color::find_colors(&text)).unwrap_or_default();
+
+            match colors.as_slice() {
+                [] => this
+                    .imp()
+                    .toast_overlay
+                    .add_toast(adw::Toast::new(&gettext("No colors found in the clipboard"))),
+                [color] => this.apply_colors(presets::around_accent(color)),
+                _ => this.apply_colors(colors),
+            }
+        }));
+    }
+
+    /// Builds a gradient between two colors picked one after the other
+    /// anywhere on the screen.
+    fn pick_endpoints(&self) {
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let mut colors = Vec::new();
+            while colors.len() < 2 {
+                match screenshot::pick_color().await {
+                    Ok(color) => colors.push(color),
+                    Err(err) if err.matches(gio::IOErrorEnum::Cancelled) => return,
+                    Err(err) => {
+                        glib::g_warning!("vibrant", "Could not pick a color: {}", err);
+                        this.imp()
+                            .toast_overlay
+                            .add_toast(adw::Toast::new(&gettext("Could not pick a color")));
+                        return;
+                    }
+                }
+
+                if colors.len() == 1 {
+                    this.imp()
+                        .toast_overlay
+                        .add_toast(adw::Toast::new(&gettext("Now pick the end color")));
+                }
+            }
+
+            this.apply_colors(colors);
+        }));
+    }
+
+    /// Generates endpoints that text of a chosen color stays readable on,
+    /// with every point between them reaching a contrast ratio.
+    fn readable_background(&self) {
+        let settings = self.settings();
+
+        let foreground = gdk::RGBA::parse(settings.string("readable-foreground").as_str())
+            .unwrap_or(gdk::RGBA::WHITE);
+        let color_button = gtk::ColorDialogButton::builder()
+            .dialog(&gtk::ColorDialog::new())
+            .rgba(&foreground)
+            .valign(gtk::Align::Center)
+            .build();
+        let foreground_row = adw::ActionRow::builder()
+            .title(gettext("Text Color"))
+            .activatable_widget(&color_button)
+            .build();
+        foreground_row.add_suffix(&color_button);
+
+        let contrast_row = adw::SpinRow::builder()
+            .title(gettext("Contrast Ratio"))
+            .subtitle(gettext(
+                "4.5 for body text, 3 for large text, 7 for the most readable",
+            ))
+            .digits(1)
+            .adjustment(&gtk::Adjustment::new(
+                settings.double("readable-contrast"),
+                1.0,
+                21.0,
+                0.5,
+                1.0,
+                0.0,
+            ))
+            .build();
+
+        let list = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .css_classes(["boxed-list"])
+            .build();
+        list.append(&foreground_row);
+        list.append(&contrast_row);
+
+        let dialog = adw::MessageDialog::builder()
+            .transient_for(self)
+            .modal(true)
+            .heading(gettext("Keep Text Readable"))
+            .body(gettext(
+                "Change the end colors so text has enough contrast anywhere on the gradient",
+            ))
+            .extra_child(&list)
+            .default_response("generate")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[
+            ("cancel", &gettext("_Cancel")),
+            ("generate", &gettext("_Generate")),
+        ]);
+        dialog.set_response_appearance("generate", adw::ResponseAppearance::Suggested);
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            if dialog.choose_future().await != "generate" {
+                return;
+            }
+
+            let foreground = color_button.rgba();
+            let ratio = contrast_row.value();
+            let settings = this.settings();
+            let _ = settings.set_string("readable-foreground", &color::to_hex(&foreground));
+            let _ = settings.set_double("readable-contrast", ratio);
+
+            let endpoints = presets::readable_endpoints(&this.edited(), &foreground, ratio as f32);
+            match endpoints {
+                Some(colors) => {
+                    this.modify_gradient(|gradient| {
+                        gradient.stops = colors.into_iter().map(ColorStop::new).collect();
+                    });
+                    this.imp().selected_stop.set(0);
+                }
+                None => this.add_toast(adw::Toast::new(&gettext(
+                    "No colors reach this contrast with the text color",
+                ))),
+            }
+        }));
+    }
+
+    /// Approximates a region of the screen chosen by the user with a
+    /// linear gradient.
+    fn start_from_screenshot(&self) {
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            match screenshot::capture_region().await {
+                Ok(texture) => {
+                    let (angle, colors) = presets::from_region(&texture, SCREENSHOT_STOPS);
+                    this.apply_colors_at(angle, colors);
+                }
+                Err(err) if err.matches(gio::IOErrorEnum::Cancelled) => (),
+                Err(err) => {
+                    glib::g_warning!("vibrant", "Could not take a screenshot: {}", err);
+                    this.imp()
+                        .toast_overlay
+                        .add_toast(adw::Toast::new(&gettext("Could not take a screenshot")));
+                }
+            }
+        }));
+    }
+
+    /// Starts or stops collecting the colors copied to the clipboard into
+    /// the collected colors tray.
+    fn watch_clipboard(&self, watch: bool) {
+        let imp = self.imp();
+        let clipboard = self.clipboard();
+
+        if let Some(handler) = imp.clipboard_handler.take() {
+            clipboard.disconnect(handler);
+        }
+        if watch {
+            let handler = clipboard.connect_changed(clone!(@weak self as this => move |_| {
+                this.collect_clipboard_colors();
+            }));
+            imp.clipboard_handler.replace(Some(handler));
+            self.collect_clipboard_colors();
+        }
+
+        self.update_collected_colors();
+    }
+
+    fn collect_clipboard_colors(&self) {
+        let clipboard = self.clipboard();
+        //our own copies are already in the gradient
+        if clipboard.is_local() {
+            return;
+        }
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let Ok(Some(text)) = clipboard.read_text_future().await else {
+                return;
+            };
+
+            let mut collected = this.imp().collected_colors.borrow_mut();
+            let count = collected.len();
+            for color in color::find_colors(&text) {
+                let hex = color::to_hex(&color);
+                if !collected.contains(&hex) {
+                    collected.push(hex);
+                }
+            }
+            if collected.len() == count {
+                return;
+            }
+
+            let excess = collected.len().saturating_sub(MAX_COLLECTED_COLORS);
+            collected.drain(..excess);
+            drop(collected);
+            this.update_collected_colors();
+        }));
+    }
+
+    /// Replaces the stops with the collected colors, or builds a gradient
+    /// around a single one.
+    fn use_collected_colors(&self) {
+        let colors: Vec<gdk::RGBA> = self
+            .imp()
+            .collected_colors
+            .borrow()
+            .iter()
+            .filter_map(|color| gdk::RGBA::parse(color.as_str()).ok())
+            .collect();
+
+        match colors.as_slice() {
+            [] => (),
+            [color] => self.apply_colors(presets::around_accent(color)),
+            _ => self.apply_colors(colors),
+        }
+    }
+
+    /// Builds a gradient around the desktop's accent color.
+    fn match_desktop(&self) {
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            match accent::accent_color().await {
+                Ok(accent) => this.apply_colors(presets::around_accent(&accent)),
+                Err(err) => {
+                    glib::g_warning!("vibrant", "Could not read the accent color: {}", err);
+                    this.imp()
+                        .toast_overlay
+                        .add_toast(adw::Toast::new(&gettext("Could not read the accent color")));
+                }
+            }
+        }));
+    }
+
+    /// Wraps the preview in a mock window of the given style, the gradient
+    /// standing in for its header, or removes it for `"none"`.
+    fn update_chrome(&self, style: &str) {
+        let chrome_box = &self.imp().chrome_box;
+
+        chrome_box.set_visible(style != "none");
+        for class in ["light", "dark"] {
+            if class == style {
+                chrome_box.add_css_class(class);
+            } else {
+                chrome_box.remove_css_class(class);
+            }
+        }
+    }
+
+    /// Opens the chart generator, starting with a slice per gradient color.
+    /// Asks for a falloff curve and replaces the stops with the selected
+    /// color fading to transparent along it.
+    fn show_fade_out(&self) {
+        let Some(color) = self.selected_color() else {
+            return;
+        };
+
+        let names = Falloff::ALL.map(Falloff::name);
+        let curve_row = adw::ComboRow::builder()
+            .title(gettext("Curve"))
+            .model(&gtk::StringList::new(&names.each_ref().map(String::as_str)))
+            .selected(2)
+            .build();
+
+        let list = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .css_classes(["boxed-list"])
+            .build();
+        list.append(&curve_row);
+
+        let dialog = adw::MessageDialog::builder()
+            .transient_for(self)
+            .modal(true)
+            .heading(gettext("Fade to Transparent"))
+            .body(gettext(
+                "The selected color fades out, keeping its hue all the way",
+            ))
+            .extra_child(&list)
+            .default_response("fade")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[("cancel", &gettext("_Cancel")), ("fade", &gettext("_Fade"))]);
+        dialog.set_response_appearance("fade", adw::ResponseAppearance::Suggested);
+
+        dialog.connect_response(
+            None,
+            clone!(@weak self as this, @weak curve_row => move |_, response| {
+                if response != "fade" {
+                    return;
+                }
+
+                let falloff = Falloff::ALL
+                    .get(curve_row.selected() as usize)
+                    .copied()
+                    .unwrap_or(Falloff::Linear);
+                this.modify_gradient(|gradient| {
+                    gradient.hard_stops = false;
+                    gradient.symmetric = false;
+                    gradient.stops = presets::fade(&color, falloff);
+                });
+                this.imp().selected_stop.set(0);
+            }),
+        );
+
+        dialog.present();
+    }
+
+    /// Offers variations of the gradient, replacing its colors with the
+    /// one clicked.
+    fn show_variations(&self) {
+        let edited = self.edited();
+        let bounds = gdk::Rectangle::new(0, 0, VARIATION_WIDTH, VARIATION_HEIGHT);
+
+        let flow_box = gtk::FlowBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .homogeneous(true)
+            .min_children_per_line(3)
+            .max_children_per_line(3)
+            .column_spacing(6)
+            .row_spacing(6)
+            .build();
+
+        let dialog = adw::MessageDialog::builder()
+            .transient_for(self)
+            .modal(true)
+            .heading(gettext("Variations"))
+            .body(gettext("Choose one to replace the colors of the gradient"))
+            .extra_child(&flow_box)
+            .close_response("cancel")
+            .build();
+        dialog.add_response("cancel", &gettext("_Cancel"));
+
+        for variation in Variation::ALL {
+            let mut gradient = edited.clone();
+            for stop in &mut gradient.stops {
+                stop.color = variation.apply(&stop.color);
+            }
+
+            let picture = gtk::Picture::builder()
+                .width_request(VARIATION_WIDTH)
+                .height_request(VARIATION_HEIGHT)
+                .css_classes(["thumbnail"])
+                .build();
+            picture.set_paintable(
+                wallpaper::render_layout(
+                    &gradient.adjusted(),
+                    &[bounds],
+                    self.scale_factor(),
+                    true,
+                )
+                .as_ref(),
+            );
+
+            let content = gtk::Box::builder()
+                .orientation(gtk::Orientation::Vertical)
+                .spacing(6)
+                .build();
+            content.append(&picture);
+            content.append(&gtk::Label::new(Some(&variation.name())));
+
+            let button = gtk::Button::builder()
+                .child(&content)
+                .css_classes(["flat"])
+                .build();
+            button.connect_clicked(clone!(@weak self as this, @weak dialog => move |_| {
+                let stops = gradient.stops.clone();
+                this.modify_gradient(|gradient| gradient.stops = stops);
+                dialog.close();
+            }));
+            flow_box.append(&button);
+        }
+
+        dialog.present();
+    }
+
+    fn update_from_scales(&self) {
+        let imp = self.imp();
+        if imp.syncing.get() {
+            return;
+        }
+
+        let adjustments = Adjustments {
+            lightness: imp.lightness_scale.value() as f32,
+            chroma: imp.chroma_scale.value() as f32,
+            hue: imp.hue_scale.value() as f32,
+            temperature: imp.temperature_scale.value() as f32,
+            gamut_mapping: GamutMapping::from(imp.gamut_row.selected()),
+        };
+        self.edit_gradient(Edit::Adjustments, |gradient| {
+            gradient.adjustments = adjustments;
+        });
+    }
+
+    /// Reads the pattern controls into the model, merging continuous changes
+    /// of the sliders into one undo step.
+    fn update_pattern(&self, continuous: bool) {
+        let imp = self.imp();
+        if imp.syncing.get() {
+            return;
+        }
+
+        let kind = PatternKind::from(imp.pattern_combo.selected());
+        let color = if imp.pattern_color_combo.selected() == 0 {
+            gdk::RGBA::WHITE
+        } else {
+            gdk::RGBA::BLACK
+        };
+        let opacity = imp.pattern_opacity_scale.value() as f32;
+        let scale = imp.pattern_scale_scale.value() as f32;
+
+        let update = |gradient: &mut Gradient| {
+            gradient.pattern.kind = kind;
+            gradient.pattern.color = color;
+            gradient.pattern.opacity = opacity;
+            gradient.pattern.scale = scale;
+        };
+
+        if continuous {
+            self.edit_gradient(Edit::Pattern, update);
+        } else {
+            self.modify_gradient(update);
+        }
+    }
+
+    fn set_editing_dark(&self, dark: bool) {
+        let imp = self.imp();
+        if imp.editing_dark.replace(dark) == dark {
+            return;
+        }
+
+        imp.last_edit.set(None);
+        self.sync_widgets();
+        self.update_gradient();
+    }
+
+    /// The variant the controls are editing.
+    fn edited(&self) -> Ref<'_, Gradient> {
+        let dark = self.imp().editing_dark.get();
+        Ref::map(self.imp().gradient.borrow(), |gradient| {
+            gradient.variant(dark)
+        })
+    }
+
+    /// Applies a change to the edited variant as a single undo step.
+    fn modify_gradient(&self, f: impl FnOnce(&mut Gradient)) {
+        let dark = self.imp().editing_dark.get();
+        self.modify_variants(|gradient| f(gradient.variant_mut(dark)));
+    }
+
+    /// Applies a change to the gradient and all its variants as a single
+    /// undo step.
+    fn modify_variants(&self, f: impl FnOnce(&mut Gradient)) {
+        let imp = self.imp();
+
+        let previous = imp.gradient.borrow().clone();
+        f(&mut imp.gradient.borrow_mut());
+        self.snap_to_brand();
+        if *imp.gradient.borrow() == previous {
+            return;
+        }
+
+        imp.undo_stack.borrow_mut().push(previous);
+        imp.redo_stack.borrow_mut().clear();
+        imp.last_edit.set(None);
+
+        self.sync_widgets();
+        self.update_gradient();
+    }
+
+    /// Applies a change made while typing or dragging, merging it into the
+    /// previous undo step when it continues the same edit. Widgets are left
+    /// alone, as they are the source of the change.
+    fn edit_gradient(&self, edit: Edit, f: impl FnOnce(&mut Gradient)) {
+        let imp = self.imp();
+
+        if imp.last_edit.get() != Some(edit) {
+            imp.undo_stack
+                .borrow_mut()
+                .push(imp.gradient.borrow().clone());
+            imp.redo_stack.borrow_mut().clear();
+            imp.last_edit.set(Some(edit));
+        }
+
+        f(imp
+            .gradient
+            .borrow_mut()
+            .variant_mut(imp.editing_dark.get()));
+        self.snap_to_brand();
+        self.update_gradient();
+    }
+
+    /// The colors of the palette the gradient is locked to, if any.
+    fn brand_colors(&self) -> Vec<gdk::RGBA> {
+        let name = self.settings().string("brand-palette");
+        if name.is_empty() {
+            return Vec::new();
+        }
+
+        self.imp()
+            .palettes
+            .borrow()
+            .iter()
+            .find(|palette| palette.name == name)
+            .map(|palette| {
+                palette
+                    .colors
+                    .iter()
+                    .filter_map(|color| gdk::RGBA::parse(color.as_str()).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Keeps the stops on brand while a palette is locked, replacing other
+    /// colors with the closest one it has.
+    fn snap_to_brand(&self) {
+        let imp = self.imp();
+
+        let colors = self.brand_colors();
+        if !imp.gradient.borrow_mut().snap_to_colors(&colors) || imp.brand_toast.borrow().is_some()
+        {
+            return;
+        }
+
+        let toast = adw::Toast::new(&gettext("Changed to the closest brand color"));
+        toast.connect_dismissed(clone!(@weak self as this => move |_| {
+            this.imp().brand_toast.take();
+        }));
+        imp.brand_toast.replace(Some(toast.clone()));
+        self.add_toast(toast);
+    }
+
+    fn undo(&self) {
+        let imp = self.imp();
+
+        let Some(previous) = imp.undo_stack.borrow_mut().pop() else {
+            return;
+        };
+        let current = imp.gradient.replace(previous);
+        imp.redo_stack.borrow_mut().push(current);
+        imp.last_edit.set(None);
+
+        self.sync_widgets();
+        self.update_gradient();
+    }
+
+    fn redo(&self) {
+        let imp = self.imp();
+
+        let Some(next) = imp.redo_stack.borrow_mut().pop() else {
+            return;
+        };
+        let current = imp.gradient.replace(next);
+        imp.undo_stack.borrow_mut().push(current);
+        imp.last_edit.set(None);
+
+        self.sync_widgets();
+        self.update_gradient();
+    }
+
+    fn set_action_enabled(&self, name: &str, enabled: bool) {
+        if let Some(action) = self.lookup_action(name).and_downcast::<gio::SimpleAction>() {
+            action.set_enabled(enabled);
+        }
+    }
+
+    fn add_stop(&self) {
+        let imp = self.imp();
+
+        self.modify_gradient(|gradient| {
+            if let Some(last) = gradient.stops.last() {
+                gradient.stops.push(ColorStop::new(last.color));
+            }
+        });
+        imp.selected_stop
+            .set(self.edited().stops.len().saturating_sub(1));
+    }
+
+    fn copy_color(&self, text: &str) {
+        self.clipboard().set_text(text);
+        self.imp()
+            .toast_overlay
+            .add_toast(adw::Toast::new(&gettext("Copied “{}”").replace("{}", text)));
+    }
+
+    fn insert_stop(&self, offset: f32) {
+        let mut index = 0;
+        self.modify_gradient(|gradient| index = gradient.insert_stop_at(offset));
+        self.imp().selected_stop.set(index);
+    }
+
+    fn focus_stop_position(&self, index: usize) {
+        let imp = self.imp();
+
+        let entry = imp.stop_position_entries.borrow().get(index).cloned();
+        if let Some(entry) = entry {
+            imp.selected_stop.set(index);
+            entry.grab_focus();
+        }
+    }
+
+    fn remove_stop(&self, index: usize) {
+        let imp = self.imp();
+
+        self.modify_gradient(|gradient| {
+            if gradient.stops.len() > 2 && index < gradient.stops.len() {
+                gradient.stops.remove(index);
+            }
+        });
+
+        let last = self.edited().stops.len().saturating_sub(1);
+        imp.selected_stop.set(imp.selected_stop.get().min(last));
+    }
+
+    /// Keyboard editing of the color rows: arrows move between them,
+    /// Alt+arrows nudge the position, finely with Shift or to the next tenth
+    /// with Ctrl, Alt+Page Up and Alt+Page Down by ten steps, Alt+Home and
+    /// Alt+End to the ends, Ctrl+arrows move the color up or down and Alt+Delete
+    /// removes it. Plain Delete keeps editing the text.
+    fn stop_key_pressed(
+        &self,
+        index: usize,
+        key: gdk::Key,
+        state: gdk::ModifierType,
+    ) -> glib::Propagation {
+        let modifiers = state & gtk::accelerator_get_default_mod_mask();
+        let last = self.edited().stops.len().saturating_sub(1);
+
+        match key {
+            gdk::Key::Up | gdk::Key::Down if modifiers.is_empty() => {
+                let target = if key == gdk::Key::Up {
+                    index.checked_sub(1)
+                } else {
+                    Some(index + 1).filter(|&next| next <= last)
+                };
+                if let Some(target) = target {
+                    self.focus_stop(target);
+                }
+            }
+            gdk::Key::Up | gdk::Key::Down if modifiers == gdk::ModifierType::CONTROL_MASK => {
+                let target = if key == gdk::Key::Up {
+                    index.checked_sub(1)
+                } else {
+                    Some(index + 1).filter(|&next| next <= last)
+                };
+                if let Some(target) = target {
+                    self.move_stop(index, target);
+                }
+            }
+            _ if modifiers.contains(gdk::ModifierType::ALT_MASK)
+                && Nudge::from_key(key).is_some() =>
+            {
+                if let Some(nudge) = Nudge::from_key(key) {
+                    self.nudge_stop(index, nudge, Precision::from_state(modifiers));
+                }
+            }
+            gdk::Key::Delete | gdk::Key::KP_Delete if modifiers == gdk::ModifierType::ALT_MASK => {
+                self.remove_stop(index);
+                self.focus_stop(self.imp().selected_stop.get());
+            }
+            _ => return glib::Propagation::Proceed,
+        }
+
+        glib::Propagation::Stop
+    }
+
+    fn focus_stop(&self, index: usize) {
+        let imp = self.imp();
+
+        imp.selected_stop.set(index);
+        let row = imp.stop_rows.borrow().get(index).cloned();
+        if let Some(row) = row {
+            row.grab_focus();
+        }
+    }
+
+    /// Swaps the colors of two stops, keeping the positions in order.
+    fn move_stop(&self, from: usize, to: usize) {
+        self.modify_gradient(|gradient| {
+            if from < gradient.stops.len() && to < gradient.stops.len() {
+                let color = gradient.stops[from].color;
+                gradient.stops[from].color = gradient.stops[to].color;
+                gradient.stops[to].color = color;
+            }
+        });
+        self.focus_stop(to);
+    }
+
+    /// Moves a stop along the gradient, fixing its position if it was unset.
+    fn nudge_stop(&self, index: usize, nudge: Nudge, precision: Precision) {
+        let Some(position) = self.edited().positions().get(index).copied() else {
+            return;
+        };
+
+        let position = match (nudge, precision) {
+            //to the next tenth, even when already on one
+            (Nudge::Steps(steps), Precision::Snap) => {
+                let forward = steps > 0.0;
+                let tenths = position / precision::POSITION_SNAP;
+                let tenths = if forward {
+                    (tenths + 0.001).floor() + 1.0
+                } else {
+                    (tenths - 0.001).ceil() - 1.0
+                };
+                tenths * precision::POSITION_SNAP
+            }
+            _ => nudge.apply(
+                position as f64,
+                precision.scale(NUDGE_STEP as f64),
+                0.0,
+                1.0,
+            ) as f32,
+        };
+
+        self.edit_gradient(Edit::StopPosition(index), |gradient| {
+            gradient.stops[index].position = Some(position.clamp(0.0, 1.0));
+        });
+        self.sync_widgets();
+        self.focus_stop(index);
+    }
+
+    fn set_stop_color(&self, index: usize, rgba: gdk::RGBA) {
+        self.modify_gradient(|gradient| gradient.set_stop_color(index, rgba));
+    }
+
+    /// Shows the colors of the stops linked to the one being typed in,
+    /// leaving its own text alone.
+    fn sync_linked_rows(&self, index: usize) {
+        let imp = self.imp();
+        let gradient = self.edited();
+
+        imp.syncing.set(true);
+        for (i, row) in imp.stop_rows.borrow().iter().enumerate() {
+            if let Some(stop) = gradient
+                .stops
+                .get(i)
+                .filter(|stop| i != index && stop.linked)
+            {
+                row.set_text(&color::to_hex(&stop.color));
+            }
+        }
+        imp.syncing.set(false);
+    }
+
+    /// Updates every widget to reflect the gradient model.
+    fn sync_widgets(&self) {
+        let imp = self.imp();
+        let gradient = self.edited().clone();
+
+        imp.syncing.set(true);
+
+        let has_dark = imp.gradient.borrow().dark.is_some();
+        imp.dark_variant_row.set_active(has_dark);
+        if has_dark && imp.editing_dark.get() {
+            imp.dark_variant_button.set_active(true);
+        } else {
+            imp.light_variant_button.set_active(true);
+        }
+
+        imp.gradient_combo.set_selected(gradient.kind.into());
+        imp.angle_row.set_value(gradient.angle as f64);
+        //radial gradients have no direction
+        imp.angle_row
+            .set_sensitive(gradient.kind != GradientType::Radial);
+        imp.gradient_box
+            .set_cursor_from_name((gradient.kind != GradientType::Radial).then_some("crosshair"));
+        imp.angle_row
+            .set_title(&if gradient.kind == GradientType::Conic {
+                gettext("Start Angle")
+            } else {
+                gettext("Angle")
+            });
+        imp.hard_stops_row.set_active(gradient.hard_stops);
+        imp.symmetric_row.set_active(gradient.symmetric);
+        imp.tint_transparent_row
+            .set_active(gradient.tint_transparent);
+        //only offered once there is something to tint
+        imp.tint_transparent_row
+            .set_visible(gradient.tint_transparent || gradient.has_transparent_stops());
+        imp.lightness_scale
+            .set_value(gradient.adjustments.lightness as f64);
+        imp.chroma_scale
+            .set_value(gradient.adjustments.chroma as f64);
+        imp.hue_scale.set_value(gradient.adjustments.hue as f64);
+        imp.temperature_scale
+            .set_value(gradient.adjustments.temperature as f64);
+        imp.gamut_row
+            .set_selected(gradient.adjustments.gamut_mapping.into());
+
+        let pattern = gradient.pattern;
+        imp.pattern_combo.set_selected(pattern.kind.into());
+        imp.pattern_color_combo
+            .set_selected((pattern.color != gdk::RGBA::WHITE) as u32);
+        imp.pattern_opacity_scale.set_value(pattern.opacity as f64);
+        imp.pattern_scale_scale.set_value(pattern.scale as f64);
+        for row in [
+            imp.pattern_color_combo.upcast_ref::<gtk::Widget>(),
+            imp.pattern_opacity_row.upcast_ref(),
+            imp.pattern_scale_row.upcast_ref(),
+        ] {
+            row.set_sensitive(pattern.kind != PatternKind::None);
+        }
+        imp.vignette_strength_scale
+            .set_value(gradient.vignette.strength as f64);
+        imp.vignette_size_scale
+            .set_value(gradient.vignette.size as f64);
+        imp.readable_top_row.set_active(gradient.readable_top);
+        imp.grain_scale.set_value(gradient.grain as f64);
+        self.update_stop_rows();
+
+        imp.syncing.set(false);
+    }
+
+    fn update_stop_rows(&self) {
+        let imp = self.imp();
+
+        for row in imp.stop_rows.take() {
+            imp.colors_group.remove(&row);
+        }
+
+        let gradient = self.edited().clone();
+        let removable = gradient.stops.len() > 2;
+
+        let mut rows = Vec::new();
+        let mut position_entries = Vec::new();
+        for (index, stop) in gradient.stops.iter().enumerate() {
+            let row = adw::EntryRow::builder()
+                .title(
+                    pgettext("Color in a list", "Color {}").replace("{}", &(index + 1).to_string()),
+                )
+                .text(color::to_hex(&stop.color))
+                .build();
+
+            row.connect_notify_local(
+                Some("text"),
+                clone!(@weak self as this => move |row, _| {
+                    if this.imp().syncing.get() {
+                        return;
+                    }
+
+                    match gdk::RGBA::parse(row.text().as_str()) {
+                        Ok(rgba) => {
+                            row.remove_css_class("error");
+                            this.edit_gradient(Edit::StopColor(index), |gradient| {
+                                gradient.set_stop_color(index, rgba);
+                            });
+                            //linked stops changed along with it
+                            if this.edited().stops[index].linked {
+                                this.sync_linked_rows(index);
+                            }
+                        }
+                        Err(_) => row.add_css_class("error"),
+                    }
+                }),
+            );
+
+            row.connect_entry_activated(clone!(@weak self as this => move |row| {
+                this.add_recent_color(&row.text());
+            }));
+
+            let focus_controller = gtk::EventControllerFocus::new();
+            focus_controller.connect_enter(clone!(@weak self as this => move |_| {
+                this.imp().selected_stop.set(index);
+            }));
+            row.add_controller(focus_controller);
+
+            //captured before the entry uses the arrows to move its cursor
+            let key_controller = gtk::EventControllerKey::new();
+            key_controller.set_propagation_phase(gtk::PropagationPhase::Capture);
+            key_controller.connect_key_pressed(
+                clone!(@weak self as this => @default-return glib::Propagation::Proceed, move |_, key, _, state| {
+                    this.stop_key_pressed(index, key, state)
+                }),
+            );
+            row.add_controller(key_controller);
+
+            let drop_target = gtk::DropTarget::new(gdk::RGBA::static_type(), gdk::DragAction::COPY);
+            drop_target.connect_drop(
+                clone!(@weak self as this => @default-return false, move |_, value, _, _| {
+                    let Ok(rgba) = value.get::<gdk::RGBA>() else {
+                        return false;
+                    };
+
+                    this.set_stop_color(index, rgba);
+                    true
+                }),
+            );
+            row.add_controller(drop_target);
+
+            let menu = color_format::copy_menu(index);
+            let click_gesture = gtk::GestureClick::builder()
+                .button(gdk::BUTTON_SECONDARY)
+                .build();
+            click_gesture.connect_pressed(
+                clone!(@weak row, @strong menu => move |gesture, _, x, y| {
+                    gesture.set_state(gtk::EventSequenceState::Claimed);
+                    popup_menu(&row, &menu, x, y);
+                }),
+            );
+            row.add_controller(click_gesture);
+            let long_press_gesture = gtk::GestureLongPress::new();
+            long_press_gesture.connect_pressed(
+                clone!(@weak row, @strong menu => move |gesture, x, y| {
+                    gesture.set_state(gtk::EventSequenceState::Claimed);
+                    popup_menu(&row, &menu, x, y);
+                }),
+            );
+            row.add_controller(long_press_gesture);
+
+            let position_entry = gtk::Entry::builder()
+                .text(
+                    stop.position
+                        .map(|position| gradient.format_position(position))
+                        .unwrap_or_default(),
+                )
+                .placeholder_text(gettext("Auto"))
+                .tooltip_text(if gradient.kind == GradientType::Conic {
+                    gettext("Position, in degrees or as a percentage")
+                } else {
+                    gettext("Position, as a percentage or in pixels along the preview")
+                })
+                .width_chars(7)
+                .max_width_chars(7)
+                .valign(gtk::Align::Center)
+                .build();
+            position_entry.connect_changed(clone!(@weak self as this => move |entry| {
+                if this.imp().syncing.get() {
+                    return;
+                }
+
+                let length = this.preview_line_length();
+                let position = this.edited().parse_position(&entry.text(), length);
+                match position {
+                    Some(position) => {
+                        entry.remove_css_class("error");
+                        this.edit_gradient(Edit::StopPosition(index), |gradient| {
+                            gradient.stops[index].position = position;
+                        });
+                    }
+                    None => entry.add_css_class("error"),
+                }
+            }));
+            position_entry.connect_activate(clone!(@weak self as this => move |entry| {
+                this.format_position_entry(entry, index);
+            }));
+            let focus_controller = gtk::EventControllerFocus::new();
+            focus_controller.connect_leave(
+                clone!(@weak self as this, @weak position_entry => move |_| {
+                    this.format_position_entry(&position_entry, index);
+                }),
+            );
+            position_entry.add_controller(focus_controller);
+            row.add_suffix(&position_entry);
+            position_entries.push(position_entry);
+
+            let link_button = gtk::ToggleButton::builder()
+                .icon_name("insert-link-symbolic")
+                .tooltip_text(gettext("Link Hue"))
+                .active(stop.linked)
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            link_button.connect_toggled(clone!(@weak self as this => move |button| {
+                if this.imp().syncing.get() {
+                    return;
+                }
+
+                let linked = button.is_active();
+                this.modify_gradient(|gradient| gradient.stops[index].linked = linked);
+            }));
+            row.add_suffix(&link_button);
+
+            let remove_button = gtk::Button::builder()
+                .icon_name("list-remove-symbolic")
+                .tooltip_text(gettext("Remove Color"))
+                .valign(gtk::Align::Center)
+                .sensitive(removable)
+                .css_classes(["flat"])
+                .build();
+            remove_button.connect_clicked(clone!(@weak self as this => move |_| {
+                this.remove_stop(index);
+            }));
+            row.add_suffix(&remove_button);
+
+            imp.colors_group.add(&row);
+            rows.push(row);
+        }
+
+        imp.stop_rows.replace(rows);
+        imp.stop_position_entries.replace(position_entries);
+    }
+
+    /// Copies the color of a stop as it shows, with the adjustments applied.
+    fn copy_stop_color(&self, index: usize, format: ColorFormat) {
+        let Some(stop) = self.gradient().stops.get(index).copied() else {
+            return;
+        };
+
+        self.copy_color(&format.format(&stop.color));
+    }
+
+    /// Shows the position of a stop as it was understood, in the unit of
+    /// the gradient type.
+    fn format_position_entry(&self, entry: &gtk::Entry, index: usize) {
+        let gradient = self.edited().clone();
+        let Some(stop) = gradient.stops.get(index) else {
+            return;
+        };
+
+        let imp = self.imp();
+        imp.syncing.set(true);
+        entry.set_text(
+            &stop
+                .position
+                .map(|position| gradient.format_position(position))
+                .unwrap_or_default(),
+        );
+        entry.remove_css_class("error");
+        imp.syncing.set(false);
+    }
+
+    /// Length of the preview's gradient line, which positions typed in
+    /// pixels refer to, the way the SVG export is sized.
+    fn preview_line_length(&self) -> Option<f32> {
+        let gradient_box = &self.imp().gradient_box;
+        self.edited().line_length(
+            gradient_box.width().max(1) as f32,
+            gradient_box.height().max(1) as f32,
+        )
+    }
+
+    fn set_wallpaper(&self) {
+        self.remember_colors();
+
+        let gradient = self.gradient();
+        let span = self.settings().boolean("wallpaper-span-monitors");
+        let display = self.display();
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let message = match wallpaper::set_wallpaper(&display, &gradient, span).await {
+                Ok(()) => gettext("Wallpaper set"),
+                Err(err) => {
+                    glib::g_warning!("vibrant", "Could not set wallpaper: {}", err);
+                    gettext("Could not set wallpaper")
+                }
+            };
+
+            this.imp().toast_overlay.add_toast(adw::Toast::new(&message));
+        }));
+    }
+
+    fn set_background_colors(&self) {
+        self.remember_colors();
+
+        let message = match wallpaper::set_background_colors(&self.gradient()) {
+            Ok(()) => gettext("Background colors set"),
+            Err(err) => {
+                glib::g_warning!("vibrant", "Could not set background colors: {}", err);
+                gettext("Could not set background colors")
+            }
+        };
+
+        self.imp()
+            .toast_overlay
+            .add_toast(adw::Toast::new(&message));
+    }
+
+    /// Installs a background slideshow moving through the skies of the day,
+    /// keeping the textures of the current gradient.
+    fn install_slideshow(&self) {
+        let current = self.gradient();
+        let slides: Vec<(Gradient, u32)> = TimeOfDay::ALL
+            .iter()
+            .map(|time| {
+                let gradient = Gradient {
+                    kind: GradientType::Linear,
+                    angle: 0.0,
+                    stops: time.colors().into_iter().map(ColorStop::new).collect(),
+                    hard_stops: false,
+                    ..current.clone()
+                };
+                (gradient, time.hours())
+            })
+            .collect();
+
+        let span = self.settings().boolean("wallpaper-span-monitors");
+        let (monitors, scale) = wallpaper::monitor_layout(&self.display());
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let title = gettext("Installing slideshow…");
+            let message = match job::run(&this.imp().toast_overlay, &title, move |progress| {
+                wallpaper::install_slideshow(&monitors, scale, &slides, span, progress)
+            })
+            .await
+            {
+                Ok(_) => gettext("Slideshow added to the backgrounds in Settings"),
+                Err(err) if err.matches(gio::IOErrorEnum::Cancelled) => return,
+                Err(err) => {
+                    glib::g_warning!("vibrant", "Could not install slideshow: {}", err);
+                    gettext("Could not install slideshow")
+                }
+            };
+
+            this.imp().toast_overlay.add_toast(adw::Toast::new(&message));
+        }));
+    }
+
+    /// Copies CSS showing the gradient as a small embedded PNG, for places
+    /// like emails where CSS gradients don't work. Browsers stretch it
+    /// smoothly, so a small tile is enough.
+    fn copy_data_uri(&self) {
+        self.remember_colors();
+
+        let gradient_box = self.imp().gradient_box.get();
+        let (width, height) = (
+            gradient_box.width().max(1) as f64,
+            gradient_box.height().max(1) as f64,
+        );
+        let scale = DATA_URI_SIZE / width.max(height);
+        let bounds = gdk::Rectangle::new(
+            0,
+            0,
+            (width * scale).round().max(1.0) as i32,
+            (height * scale).round().max(1.0) as i32,
+        );
+
+        let png = wallpaper::render_layout(&self.gradient(), &[bounds], 1, true)
+            .ok_or_else(|| glib::Error::new(gio::IOErrorEnum::Failed, "Could not render"))
+            .and_then(|texture| png::encode_srgb(&texture));
+        let png = match png {
+            Ok(png) => png,
+            Err(err) => {
+                glib::g_warning!("vibrant", "Could not create data URI: {}", err);
+                self.add_toast(adw::Toast::new(&gettext("Could not copy data URI")));
+                return;
+            }
+        };
+
+        let css = format!(
+            ".gradient-box {{\n  background-image: url(data:image/png;base64,{});\n  background-size: 100% 100%;\n}}",
+            glib::base64_encode(&png)
+        );
+        self.clipboard().set_text(&css);
+        self.add_toast(adw::Toast::new(&gettext("Copied data URI to clipboard")));
+    }
+
+    /// Copies an email block showing the gradient in Outlook too, at the size
+    /// of the preview.
+    fn copy_outlook_html(&self) {
+        self.remember_colors();
+
+        let gradient_box = self.imp().gradient_box.get();
+        let html = self.gradient().to_outlook_html(
+            gradient_box.width().max(1) as u32,
+            gradient_box.height().max(1) as u32,
+        );
+        self.clipboard().set_text(&html);
+        self.add_toast(adw::Toast::new(&gettext("Copied email HTML to clipboard")));
+    }
+
+    fn copy_snippet(&self, snippet: Snippet) {
+        self.remember_colors();
+
+        self.clipboard()
+            .set_text(&snippet.generate(&self.gradient()));
+        self.add_toast(adw::Toast::new(&gettext("Copied code to clipboard")));
+    }
+
+    /// Asks how many LEDs the strip has, then copies the gradient sampled
+    /// once for each of them.
+    fn copy_led_colors(&self) {
+        let count_row = adw::SpinRow::builder()
+            .title(gettext("LEDs"))
+            .adjustment(&gtk::Adjustment::new(
+                self.settings().uint("led-count") as f64,
+                1.0,
+                1500.0,
+                1.0,
+                10.0,
+                0.0,
+            ))
+            .build();
+
+        let list = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .css_classes(["boxed-list"])
+            .build();
+        list.append(&count_row);
+
+        let dialog = adw::MessageDialog::builder()
+            .transient_for(self)
+            .modal(true)
+            .heading(gettext("Copy for LED Strip"))
+            .body(gettext(
+                "Copy a color for each LED, as a WLED state or as plain RGB values",
+            ))
+            .extra_child(&list)
+            .default_response("wled")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[
+            ("cancel", &gettext("_Cancel")),
+            ("rgb", &gettext("Copy _RGB")),
+            ("wled", &gettext("Copy _WLED")),
+        ]);
+        dialog.set_response_appearance("wled", adw::ResponseAppearance::Suggested);
+
+        dialog.connect_response(
+            None,
+            clone!(@weak self as this, @weak count_row => move |_, response| {
+                if response == "cancel" {
+                    return;
+                }
+
+                let count = count_row.value() as u32;
+                let _ = this.settings().set_uint("led-count", count);
+
+                let colors = this.gradient().sample(count as usize);
+                let text = if response == "wled" {
+                    snippet::wled_state(&colors)
+                } else {
+                    snippet::led_array(&colors)
+                };
+                this.clipboard().set_text(&text);
+                this.add_toast(adw::Toast::new(&gettext("Copied LED colors to clipboard")));
+            }),
+        );
+
+        dialog.present();
+    }
+
+    fn remember_colors(&self) {
+        let stops = self.gradient().stops;
+
+        for stop in stops.iter().rev() {
+            self.add_recent_color(&color::to_hex(&stop.color));
+        }
+    }
+
+    fn add_recent_color(&self, color: &str) {
+        let color = color.trim();
+        let Ok(rgba) = gdk::RGBA::parse(color) else {
+            return;
+        };
+
+        let mut colors: Vec<String> = self
+            .settings()
+            .strv("recent-colors")
+            .iter()
+            .map(|recent| recent.to_string())
+            .filter(|recent| gdk::RGBA::parse(recent.as_str()).ok() != Some(rgba))
+            .collect();
+        colors.insert(0, color.to_owned());
+        colors.truncate(MAX_RECENT_COLORS);
+
+        if let Err(err) = self.settings().set_strv("recent-colors", colors.as_slice()) {
+            glib::g_warning!("vibrant", "Could not save recent colors: {}", err);
+        }
+    }
+
+    /// Shows the colors of the gradient, so other stops can reuse them.
+    fn update_gradient_colors(&self) {
+        let imp = self.imp();
+
+        let mut colors: Vec<String> = Vec::new();
+        for stop in &self.edited().stops {
+            let hex = color::to_hex(&stop.color);
+            if !colors.contains(&hex) {
+                colors.push(hex);
+            }
+        }
+
+        //rebuilding on every edit would drop the hovered swatch
+        if *imp.gradient_colors.borrow() == colors {
+            return;
+        }
+
+        while let Some(child) = imp.gradient_colors_box.first_child() {
+            imp.gradient_colors_box.remove(&child);
+        }
+        for color in &colors {
+            if let Some(button) = self.swatch_button(color) {
+                imp.gradient_colors_box.append(&button);
+            }
+        }
+
+        imp.gradient_colors.replace(colors);
+    }
+
+    fn update_collected_colors(&self) {
+        let imp = self.imp();
+
+        while let Some(child) = imp.collected_colors_box.first_child() {
+            imp.collected_colors_box.remove(&child);
+        }
+
+        let colors = imp.collected_colors.borrow();
+        for color in colors.iter() {
+            if let Some(button) = self.swatch_button(color) {
+                imp.collected_colors_box.append(&button);
+            }
+        }
+
+        let watching = imp.clipboard_handler.borrow().is_some();
+        imp.collected_colors_group
+            .set_visible(watching || !colors.is_empty());
+        self.set_action_enabled("use-collected-colors", !colors.is_empty());
+        self.set_action_enabled("clear-collected-colors", !colors.is_empty());
+    }
+
+    fn update_recent_colors(&self) {
+        let imp = self.imp();
+
+        while let Some(child) = imp.recent_colors_box.first_child() {
+            imp.recent_colors_box.remove(&child);
+        }
+
+        let colors = self.settings().strv("recent-colors");
+        for color in colors.iter() {
+            if let Some(button) = self.swatch_button(color.to_str()) {
+                imp.recent_colors_box.append(&button);
+            }
+        }
+
+        imp.recent_colors_group.set_visible(!colors.is_empty());
+    }
+
+    /// Creates a button showing `color` that applies it to the stop being
+    /// edited when clicked, and can be dragged onto any stop.
+    fn swatch_button(&self, color: &str) -> Option<gtk::Button> {
+        let rgba = gdk::RGBA::parse(color).ok()?;
+        let swatch = VibrantSwatch::new(&rgba);
+
+        let button = gtk::Button::builder()
+            .child(&swatch)
+            .tooltip_text(color)
+            .css_classes(["flat", "swatch-button"])
+            .build();
+
+        let color = color.to_owned();
+        button.connect_clicked(clone!(@weak self as this => move |_| {
+            this.apply_color(&color);
+        }));
+
+        let drag_source = gtk::DragSource::builder()
+            .actions(gdk::DragAction::COPY)
+            .content(&gdk::ContentProvider::for_value(&rgba.to_value()))
+            .build();
+        drag_source.connect_drag_begin(clone!(@weak swatch => move |source, _| {
+            source.set_icon(Some(&gtk::WidgetPaintable::new(Some(&swatch))), 0, 0);
+        }));
+        button.add_controller(drag_source);
+
+        Some(button)
+    }
+
+    fn setup_color_search(&self) {
+        let imp = self.imp();
+
+        for (name, hex) in color::CSS_NAMED_COLORS.iter().chain(color::GNOME_PALETTE) {
+            let Ok(rgba) = gdk::RGBA::parse(*hex) else {
+                continue;
+            };
+
+            let row = adw::ActionRow::builder()
+                .title(*name)
+                .subtitle(*hex)
+                .activatable(true)
+                .build();
+            row.add_prefix(&VibrantSwatch::new(&rgba));
+
+            row.connect_activated(clone!(@weak self as this => move |_| {
+                this.apply_color(hex);
+                this.imp().color_search_popover.popdown();
+            }));
+
+            imp.color_search_list.append(&row);
+        }
+
+        let entry = imp.color_search_entry.get();
+        let list = imp.color_search_list.get();
+
+        list.set_filter_func(clone!(@weak entry => @default-return true, move |row| {
+            let query = entry.text().to_lowercase().replace(' ', "");
+            let Some(row) = row.downcast_ref::<adw::ActionRow>() else {
+                return true;
+            };
+
+            row.title().to_lowercase().replace(' ', "").contains(&query)
+                || row.subtitle().is_some_and(|hex| hex.contains(&query))
+        }));
+
+        entry.connect_search_changed(clone!(@weak list => move |_| {
+            list.invalidate_filter();
+        }));
+
+        entry.connect_activate(clone!(@weak list => move |_| {
+            //apply the first match
+            let mut child = list.first_child();
+            while let Some(row) = child.and_downcast::<adw::ActionRow>() {
+                if row.is_child_visible() {
+                    ActionRowExt::activate(&row);
+                    break;
+                }
+                child = row.next_sibling();
+            }
+        }));
+    }
+
+    fn setup_gnome_palette(&self) {
+        let imp = self.imp();
+
+        //one column per hue, from the lightest to the darkest shade
+        for (index, (name, hex)) in color::GNOME_PALETTE.iter().enumerate() {
+            let Some(button) = self.swatch_button(hex) else {
+                continue;
+            };
+            button.set_tooltip_text(Some(&format!("{name} ({hex})")));
+
+            imp.gnome_palette_grid
+                .attach(&button, (index / 5) as i32, (index % 5) as i32, 1, 1);
+        }
+    }
+
+    fn selected_color(&self) -> Option<gdk::RGBA> {
+        let imp = self.imp();
+
+        self.edited()
+            .stops
+            .get(imp.selected_stop.get())
+            .map(|stop| stop.color)
+    }
+
+    /// Sets the color of the stop being edited.
+    fn apply_color(&self, color: &str) {
+        if let Ok(rgba) = gdk::RGBA::parse(color) {
+            self.set_stop_color(self.imp().selected_stop.get(), rgba);
+        }
+    }
+
+    #[template_callback]
+    fn new_palette(&self, _button: gtk::Button) {
+        let entry = gtk::Entry::builder()
+            .placeholder_text(gettext("Name"))
+            .activates_default(true)
+            .build();
+
+        let dialog = adw::MessageDialog::builder()
+            .transient_for(self)
+            .modal(true)
+            .heading(gettext("New Palette"))
+            .extra_child(&entry)
+            .default_response("create")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[
+            ("cancel", &gettext("_Cancel")),
+            ("create", &gettext("_Create")),
+        ]);
+        dialog.set_response_appearance("create", adw::ResponseAppearance::Suggested);
+
+        dialog.connect_response(
+            None,
+            clone!(@weak self as this, @weak entry => move |_, response| {
+                let name = entry.text().trim().to_owned();
+                if response != "create" || name.is_empty() {
+                    return;
+                }
+
+                this.modify_palettes(|palettes| {
+                    palettes.push(Palette {
+                        name,
+                        colors: Vec::new(),
+                    })
+                });
+            }),
+        );
+
+        dialog.present();
+    }
+
+    #[template_callback]
+    fn import_palette(&self, _button: gtk::Button) {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(&gettext("Palettes")));
+        filter.add_suffix("gpl");
+        filter.add_suffix("ase");
+
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        filters.append(&filter);
+
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Import Palette"))
+            .modal(true)
+            .filters(&filters)
+            .build();
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let Ok(file) = dialog.open_future(Some(&this)).await else {
+                return;
+            };
+
+            match palette::import(&file).await {
+                Ok(palette) => {
+                    this.modify_palettes(|palettes| palettes.push(palette));
+                }
+                Err(err) => {
+                    glib::g_warning!("vibrant", "Could not import palette: {}", err);
+                    this.imp()
+                        .toast_overlay
+                        .add_toast(adw::Toast::new(&gettext("Could not import palette")));
+                }
+            }
+        }));
+    }
+
+    fn modify_palettes(&self, f: impl FnOnce(&mut Vec<Palette>)) {
+        let imp = self.imp();
+
+        f(&mut imp.palettes.borrow_mut());
+        if let Err(err) = palette::save(&imp.palettes.borrow()) {
+            glib::g_warning!("vibrant", "Could not save palettes: {}", err);
+        }
+
+        self.update_palettes();
+        self.sync_library();
+    }
+
+    /// Brings the saved palettes and the sync folder up to date with each
+    /// other, when one is set.
+    fn sync_library(&self) {
+        let folder = self.settings().string("sync-folder");
+        if folder.is_empty() {
+            return;
+        }
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let settings = this.settings();
+            let last_sync = settings.int64("sync-time");
+
+            match sync::sync(&gio::File::for_uri(&folder), last_sync).await {
+                Ok(outcome) => {
+                    if let Ok(now) = glib::DateTime::now_utc() {
+                        let _ = settings.set_int64("sync-time", now.to_unix());
+                    }
+                    if outcome.downloaded {
+                        this.imp().palettes.replace(palette::load());
+                        this.update_palettes();
+                    }
+                    if outcome.conflict {
+                        this.imp().toast_overlay.add_toast(adw::Toast::new(&gettext(
+                            "Palettes also changed on another computer, kept the newest changes",
+                        )));
+                    }
+                }
+                Err(err) => {
+                    glib::g_warning!("vibrant", "Could not sync the library: {}", err);
+                    this.imp()
+                        .toast_overlay
+                        .add_toast(adw::Toast::new(&gettext("Could not sync palettes")));
+                }
+            }
+        }));
+    }
+
+    fn add_palette_color(&self, index: usize) {
+        let Some(color) = self.selected_color().as_ref().map(color::to_hex) else {
+            return;
+        };
+
+        self.modify_palettes(|palettes| {
+            if let Some(palette) = palettes.get_mut(index) {
+                if !palette.colors.contains(&color) {
+                    palette.colors.push(color);
+                }
+            }
+        });
+    }
+
+    fn update_palettes(&self) {
+        let imp = self.imp();
+
+        for row in imp.palette_rows.take() {
+            imp.palettes_group.remove(&row);
+        }
+
+        let brand = self.settings().string("brand-palette");
+        let locked = imp
+            .palettes
+            .borrow()
+            .iter()
+            .any(|palette| !brand.is_empty() && palette.name == brand);
+        imp.colors_group.set_description(
+            locked
+                .then(|| {
+                    gettext("Only the colors of the “{}” palette can be used").replace("{}", &brand)
+                })
+                .as_deref(),
+        );
+
+        let mut rows = Vec::new();
+        for (index, palette) in imp.palettes.borrow().iter().enumerate() {
+            let count = palette.colors.len() as u32;
+            let row = adw::ExpanderRow::builder()
+                .title(&palette.name)
+                .subtitle(
+                    ngettext("{} color", "{} colors", count).replace("{}", &count.to_string()),
+                )
+                .build();
+
+            let colors_box = gtk::FlowBox::builder()
+                .selection_mode(gtk::SelectionMode::None)
+                .max_children_per_line(12)
+                .column_spacing(6)
+                .row_spacing(6)
+                .margin_top(6)
+                .margin_bottom(6)
+                .margin_start(6)
+                .margin_end(6)
+                .build();
+
+            for (color_index, color) in palette.colors.iter().enumerate() {
+                let Some(button) = self.swatch_button(color) else {
+                    continue;
+                };
+
+                //secondary click removes the color from the palette
+                let gesture = gtk::GestureClick::builder()
+                    .button(gdk::BUTTON_SECONDARY)
+                    .build();
+                gesture.connect_pressed(clone!(@weak self as this => move |_, _, _, _| {
+                    this.modify_palettes(|palettes| {
+                        palettes[index].colors.remove(color_index);
+                    });
+                }));
+                button.add_controller(gesture);
+
+                colors_box.append(&button);
+            }
+
+            let add_button = gtk::Button::builder()
+                .icon_name("list-add-symbolic")
+                .tooltip_text(gettext("Add Current Color"))
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            add_button.connect_clicked(clone!(@weak self as this => move |_| {
+                this.add_palette_color(index);
+            }));
+            colors_box.append(&add_button);
+
+            row.add_row(&colors_box);
+
+            let lock_button = gtk::ToggleButton::builder()
+                .icon_name("changes-prevent-symbolic")
+                .tooltip_text(gettext("Lock Colors to This Palette"))
+                .valign(gtk::Align::Center)
+                .active(palette.name == brand)
+                .css_classes(["flat"])
+                .build();
+            let name = palette.name.clone();
+            lock_button.connect_toggled(clone!(@weak self as this => move |button| {
+                let brand = if button.is_active() { name.as_str() } else { "" };
+                let _ = this.settings().set_string("brand-palette", brand);
+            }));
+            row.add_suffix(&lock_button);
+
+            let delete_button = gtk::Button::builder()
+                .icon_name("user-trash-symbolic")
+                .tooltip_text(gettext("Delete Palette"))
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            delete_button.connect_clicked(clone!(@weak self as this => move |_| {
+                this.modify_palettes(|palettes| {
+                    palettes.remove(index);
+                });
+            }));
+            row.add_suffix(&delete_button);
+
+            imp.palettes_group.add(&row);
+            rows.push(row);
+        }
+
+        imp.palette_rows.replace(rows);
+    }
+
+    /// The edited variant as exported, with adjustments applied.
+    fn gradient(&self) -> Gradient {
+        self.edited().adjusted()
+    }
+
+    /// Shows the start page in place of the preview until a new document
+    /// gets its first colors.
+    fn update_start_page(&self) {
+        let imp = self.imp();
+
+        imp.started.set(imp.started.get() || !self.is_pristine());
+        let page = if imp.started.get() { "editor" } else { "start" };
+        imp.content_stack.set_visible_child_name(page);
+    }
+
+    /// Lists the shared gradients on the start page when the user opted in,
+    /// from the cache first and then from the feed once downloaded.
+    fn update_community(&self) {
+        let settings = self.settings();
+        if !settings.boolean("community-feed") {
+            self.show_community(&[]);
+            return;
+        }
+
+        self.show_community(&community::cached());
+
+        let url = settings.string("community-feed-url");
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            match community::fetch(&url).await {
+                Ok(entries) => {
+                    if this.settings().boolean("community-feed") {
+                        this.show_community(&entries);
+                        if entries.is_empty() {
+                            this.imp()
+                                .community_group
+                                .set_description(Some(&gettext("No gradients have been shared yet")));
+                        }
+                    }
+                }
+                Err(err) => {
+                    glib::g_warning!("vibrant", "Could not download shared gradients: {}", err);
+                    this.imp()
+                        .community_group
+                        .set_description(Some(&gettext("Offline, showing the gradients downloaded before")));
+                }
+            }
+        }));
+    }
+
+    fn show_community(&self, entries: &[community::Entry]) {
+        let imp = self.imp();
+
+        for row in imp.community_rows.take() {
+            imp.community_group.remove(&row);
+        }
+        imp.community_group.set_description(None);
+
+        let mut rows = Vec::new();
+        for entry in entries {
+            let row = adw::ActionRow::builder()
+                .title(glib::markup_escape_text(&entry.document.metadata.name))
+                .subtitle(glib::markup_escape_text(&entry.author))
+                .activatable(true)
+                .build();
+            row.add_suffix(&gtk::Image::from_icon_name("go-next-symbolic"));
+
+            let thumbnail = gtk::Picture::builder()
+                .width_request(thumbnail::SIZE)
+                .height_request(thumbnail::SIZE)
+                .valign(gtk::Align::Center)
+                .overflow(gtk::Overflow::Hidden)
+                .css_classes(["thumbnail"])
+                .build();
+            thumbnail.set_paintable(
+                thumbnail::load(&entry.document.gradient, self.scale_factor()).as_ref(),
+            );
+            row.add_prefix(&thumbnail);
+
+            let gradient = entry.document.gradient.clone();
+            row.connect_activated(clone!(@weak self as this => move |_| {
+                let gradient = gradient.clone();
+                this.modify_variants(|edited| *edited = gradient);
+                this.imp().selected_stop.set(0);
+            }));
+
+            imp.community_group.add(&row);
+            rows.push(row);
+        }
+
+        imp.community_rows.replace(rows);
+    }
+
+    /// Uploads the gradient to the community gallery, after telling the
+    /// user what gets published where and waiting for them to agree.
+    fn share_to_gallery(&self) {
+        let settings = self.settings();
+        let url = settings.string("community-submit-url");
+        let Some(host) = glib::Uri::parse(&url, glib::UriFlags::NONE)
+            .ok()
+            .and_then(|uri| uri.host())
+        else {
+            self.imp().toast_overlay.add_toast(adw::Toast::new(&gettext(
+                "Set a gallery submission address in Preferences first",
+            )));
+            return;
+        };
+
+        let name = self.document_name();
+        let author = settings.string("community-author");
+        let credited = settings.boolean("community-credit") && !author.is_empty();
+        let body = if credited {
+            gettext("“{name}” will be published on {host} credited to {author}, for anyone to see and reuse. It can't be taken back from this app.")
+                .replace("{author}", &author)
+        } else {
+            gettext("“{name}” will be published on {host} without an author, for anyone to see and reuse. It can't be taken back from this app.")
+        }
+        .replace("{name}", &name)
+        .replace("{host}", &host);
+
+        let dialog = adw::MessageDialog::builder()
+            .transient_for(self)
+            .modal(true)
+            .heading(gettext("Share to Gallery?"))
+            .body(body)
+            .default_response("cancel")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[
+            ("cancel", &gettext("_Cancel")),
+            ("share", &gettext("_Share")),
+        ]);
+        dialog.set_response_appearance("share", adw::ResponseAppearance::Suggested);
+
+        let entry = community::Entry {
+            document: Document {
+                gradient: self.imp().gradient.borrow().clone(),
+                metadata: Metadata {
+                    name,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            author: if credited {
+                author.into()
+            } else {
+                String::new()
+            },
+        };
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            if dialog.choose_future().await != "share" {
+                return;
+            }
+
+            match community::submit(&url, &entry).await {
+                Ok(()) => this
+                    .imp()
+                    .toast_overlay
+                    .add_toast(adw::Toast::new(&gettext("Shared to the gallery"))),
+                Err(err) => {
+                    glib::g_warning!("vibrant", "Could not share the gradient: {}", err);
+                    this.imp()
+                        .toast_overlay
+                        .add_toast(adw::Toast::new(&gettext("Could not share the gradient")));
+                }
+            }
+        }));
+    }
+
+    fn css_options(&self) -> CssOptions {
+        CssOptions {
+            style: CodeStyle::load("css"),
+            angle_keywords: self.settings().boolean("css-angle-keywords"),
+            corner_keywords: self.settings().boolean("css-corner-keywords"),
+            legacy_fallbacks: self.settings().boolean("css-legacy-fallbacks"),
+        }
+    }
+
+    fn generate_css(&self) -> String {
+        let gradient = self.imp().gradient.borrow();
+        let options = self.css_options();
+
+        if self.settings().boolean("wide-gamut-css") {
+            gradient.to_wide_gamut_stylesheet(".gradient-box", &options)
+        } else {
+            gradient.adjusted().to_stylesheet(".gradient-box", &options)
+        }
+    }
+
+    /// Refreshes the previews on the next frame, so continuous edits like
+    /// dragging a color wheel reload the styles once per frame at most.
+    fn queue_preview_update(&self) {
+        let imp = self.imp();
+
+        if imp.preview_queued.replace(true) {
+            return;
+        }
+
+        imp.gradient_box.add_tick_callback(
+            clone!(@weak self as win => @default-return glib::ControlFlow::Break, move |_, _| {
+                win.imp().preview_queued.set(false);
+                win.update_preview();
+                glib::ControlFlow::Break
+            }),
+        );
+    }
+
+    fn update_preview(&self) {
+        let imp = self.imp();
+
+        //the other variant is previewed next to the edited one
+        let gradient = imp.gradient.borrow().adjusted();
+        let editing_dark = imp.editing_dark.get();
+        //the grain is drawn by the layers on top instead of as a CSS image
+        let print_preview = imp.print_preview.get();
+        let background = |dark: bool| {
+            let mut variant = gradient.variant(dark).clone();
+            variant.grain = 0.0;
+            if print_preview {
+                for stop in &mut variant.stops {
+                    stop.color = print::simulate(&stop.color);
+                }
+            }
+            variant.to_background()
+        };
+        let zoom = imp
+            .preview_zoom
+            .get()
+            .to_css(
+                imp.gradient_box.width() as f64,
+                imp.gradient_box.height() as f64,
+            )
+            .unwrap_or_default();
+        let css = format!(
+            ".gradient-box {{background: {};{zoom}}}\n.paired-gradient-box {{background: {};}}",
+            background(editing_dark),
+            background(!editing_dark)
+        );
+
+        //reparsing restyles both previews even when nothing changed
+        if *imp.preview_css.borrow() != css {
+            imp.preview_provider.load_from_data(&css);
+            imp.preview_css.replace(css);
+        }
+
+        imp.grain_layer
+            .set_amount(gradient.variant(editing_dark).grain);
+        imp.handle_layer
+            .set_gradient(gradient.variant(editing_dark).clone());
+        imp.paired_grain_layer
+            .set_amount(gradient.variant(!editing_dark).grain);
+
+        imp.gradient_box
+            .update_property(&[gtk::accessible::Property::Description(&describe_gradient(
+                gradient.variant(editing_dark),
+            ))]);
+        imp.paired_gradient_box
+            .update_property(&[gtk::accessible::Property::Description(&describe_gradient(
+                gradient.variant(!editing_dark),
+            ))]);
+
+        //warn about the top of the preview, while the band isn't fixing it
+        let gradient = self.gradient();
+        let bounds = graphene::Rect::new(
+            0.0,
+            0.0,
+            imp.gradient_box.width().max(1) as f32,
+            imp.gradient_box.height().max(1) as f32,
+        );
+        imp.readability_warning
+            .set_visible(!gradient.readable_top && !readability::is_readable(&gradient, &bounds));
+
+        let out_of_gamut = self.edited().out_of_gamut_stops();
+        imp.gamut_warning.set_visible(out_of_gamut > 0);
+        imp.gamut_warning.set_tooltip_text(Some(
+            &ngettext(
+                "The adjustments push {} color outside sRGB",
+                "The adjustments push {} colors outside sRGB",
+                out_of_gamut as u32,
+            )
+            .replace("{}", &out_of_gamut.to_string()),
+        ));
+
+        self.update_stats(&gradient);
+        self.update_gradient_colors();
+    }
+
+    fn update_gradient(&self) {
+        let imp = self.imp();
+
+        self.queue_preview_update();
+        self.update_title();
+        self.update_start_page();
+        self.set_action_enabled("undo", !imp.undo_stack.borrow().is_empty());
+        self.set_action_enabled("redo", !imp.redo_stack.borrow().is_empty());
     }
 }
@@ -0,0 +1,505 @@
+/* export.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gettextrs::gettext;
+use glib::clone;
+
+use gtk::prelude::*;
+use gtk::{gdk, gio, glib};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use crate::color;
+use crate::icc::ColorSpace;
+use crate::job;
+use crate::material;
+use crate::palette::{self, Palette};
+use crate::pdf;
+use crate::plymouth;
+use crate::png;
+use crate::report;
+use crate::tiff;
+use crate::tokens;
+use crate::wallpaper;
+
+use super::VibrantWindow;
+
+/// Default width of exported PDF pages, in millimeters, that of an A4 page.
+const PDF_WIDTH: f64 = 210.0;
+/// Longest side of exported TIFF ramps, in pixels.
+const TIFF_SIZE: f64 = 2048.0;
+/// Longest side of exported PNG images, in pixels.
+const PNG_SIZE: f64 = 2048.0;
+
+impl VibrantWindow {
+    /// Asks how many colors to sample and how to space them, then writes
+    /// them as a palette file.
+    pub(super) fn export_palette(&self) {
+        let settings = self.settings();
+
+        let count_row = adw::SpinRow::builder()
+            .title(gettext("Colors"))
+            .adjustment(&gtk::Adjustment::new(
+                settings.uint("palette-export-count") as f64,
+                2.0,
+                256.0,
+                1.0,
+                10.0,
+                0.0,
+            ))
+            .build();
+        let perceptual_row = adw::SwitchRow::builder()
+            .title(gettext("Perceptual Spacing"))
+            .subtitle(gettext("Space the colors by how different they look"))
+            .active(settings.boolean("palette-export-perceptual"))
+            .build();
+
+        let list = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .css_classes(["boxed-list"])
+            .build();
+        list.append(&count_row);
+        list.append(&perceptual_row);
+
+        let dialog = adw::MessageDialog::builder()
+            .transient_for(self)
+            .modal(true)
+            .heading(gettext("Export as Palette"))
+            .body(gettext(
+                "Sample colors along the gradient, as a scale for charts and maps",
+            ))
+            .extra_child(&list)
+            .default_response("export")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[
+            ("cancel", &gettext("_Cancel")),
+            ("export", &gettext("_Export…")),
+        ]);
+        dialog.set_response_appearance("export", adw::ResponseAppearance::Suggested);
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            if dialog.choose_future().await != "export" {
+                return;
+            }
+
+            let count = count_row.value() as u32;
+            let perceptual = perceptual_row.is_active();
+            let settings = this.settings();
+            let _ = settings.set_uint("palette-export-count", count);
+            let _ = settings.set_boolean("palette-export-perceptual", perceptual);
+
+            let gradient = this.gradient();
+            let colors = if perceptual {
+                gradient.sample_perceptual(count as usize)
+            } else {
+                gradient.sample(count as usize)
+            };
+            let palette = Palette {
+                name: this.document_name(),
+                colors: colors.iter().map(color::to_hex).collect(),
+            };
+
+            let filters = gio::ListStore::new::<gtk::FileFilter>();
+            for (name, suffix) in [
+                (gettext("GIMP Palettes"), "gpl"),
+                (gettext("Adobe Swatch Exchange"), "ase"),
+                (gettext("JSON"), "json"),
+            ] {
+                let filter = gtk::FileFilter::new();
+                filter.set_name(Some(&name));
+                filter.add_suffix(suffix);
+                filters.append(&filter);
+            }
+
+            let file_dialog = gtk::FileDialog::builder()
+                .title(gettext("Export as Palette"))
+                .modal(true)
+                .filters(&filters)
+                .initial_name(format!("{}.gpl", palette.name))
+                .build();
+            let Ok(file) = file_dialog.save_future(Some(&this)).await else {
+                return;
+            };
+
+            let message = match palette::export(&palette, &file).await {
+                Ok(()) => gettext("Exported palette"),
+                Err(err) => {
+                    glib::g_warning!("vibrant", "Could not export palette: {}", err);
+                    gettext("Could not export palette")
+                }
+            };
+            this.add_toast(adw::Toast::new(&message));
+        }));
+    }
+
+    pub(super) fn export_svg(&self) {
+        //export at the size of the preview, the image scales freely anyway
+        let gradient_box = self.imp().gradient_box.get();
+        let svg = self.gradient().to_svg(
+            gradient_box.width().max(1) as f32,
+            gradient_box.height().max(1) as f32,
+        );
+
+        self.export_text(&gettext("Export SVG"), &gettext("SVG Images"), "svg", svg);
+    }
+
+    pub(super) fn export_material_theme(&self) {
+        let theme = material::theme(&self.gradient());
+
+        self.export_text(
+            &gettext("Export Material Theme"),
+            &gettext("JSON Files"),
+            "json",
+            theme,
+        );
+    }
+
+    /// Exports every gradient of the collection, or the only one, as
+    /// design tokens named after them.
+    pub(super) fn export_design_tokens(&self) {
+        let collection = self.current_collection();
+        let gradients = if collection.members.is_empty() {
+            vec![(self.document_name(), self.gradient())]
+        } else {
+            collection
+                .members
+                .into_iter()
+                .map(|member| (member.name, member.gradient.adjusted()))
+                .collect()
+        };
+
+        self.export_text(
+            &gettext("Export Design Tokens"),
+            &gettext("JSON Files"),
+            "json",
+            tokens::design_tokens(&gradients),
+        );
+    }
+
+    pub(super) fn export_report(&self) {
+        let report = report::markdown(&self.gradient());
+
+        self.export_text(
+            &gettext("Export Accessibility Report"),
+            &gettext("Markdown Documents"),
+            "md",
+            report,
+        );
+    }
+
+    /// Asks where to save a text export of the gradient, then writes it.
+    fn export_text(&self, title: &str, filter_name: &str, suffix: &str, text: String) {
+        self.remember_colors();
+        self.save_text(title, filter_name, suffix, text);
+    }
+
+    /// Asks where to save `text`, then writes it.
+    pub(super) fn save_text(&self, title: &str, filter_name: &str, suffix: &str, text: String) {
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(filter_name));
+        filter.add_suffix(suffix);
+
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        filters.append(&filter);
+
+        let dialog = gtk::FileDialog::builder()
+            .title(title)
+            .modal(true)
+            .filters(&filters)
+            .initial_name(format!("{}.{suffix}", gettext("gradient")))
+            .build();
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let Ok(file) = dialog.save_future(Some(&this)).await else {
+                return;
+            };
+
+            let result = file
+                .replace_contents_future(text, None, false, gio::FileCreateFlags::REPLACE_DESTINATION)
+                .await;
+
+            let message = match result {
+                Ok(_) => gettext("Exported gradient"),
+                Err((_, err)) => {
+                    glib::g_warning!("vibrant", "Could not export gradient: {}", err);
+                    gettext("Could not export gradient")
+                }
+            };
+            this.imp().toast_overlay.add_toast(adw::Toast::new(&message));
+        }));
+    }
+
+    /// Asks for a page size, defaulting to the width of an A4 page and the
+    /// shape of the preview, then exports a PDF of it.
+    pub(super) fn export_pdf(&self) {
+        let gradient_box = self.imp().gradient_box.get();
+        let aspect = gradient_box.height().max(1) as f64 / gradient_box.width().max(1) as f64;
+
+        let size_row = |title: String, value: f64| {
+            adw::SpinRow::builder()
+                .title(title)
+                .subtitle(gettext("In millimeters"))
+                .adjustment(&gtk::Adjustment::new(value, 1.0, 5000.0, 1.0, 10.0, 0.0))
+                .build()
+        };
+        let width_row = size_row(gettext("Width"), PDF_WIDTH);
+        let height_row = size_row(gettext("Height"), (PDF_WIDTH * aspect).round());
+
+        let list = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .css_classes(["boxed-list"])
+            .build();
+        list.append(&width_row);
+        list.append(&height_row);
+
+        let dialog = adw::MessageDialog::builder()
+            .transient_for(self)
+            .modal(true)
+            .heading(gettext("Export PDF"))
+            .body(gettext("The page is filled with the gradient"))
+            .extra_child(&list)
+            .default_response("export")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[
+            ("cancel", &gettext("_Cancel")),
+            ("export", &gettext("_Export…")),
+        ]);
+        dialog.set_response_appearance("export", adw::ResponseAppearance::Suggested);
+
+        dialog.connect_response(
+            None,
+            clone!(@weak self as this, @weak width_row, @weak height_row => move |_, response| {
+                if response == "export" {
+                    this.save_pdf(width_row.value(), height_row.value());
+                }
+            }),
+        );
+
+        dialog.present();
+    }
+
+    fn save_pdf(&self, width: f64, height: f64) {
+        self.remember_colors();
+
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(&gettext("PDF Documents")));
+        filter.add_suffix("pdf");
+
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        filters.append(&filter);
+
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Export PDF"))
+            .modal(true)
+            .filters(&filters)
+            .initial_name(format!("{}.pdf", gettext("gradient")))
+            .build();
+
+        let gradient = self.gradient();
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let Ok(file) = dialog.save_future(Some(&this)).await else {
+                return;
+            };
+
+            let result = match pdf::render(&gradient, width, height) {
+                Ok(pdf) => file
+                    .replace_contents_future(pdf, None, false, gio::FileCreateFlags::REPLACE_DESTINATION)
+                    .await
+                    .map(|_| ())
+                    .map_err(|(_, err)| err),
+                Err(err) => Err(err),
+            };
+
+            let message = match result {
+                Ok(_) => gettext("Exported gradient"),
+                Err(err) => {
+                    glib::g_warning!("vibrant", "Could not export gradient: {}", err);
+                    gettext("Could not export gradient")
+                }
+            };
+            this.imp().toast_overlay.add_toast(adw::Toast::new(&message));
+        }));
+    }
+
+    /// Exports the gradient alone as a 16 bit ramp, in the shape of the
+    /// preview.
+    pub(super) fn export_tiff(&self) {
+        self.remember_colors();
+
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(&gettext("TIFF Images")));
+        filter.add_suffix("tif");
+        filter.add_suffix("tiff");
+
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        filters.append(&filter);
+
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Export TIFF"))
+            .modal(true)
+            .filters(&filters)
+            .initial_name(format!("{}.tif", gettext("gradient")))
+            .build();
+
+        let gradient_box = self.imp().gradient_box.get();
+        let (width, height) = (
+            gradient_box.width().max(1) as f64,
+            gradient_box.height().max(1) as f64,
+        );
+        let scale = TIFF_SIZE / width.max(height);
+        let width = (width * scale).round().max(1.0) as u32;
+        let height = (height * scale).round().max(1.0) as u32;
+        let gradient = self.gradient();
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let Ok(file) = dialog.save_future(Some(&this)).await else {
+                return;
+            };
+
+            let title = gettext("Exporting TIFF…");
+            let result = match job::run(&this.imp().toast_overlay, &title, move |progress| {
+                let samples = tiff::samples(&gradient, width, height, progress)?;
+                Ok(tiff::encode(samples, width, height))
+            })
+            .await
+            {
+                Ok(tiff) => file
+                    .replace_contents_future(tiff, None, false, gio::FileCreateFlags::REPLACE_DESTINATION)
+                    .await
+                    .map(|_| ())
+                    .map_err(|(_, err)| err),
+                Err(err) if err.matches(gio::IOErrorEnum::Cancelled) => return,
+                Err(err) => Err(err),
+            };
+
+            let message = match result {
+                Ok(_) => gettext("Exported gradient"),
+                Err(err) => {
+                    glib::g_warning!("vibrant", "Could not export gradient: {}", err);
+                    gettext("Could not export gradient")
+                }
+            };
+            this.imp().toast_overlay.add_toast(adw::Toast::new(&message));
+        }));
+    }
+
+    /// Exports the preview with its layers as a PNG in `space`, tagged with
+    /// its profile. Display P3 keeps the colors the adjustments push outside
+    /// sRGB.
+    pub(super) fn export_png(&self, space: ColorSpace) {
+        self.remember_colors();
+
+        let filter = gtk::FileFilter::new();
+        filter.set_name(Some(&gettext("PNG Images")));
+        filter.add_mime_type("image/png");
+
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        filters.append(&filter);
+
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Export PNG"))
+            .modal(true)
+            .filters(&filters)
+            .initial_name(format!("{}.png", gettext("gradient")))
+            .build();
+
+        let gradient_box = self.imp().gradient_box.get();
+        let (width, height) = (
+            gradient_box.width().max(1) as f64,
+            gradient_box.height().max(1) as f64,
+        );
+        let scale = PNG_SIZE / width.max(height);
+        let bounds = gdk::Rectangle::new(
+            0,
+            0,
+            (width * scale).round().max(1.0) as i32,
+            (height * scale).round().max(1.0) as i32,
+        );
+        let gradient = match space {
+            ColorSpace::Srgb => self.gradient(),
+            ColorSpace::DisplayP3 => self.edited().to_display_p3().adjusted(),
+        };
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let Ok(file) = dialog.save_future(Some(&this)).await else {
+                return;
+            };
+
+            let title = gettext("Exporting PNG…");
+            let result = match job::run(&this.imp().toast_overlay, &title, move |_| {
+                let texture = wallpaper::render_layout(&gradient, &[bounds], 1, true)
+                    .ok_or_else(|| glib::Error::new(gio::IOErrorEnum::Failed, "Nothing to draw"))?;
+                png::encode(&texture, space)
+            })
+            .await
+            {
+                Ok(png) => file
+                    .replace_contents_future(png, None, false, gio::FileCreateFlags::REPLACE_DESTINATION)
+                    .await
+                    .map(|_| ())
+                    .map_err(|(_, err)| err),
+                Err(err) if err.matches(gio::IOErrorEnum::Cancelled) => return,
+                Err(err) => Err(err),
+            };
+
+            let message = match result {
+                Ok(_) => gettext("Exported gradient"),
+                Err(err) => {
+                    glib::g_warning!("vibrant", "Could not export gradient: {}", err);
+                    gettext("Could not export gradient")
+                }
+            };
+            this.imp().toast_overlay.add_toast(adw::Toast::new(&message));
+        }));
+    }
+
+    pub(super) fn export_plymouth(&self) {
+        self.remember_colors();
+
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Export Boot Splash"))
+            .modal(true)
+            .build();
+        let gradient = self.gradient();
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let Ok(folder) = dialog.select_folder_future(Some(&this)).await else {
+                return;
+            };
+            let Some(path) = folder.path() else {
+                return;
+            };
+
+            let message = match plymouth::export(&gradient, &path) {
+                Ok(dir) => gettext("Boot splash exported to {}, copy it to /usr/share/plymouth/themes")
+                    .replace("{}", &dir.display().to_string()),
+                Err(err) => {
+                    glib::g_warning!("vibrant", "Could not export boot splash: {}", err);
+                    gettext("Could not export boot splash")
+                }
+            };
+            this.imp().toast_overlay.add_toast(adw::Toast::new(&message));
+        }));
+    }
+}
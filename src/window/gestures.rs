@@ -0,0 +1,350 @@
+/* gestures.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gettextrs::{gettext, pgettext};
+use glib::clone;
+
+use gtk::prelude::*;
+use gtk::{gdk, gio, glib, graphene};
+
+use adw::subclass::prelude::*;
+
+use crate::color;
+use crate::color_format::ColorFormat;
+use crate::gradient::GradientType;
+use crate::handles::{self, Handle, Zoom};
+use crate::precision::{self, Nudge, Precision};
+
+use super::{popup_menu, Edit, VibrantWindow};
+
+impl VibrantWindow {
+    /// Lets the preview edit the gradient directly: dragging the handle of
+    /// the angle points the gradient toward the pointer, finger or stylus,
+    /// dragging a stop moves it, and rotating two fingers turns it. Handles
+    /// reach further than they are drawn, so they're easy to hit by touch.
+    /// Holding Shift fine tunes the angle and Ctrl snaps angles and
+    /// positions. Pinching zooms the preview in.
+    pub(super) fn setup_preview_gestures(&self) {
+        let imp = self.imp();
+
+        let drag_gesture = gtk::GestureDrag::new();
+        drag_gesture.connect_drag_begin(clone!(@weak self as this => move |gesture, x, y| {
+            let imp = this.imp();
+            let Some(handle) = this.handle_at(x, y) else {
+                gesture.set_state(gtk::EventSequenceState::Denied);
+                return;
+            };
+
+            //a tap leaves the handle alone, dragging waits for the pointer to move
+            imp.dragged_handle.set(Some(handle));
+            imp.drag_turning.set(false);
+        }));
+        drag_gesture.connect_drag_update(clone!(@weak self as this => move |gesture, dx, dy| {
+            let imp = this.imp();
+            let Some((x, y)) = gesture.start_point() else {
+                return;
+            };
+
+            let Some(handle) = imp.dragged_handle.get() else {
+                return;
+            };
+            if !imp.drag_turning.get() {
+                let threshold = WidgetExt::settings(&this).gtk_dnd_drag_threshold() as f64;
+                if dx.hypot(dy) < threshold {
+                    return;
+                }
+
+                //claiming also keeps the window from being dragged around
+                gesture.set_state(gtk::EventSequenceState::Claimed);
+                imp.drag_turning.set(true);
+                if let Some(start) = this.preview_angle_at(x, y) {
+                    imp.drag_angle.set((start, this.edited().angle as f64));
+                }
+            }
+
+            let precision = Precision::from_state(gesture.current_event_state());
+            if let Handle::Stop(index) = handle {
+                this.drag_stop(index, x + dx, y + dy, precision);
+                return;
+            }
+            let Some(pointer) = this.preview_angle_at(x + dx, y + dy) else {
+                return;
+            };
+
+            let angle = if precision == Precision::Fine {
+                let (last, angle) = imp.drag_angle.get();
+                //the shortest way around, so crossing the top doesn't jump
+                let delta = (pointer - last + 540.0).rem_euclid(360.0) - 180.0;
+                angle + precision.scale(delta)
+            } else {
+                pointer
+            };
+            this.turn_preview(pointer, angle, precision);
+        }));
+        imp.gradient_box.add_controller(drag_gesture.clone());
+
+        //a probe showing the exact color under the pointer
+        imp.gradient_box.connect_query_tooltip(
+            clone!(@weak self as this => @default-return false, move |_, x, y, _, tooltip| {
+                let (rgba, offset) = this.color_at_preview(x as f64, y as f64);
+                tooltip.set_text(Some(
+                    &gettext("{color} at {position}")
+                        .replace("{color}", &color::to_hex(&rgba))
+                        .replace("{position}", &this.edited().format_position(offset)),
+                ));
+                true
+            }),
+        );
+
+        let click_gesture = gtk::GestureClick::builder()
+            .button(gdk::BUTTON_SECONDARY)
+            .build();
+        click_gesture.connect_pressed(clone!(@weak self as this => move |gesture, _, x, y| {
+            gesture.set_state(gtk::EventSequenceState::Claimed);
+            this.popup_preview_menu(x, y);
+        }));
+        imp.gradient_box.add_controller(click_gesture);
+
+        //the same keys as the angle row, for turning the preview itself
+        let key_controller = gtk::EventControllerKey::new();
+        key_controller.connect_key_pressed(
+            clone!(@weak self as this => @default-return glib::Propagation::Proceed, move |_, key, _, state| {
+                //leaves Ctrl+Page Up and the like to the tabs
+                let modifiers = state & gtk::accelerator_get_default_mod_mask();
+                let Some(nudge) = Nudge::from_key(key).filter(|_| modifiers.is_empty()) else {
+                    return glib::Propagation::Proceed;
+                };
+                if this.edited().kind == GradientType::Radial {
+                    return glib::Propagation::Proceed;
+                }
+
+                let angle = this.imp().angle_row.value();
+                let angle = match nudge {
+                    Nudge::Steps(steps) => angle + steps,
+                    Nudge::Start => 0.0,
+                    Nudge::End => 359.0,
+                };
+                this.turn_preview(angle, angle, Precision::Normal);
+                glib::Propagation::Stop
+            }),
+        );
+        imp.gradient_box.add_controller(key_controller);
+
+        //two finger gestures go to the overlay, which isn't zoomed itself
+        let rotate_gesture = gtk::GestureRotate::new();
+        rotate_gesture.connect_begin(
+            clone!(@weak self as this, @weak drag_gesture => move |gesture, _| {
+                //the zoom is grouped with the rotation, so this claims both
+                drag_gesture.set_state(gtk::EventSequenceState::Denied);
+                gesture.set_state(gtk::EventSequenceState::Claimed);
+                this.imp().drag_angle.set((0.0, this.edited().angle as f64));
+            }),
+        );
+        rotate_gesture.connect_angle_changed(
+            clone!(@weak self as this => move |gesture, _, delta| {
+                if this.edited().kind == GradientType::Radial {
+                    return;
+                }
+
+                let rotation = delta.to_degrees();
+                let precision = Precision::from_state(gesture.current_event_state());
+                let (last, angle) = this.imp().drag_angle.get();
+                this.turn_preview(rotation, angle + precision.scale(rotation - last), precision);
+            }),
+        );
+        imp.gradient_overlay.add_controller(rotate_gesture.clone());
+
+        //the point between the fingers stays under them, so moving them pans
+        let zoom_gesture = gtk::GestureZoom::new();
+        zoom_gesture.connect_begin(clone!(@weak self as this => move |gesture, _| {
+            let imp = this.imp();
+            let Some((x, y)) = gesture.bounding_box_center() else {
+                return;
+            };
+
+            let zoom = imp.preview_zoom.get();
+            let (x, y) = zoom.unmap(x, y);
+            imp.pinch.set((zoom.scale, x, y));
+        }));
+        zoom_gesture.connect_scale_changed(clone!(@weak self as this => move |gesture, scale| {
+            let imp = this.imp();
+            let Some(center) = gesture.bounding_box_center() else {
+                return;
+            };
+
+            let (start, x, y) = imp.pinch.get();
+            this.set_preview_zoom(Zoom::around(
+                start * scale,
+                (x, y),
+                center,
+                imp.gradient_overlay.width() as f64,
+                imp.gradient_overlay.height() as f64,
+            ));
+        }));
+        imp.gradient_overlay.add_controller(zoom_gesture.clone());
+        zoom_gesture.group_with(&rotate_gesture);
+    }
+
+    /// The handle of the preview under a point of it, reaching as far on
+    /// screen whatever the zoom.
+    fn handle_at(&self, x: f64, y: f64) -> Option<Handle> {
+        let imp = self.imp();
+        let handles = handles::layout(
+            &self.edited(),
+            imp.gradient_box.width() as f32,
+            imp.gradient_box.height() as f32,
+        );
+        let reach = handles::REACH / imp.preview_zoom.get().scale as f32;
+        handles::handle_at(&handles, &graphene::Point::new(x as f32, y as f32), reach)
+    }
+
+    /// Moves a stop to the offset of the gradient at a point of the preview.
+    fn drag_stop(&self, index: usize, x: f64, y: f64, precision: Precision) {
+        let gradient_box = &self.imp().gradient_box;
+        let bounds = graphene::Rect::new(
+            0.0,
+            0.0,
+            gradient_box.width().max(1) as f32,
+            gradient_box.height().max(1) as f32,
+        );
+        let position = {
+            let gradient = self.edited();
+            let offset =
+                gradient.offset_at_point(&graphene::Point::new(x as f32, y as f32), &bounds);
+            gradient.stored_offset(offset)
+        };
+        let position = precision.apply(position as f64, precision::POSITION_SNAP as f64) as f32;
+
+        self.edit_gradient(Edit::StopPosition(index), |gradient| {
+            gradient.stops[index].position = Some(position.clamp(0.0, 1.0));
+        });
+        self.sync_widgets();
+    }
+
+    fn set_preview_zoom(&self, zoom: Zoom) {
+        let imp = self.imp();
+        imp.preview_zoom.set(zoom);
+        imp.handle_layer.set_zoom(zoom);
+        self.queue_preview_update();
+    }
+
+    /// The angle pointing from the middle of the preview toward a point of
+    /// it, if the gradient has a direction.
+    fn preview_angle_at(&self, x: f64, y: f64) -> Option<f64> {
+        let imp = self.imp();
+        if self.edited().kind == GradientType::Radial {
+            return None;
+        }
+
+        let dx = x - imp.gradient_box.width() as f64 / 2.0;
+        let dy = y - imp.gradient_box.height() as f64 / 2.0;
+        if dx.hypot(dy) < 1.0 {
+            return Some(self.edited().angle as f64);
+        }
+
+        //CSS angles start at the top and go clockwise
+        Some(dx.atan2(-dy).to_degrees())
+    }
+
+    /// Sets the angle while dragging or rotating the preview, remembering
+    /// where the gesture is for the next update.
+    fn turn_preview(&self, gesture_angle: f64, angle: f64, precision: Precision) {
+        let imp = self.imp();
+
+        imp.drag_angle.set((gesture_angle, angle));
+        let angle = precision.apply(angle, precision::ANGLE_SNAP);
+        imp.angle_row.set_value(angle.round().rem_euclid(360.0));
+    }
+
+    /// Offers the usual gradient editor actions for the point of the preview
+    /// under the pointer: inserting a stop there, and removing or placing
+    /// the closest one.
+    fn popup_preview_menu(&self, x: f64, y: f64) {
+        let gradient_box = self.imp().gradient_box.get();
+        let gradient = self.edited().clone();
+        let (rgba, offset) = self.color_at_preview(x, y);
+        let Some(nearest) = gradient.nearest_stop(offset) else {
+            return;
+        };
+
+        let menu = gio::Menu::new();
+        let insert = gio::MenuItem::new(Some(&gettext("_Insert Color Here")), None);
+        insert.set_action_and_target_value(
+            Some("win.insert-stop"),
+            Some(&(offset as f64).to_variant()),
+        );
+        menu.append_item(&insert);
+
+        let copy = gio::Menu::new();
+        for format in ColorFormat::ALL {
+            let text = format.format(&rgba);
+            let item = gio::MenuItem::new(Some(&text), None);
+            item.set_action_and_target_value(Some("win.copy-color"), Some(&text.to_variant()));
+            copy.append_item(&item);
+        }
+        menu.append_submenu(Some(&gettext("_Copy Color Here")), &copy);
+
+        let section = gio::Menu::new();
+        let label =
+            pgettext("Color in a list", "Color {}").replace("{}", &(nearest + 1).to_string());
+        let position = gio::MenuItem::new(
+            Some(&gettext("Edit Position of {}…").replace("{}", &label)),
+            None,
+        );
+        position.set_action_and_target_value(
+            Some("win.edit-stop-position"),
+            Some(&(nearest as u32).to_variant()),
+        );
+        section.append_item(&position);
+        //a gradient needs two colors
+        if gradient.stops.len() > 2 {
+            let remove =
+                gio::MenuItem::new(Some(&gettext("Remove {}").replace("{}", &label)), None);
+            remove.set_action_and_target_value(
+                Some("win.remove-stop"),
+                Some(&(nearest as u32).to_variant()),
+            );
+            section.append_item(&remove);
+        }
+        menu.append_section(None, &section);
+
+        popup_menu(&gradient_box, &menu, x, y);
+    }
+
+    /// The color of the gradient at a point of the preview, computed from
+    /// the model with the adjustments applied, and its offset along the
+    /// gradient line.
+    fn color_at_preview(&self, x: f64, y: f64) -> (gdk::RGBA, f32) {
+        let gradient_box = &self.imp().gradient_box;
+        let bounds = graphene::Rect::new(
+            0.0,
+            0.0,
+            gradient_box.width().max(1) as f32,
+            gradient_box.height().max(1) as f32,
+        );
+        let point = graphene::Point::new(x as f32, y as f32);
+
+        let gradient = self.gradient();
+        (
+            gradient.color_at_point(&point, &bounds),
+            gradient.offset_at_point(&point, &bounds),
+        )
+    }
+}
@@ -0,0 +1,497 @@
+/* saved.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gettextrs::{gettext, ngettext};
+use glib::clone;
+
+use gtk::prelude::*;
+use gtk::{gio, glib};
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use crate::document;
+use crate::library;
+use crate::thumbnail;
+use crate::tokens;
+
+use super::VibrantWindow;
+
+impl VibrantWindow {
+    /// A gradient of the library looking the same as the edited one, left
+    /// out when it is open in a tab.
+    pub(super) async fn similar_saved_gradient(&self) -> Option<library::Entry> {
+        let imp = self.imp();
+        if !imp.collection.borrow().members.is_empty() {
+            return None;
+        }
+
+        let mut entries = library::load().await;
+        entries.retain(|entry| self.tab_for_file(&entry.file).is_none());
+        let index = {
+            let similar = library::find_similar(&entries, &imp.gradient.borrow())?;
+            entries
+                .iter()
+                .position(|entry| std::ptr::eq(entry, similar))?
+        };
+        Some(entries.swap_remove(index))
+    }
+
+    pub(super) async fn confirm_update_similar(&self, file: &gio::File) -> glib::GString {
+        let dialog = adw::MessageDialog::builder()
+            .transient_for(self)
+            .modal(true)
+            .heading(gettext("Similar Gradient Saved"))
+            .body(
+                gettext("“{}” looks the same as this gradient. Update it instead of saving another copy?")
+                    .replace("{}", &document::display_name(file)),
+            )
+            .default_response("update")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[
+            ("cancel", &gettext("_Cancel")),
+            ("copy", &gettext("Save _Copy")),
+            ("update", &gettext("_Update")),
+        ]);
+        dialog.set_response_appearance("update", adw::ResponseAppearance::Suggested);
+
+        dialog.choose_future().await
+    }
+
+    /// Moves the saved gradients looking the same as a more recently used
+    /// one to the trash, after asking.
+    pub(super) fn remove_duplicates(&self) {
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let entries = library::load().await;
+            let duplicates: Vec<gio::File> = library::duplicates(&entries)
+                .into_iter()
+                .filter(|entry| this.tab_for_file(&entry.file).is_none())
+                .map(|entry| entry.file.clone())
+                .collect();
+            if duplicates.is_empty() {
+                this.add_toast(adw::Toast::new(&gettext("No duplicate gradients")));
+                return;
+            }
+
+            let count = duplicates.len() as u32;
+            let dialog = adw::MessageDialog::builder()
+                .transient_for(&this)
+                .modal(true)
+                .heading(gettext("Remove Duplicates?"))
+                .body(
+                    ngettext(
+                        "{} saved gradient looks the same as one used more recently",
+                        "{} saved gradients look the same as ones used more recently",
+                        count,
+                    )
+                    .replace("{}", &count.to_string()),
+                )
+                .default_response("cancel")
+                .close_response("cancel")
+                .build();
+            dialog.add_responses(&[
+                ("cancel", &gettext("_Cancel")),
+                ("remove", &gettext("_Remove")),
+            ]);
+            dialog.set_response_appearance("remove", adw::ResponseAppearance::Destructive);
+            if dialog.choose_future().await != "remove" {
+                return;
+            }
+
+            this.trash_documents(&duplicates);
+        }));
+    }
+
+    /// Moves saved gradients to the trash of the library, with a toast to
+    /// put them back.
+    pub(super) fn trash_documents(&self, files: &[gio::File]) {
+        let mut trashed = Vec::new();
+        for file in files {
+            //its tab would save it again
+            if self.tab_for_file(file).is_some() {
+                self.add_toast(adw::Toast::new(
+                    &gettext("Close “{}” before deleting it")
+                        .replace("{}", &document::display_name(file)),
+                ));
+                continue;
+            }
+
+            match library::trash(file) {
+                Ok(entry) => trashed.push(entry),
+                Err(err) => {
+                    glib::g_warning!("vibrant", "Could not move gradient to the trash: {}", err);
+                    self.add_toast(adw::Toast::new(&gettext(
+                        "Could not move gradient to the trash",
+                    )));
+                }
+            }
+        }
+        self.update_recent_documents();
+
+        let title = match trashed.as_slice() {
+            [] => return,
+            [entry] => gettext("“{}” moved to the trash").replace("{}", &entry.display_name()),
+            _ => ngettext(
+                "{} gradient moved to the trash",
+                "{} gradients moved to the trash",
+                trashed.len() as u32,
+            )
+            .replace("{}", &trashed.len().to_string()),
+        };
+        let toast = adw::Toast::builder()
+            .title(title)
+            .button_label(gettext("_Undo"))
+            .build();
+        toast.connect_button_clicked(clone!(@weak self as this => move |_| {
+            this.restore_trashed(&trashed);
+        }));
+        self.add_toast(toast);
+    }
+
+    pub(super) fn remember_in_library(&self, file: &gio::File) {
+        if let Err(err) = library::remember(file) {
+            glib::g_warning!("vibrant", "Could not add gradient to the library: {}", err);
+            self.add_toast(adw::Toast::new(&gettext(
+                "Could not add gradient to the library",
+            )));
+        }
+    }
+
+    fn restore_trashed(&self, trashed: &[library::Trashed]) {
+        for entry in trashed {
+            if let Err(err) = library::restore(entry) {
+                glib::g_warning!("vibrant", "Could not restore gradient: {}", err);
+                self.add_toast(adw::Toast::new(&gettext("Could not restore gradient")));
+            }
+        }
+        self.update_recent_documents();
+    }
+
+    /// Lists the deleted gradients, each of them with a button to restore
+    /// it, until they get purged.
+    pub(super) fn show_trash(&self) {
+        let trashed = match library::trash_contents() {
+            Ok(trashed) => trashed,
+            Err(err) => {
+                glib::g_warning!("vibrant", "Could not read the trash: {}", err);
+                self.add_toast(adw::Toast::new(&gettext("Could not read the trash")));
+                return;
+            }
+        };
+
+        let list = gtk::ListBox::builder()
+            .selection_mode(gtk::SelectionMode::None)
+            .css_classes(["boxed-list"])
+            .build();
+
+        let dialog = adw::MessageDialog::builder()
+            .transient_for(self)
+            .modal(true)
+            .heading(gettext("Trash"))
+            .close_response("close")
+            .build();
+        dialog.add_response("close", &gettext("_Close"));
+
+        if trashed.is_empty() {
+            dialog.set_body(&gettext("No deleted gradients"));
+        } else {
+            dialog.set_body(
+                &ngettext(
+                    "Deleted gradients are removed for good after {} day",
+                    "Deleted gradients are removed for good after {} days",
+                    library::TRASH_DAYS as u32,
+                )
+                .replace("{}", &library::TRASH_DAYS.to_string()),
+            );
+            dialog.set_extra_child(Some(&list));
+            dialog.add_response("empty", &gettext("_Empty Trash"));
+            dialog.set_response_appearance("empty", adw::ResponseAppearance::Destructive);
+        }
+
+        for entry in trashed {
+            let subtitle = glib::DateTime::from_unix_local(entry.deleted)
+                .and_then(|date| date.format("%x"))
+                .map(|date| gettext("Deleted on {}").replace("{}", &date))
+                .unwrap_or_default();
+            let row = adw::ActionRow::builder()
+                .title(entry.display_name())
+                .subtitle(subtitle)
+                .build();
+
+            let restore_button = gtk::Button::builder()
+                .label(gettext("_Restore"))
+                .use_underline(true)
+                .valign(gtk::Align::Center)
+                .build();
+            restore_button.connect_clicked(
+                clone!(@weak self as this, @weak list, @weak row => move |_| {
+                    this.restore_trashed(std::slice::from_ref(&entry));
+                    list.remove(&row);
+                }),
+            );
+            row.add_suffix(&restore_button);
+
+            list.append(&row);
+        }
+
+        dialog.connect_response(
+            None,
+            clone!(@weak self as this => move |_, response| {
+                if response == "empty" {
+                    if let Err(err) = library::purge(true) {
+                        glib::g_warning!("vibrant", "Could not empty the trash: {}", err);
+                        this.add_toast(adw::Toast::new(&gettext("Could not empty the trash")));
+                    }
+                }
+            }),
+        );
+        dialog.present();
+    }
+
+    /// Lists the saved gradients on the start page, in the order chosen in
+    /// the settings.
+    pub(super) fn update_recent_documents(&self) {
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let mut entries = library::load().await;
+            let order = library::SortOrder::from_name(&this.settings().string("library-sort"))
+                .unwrap_or(library::SortOrder::LastUsed);
+            library::sort(&mut entries, order);
+
+            this.show_library(entries);
+        }));
+    }
+
+    fn show_library(&self, entries: Vec<library::Entry>) {
+        let imp = self.imp();
+
+        for row in imp.recent_document_rows.take() {
+            imp.recent_documents_group.remove(&row);
+        }
+
+        let selecting = self.is_selecting_saved();
+        let mut checks = Vec::new();
+        let mut rows = Vec::new();
+        for entry in entries {
+            let file = entry.file;
+
+            let tags = &entry.document.metadata.tags;
+            let subtitle = if tags.is_empty() {
+                file.parse_name().to_string()
+            } else {
+                format!("{} · {}", tags.join(", "), file.parse_name())
+            };
+            let row = adw::ActionRow::builder()
+                .title(glib::markup_escape_text(&document::display_name(&file)))
+                .subtitle(glib::markup_escape_text(&subtitle))
+                .activatable(true)
+                .build();
+
+            let check = gtk::CheckButton::builder()
+                .valign(gtk::Align::Center)
+                .visible(selecting)
+                .build();
+            check.connect_toggled(clone!(@weak self as this => move |_| {
+                this.update_saved_selection();
+            }));
+            row.add_prefix(&check);
+            checks.push((file.clone(), check.clone()));
+
+            let trash_button = gtk::Button::builder()
+                .icon_name("user-trash-symbolic")
+                .tooltip_text(gettext("Move to Trash"))
+                .valign(gtk::Align::Center)
+                .css_classes(["flat"])
+                .build();
+            trash_button.connect_clicked(clone!(@weak self as this, @strong file => move |_| {
+                this.trash_documents(std::slice::from_ref(&file));
+            }));
+            row.add_suffix(&trash_button);
+            row.add_suffix(&gtk::Image::from_icon_name("go-next-symbolic"));
+
+            let thumbnail = gtk::Picture::builder()
+                .width_request(thumbnail::SIZE)
+                .height_request(thumbnail::SIZE)
+                .valign(gtk::Align::Center)
+                .overflow(gtk::Overflow::Hidden)
+                .css_classes(["thumbnail"])
+                .build();
+            let texture = thumbnail::load(&entry.document.gradient, self.scale_factor());
+            thumbnail.set_paintable(texture.as_ref());
+            row.add_prefix(&thumbnail);
+
+            row.connect_activated(clone!(@weak self as this => move |_| {
+                if this.is_selecting_saved() {
+                    check.set_active(!check.is_active());
+                } else {
+                    this.open_file(&file);
+                }
+            }));
+
+            imp.recent_documents_group.add(&row);
+            rows.push(row);
+        }
+
+        imp.recent_documents_group.set_visible(!rows.is_empty());
+        imp.recent_document_rows.replace(rows);
+        imp.saved_checks.replace(checks);
+        self.update_saved_selection();
+    }
+
+    fn is_selecting_saved(&self) -> bool {
+        self.action_state("select-saved")
+            .and_then(|state| state.get::<bool>())
+            .unwrap_or_default()
+    }
+
+    /// Shows check buttons on the saved gradients, to act on several of
+    /// them at once.
+    pub(super) fn set_selecting_saved(&self, selecting: bool) {
+        let imp = self.imp();
+
+        self.change_action_state("select-saved", &selecting.to_variant());
+        for (_, check) in imp.saved_checks.borrow().iter() {
+            check.set_active(false);
+            check.set_visible(selecting);
+        }
+        imp.selection_bar.set_visible(selecting);
+        self.update_saved_selection();
+    }
+
+    pub(super) fn selected_saved(&self) -> Vec<gio::File> {
+        self.imp()
+            .saved_checks
+            .borrow()
+            .iter()
+            .filter(|(_, check)| check.is_active())
+            .map(|(file, _)| file.clone())
+            .collect()
+    }
+
+    fn update_saved_selection(&self) {
+        let count = self.selected_saved().len() as u32;
+
+        self.imp().selection_label.set_label(&if count == 0 {
+            gettext("No gradients selected")
+        } else {
+            ngettext("{} gradient selected", "{} gradients selected", count)
+                .replace("{}", &count.to_string())
+        });
+        for action in ["tag-selected", "export-selected", "trash-selected"] {
+            self.set_action_enabled(action, count > 0);
+        }
+    }
+
+    /// Asks for a tag and adds it to every selected gradient.
+    pub(super) fn tag_selected(&self) {
+        let files = self.selected_saved();
+
+        let entry = gtk::Entry::builder()
+            .placeholder_text(gettext("Tag"))
+            .activates_default(true)
+            .build();
+
+        let dialog = adw::MessageDialog::builder()
+            .transient_for(self)
+            .modal(true)
+            .heading(gettext("Add Tag"))
+            .extra_child(&entry)
+            .default_response("add")
+            .close_response("cancel")
+            .build();
+        dialog.add_responses(&[("cancel", &gettext("_Cancel")), ("add", &gettext("_Add"))]);
+        dialog.set_response_appearance("add", adw::ResponseAppearance::Suggested);
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let response = dialog.choose_future().await;
+            let tag = entry.text().trim().to_owned();
+            if response != "add" || tag.is_empty() {
+                return;
+            }
+
+            for file in files {
+                //its tab would save it again without the tag
+                if this.tab_for_file(&file).is_some() {
+                    this.add_toast(adw::Toast::new(
+                        &gettext("Close “{}” before tagging it")
+                            .replace("{}", &document::display_name(&file)),
+                    ));
+                    continue;
+                }
+
+                let result = match document::load(&file).await {
+                    Ok(mut document) => {
+                        if !document.metadata.tags.contains(&tag) {
+                            document.metadata.tags.push(tag.clone());
+                        }
+                        document::save(&file, &mut document).await
+                    }
+                    Err(err) => Err(err),
+                };
+                if let Err(err) = result {
+                    glib::g_warning!("vibrant", "Could not tag gradient: {}", err);
+                    this.add_toast(adw::Toast::new(&gettext("Could not tag gradient")));
+                }
+            }
+
+            this.set_selecting_saved(false);
+            this.update_recent_documents();
+        }));
+    }
+
+    /// Exports the selected gradients together as design tokens, with every
+    /// gradient of the collections among them.
+    pub(super) fn export_selected(&self) {
+        let files = self.selected_saved();
+
+        glib::MainContext::default().spawn_local(clone!(@weak self as this => async move {
+            let mut gradients = Vec::new();
+            for file in files {
+                let document = match document::load(&file).await {
+                    Ok(document) => document,
+                    Err(err) => {
+                        glib::g_warning!("vibrant", "Could not read gradient: {}", err);
+                        continue;
+                    }
+                };
+
+                if document.collection.members.is_empty() {
+                    gradients.push((document::display_name(&file), document.gradient.adjusted()));
+                } else {
+                    gradients.extend(
+                        document
+                            .collection
+                            .members
+                            .into_iter()
+                            .map(|member| (member.name, member.gradient.adjusted())),
+                    );
+                }
+            }
+
+            this.set_selecting_saved(false);
+            this.save_text(
+                &gettext("Export Design Tokens"),
+                &gettext("JSON Files"),
+                "json",
+                tokens::design_tokens(&gradients),
+            );
+        }));
+    }
+}
@@ -0,0 +1,105 @@
+/* statistics.rs
+ *
+ * Copyright 2023 Felipe Kinoshita
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ *
+ * SPDX-License-Identifier: GPL-3.0-or-later
+ */
+
+use gettextrs::{gettext, ngettext};
+
+use gtk::prelude::*;
+
+use adw::prelude::*;
+use adw::subclass::prelude::*;
+
+use crate::chart::Chart;
+use crate::chart_dialog::VibrantChartDialog;
+use crate::color;
+use crate::gradient::Gradient;
+use crate::stats::Stats;
+
+use super::VibrantWindow;
+
+impl VibrantWindow {
+    pub(super) fn update_stats(&self, gradient: &Gradient) {
+        let imp = self.imp();
+        let stats = Stats::new(gradient);
+
+        imp.average_luminance_label
+            .set_label(&format!("{:.0}%", stats.average_luminance * 100.0));
+        imp.end_contrast_label
+            .set_label(&format!("{:.2}:1", stats.end_contrast));
+
+        //in hundredths of OKLab units, the usual scale for delta E
+        let differences: Vec<String> = stats
+            .differences
+            .iter()
+            .map(|difference| format!("{:.0}", difference * 100.0))
+            .collect();
+        imp.stop_differences_row
+            .set_subtitle(&differences.join(" · "));
+
+        imp.lightness_plot.set_samples(stats.samples.clone());
+        imp.uniformity_label
+            .set_label(&format!("{:.0}%", stats.uniformity * 100.0));
+        imp.lightness_turns_row
+            .set_visible(stats.lightness_turns > 0);
+        imp.lightness_turns_row.set_subtitle(
+            &ngettext(
+                "Lightness turns {} time, which makes it hard to read as a colormap",
+                "Lightness turns {} times, which makes it hard to read as a colormap",
+                stats.lightness_turns as u32,
+            )
+            .replace("{}", &stats.lightness_turns.to_string()),
+        );
+
+        imp.muddy_row.set_visible(!stats.muddy.is_empty());
+        let pairs: Vec<String> = stats
+            .muddy
+            .iter()
+            .map(|&i| {
+                gettext("{first} to {second} blends through gray")
+                    .replace("{first}", &color::to_hex(&gradient.stops[i].color))
+                    .replace("{second}", &color::to_hex(&gradient.stops[i + 1].color))
+            })
+            .collect();
+        imp.muddy_row.set_subtitle(&pairs.join("\n"));
+
+        imp.unprintable_row
+            .set_visible(!stats.unprintable.is_empty());
+        let colors: Vec<String> = stats
+            .unprintable
+            .iter()
+            .map(|&i| color::to_hex(&gradient.stops[i].color))
+            .collect();
+        imp.unprintable_row.set_subtitle(
+            &gettext("{} will look duller on paper").replace("{}", &colors.join(", ")),
+        );
+
+        imp.banding_row.set_visible(stats.bands());
+        imp.banding_row.set_subtitle(
+            &gettext(
+                "Bands up to {} pixels wide on 8-bit displays at 4K, add grain to dither them",
+            )
+            .replace("{}", &format!("{:.0}", stats.widest_band)),
+        );
+    }
+
+    pub(super) fn show_pie_chart(&self) {
+        let colors = self.gradient().stops.into_iter().map(|stop| stop.color);
+        VibrantChartDialog::new(self, Chart::from_colors(colors)).present();
+    }
+}